@@ -1,9 +1,12 @@
 pub mod attestation;
+pub mod block;
 pub mod seal;
 pub mod pccs;
 
 use anyhow::Result;
 
+use crate::config::ChainConfig;
+
 use alloy::{
     network::{EthereumWallet, TransactionBuilder},
     primitives::{Address, Bytes},
@@ -88,3 +91,19 @@ pub fn get_evm_address_from_key(key: &str) -> String {
     let address = secret_key_to_address(&signing_key);
     address.to_checksum(None)
 }
+
+/// Fails if the RPC endpoint serves a different chain than the one the profile expects.
+pub async fn check_chain_id(config: &ChainConfig) -> Result<()> {
+    if let Some(expected) = config.chain_id {
+        let rpc_url = config.rpc_url.parse()?;
+        let provider = ProviderBuilder::new().on_http(rpc_url);
+        let chain_id = provider.get_chain_id().await?;
+        if chain_id != expected {
+            return Err(anyhow::Error::msg(format!(
+                "RPC endpoint {} serves chain ID {}, expected {}",
+                config.rpc_url, chain_id, expected
+            )));
+        }
+    }
+    Ok(())
+}
@@ -1,3 +1,52 @@
-pub mod pcs;
 pub mod enclave_id;
-pub mod fmspc_tcb;
\ No newline at end of file
+pub mod fmspc_tcb;
+pub mod multicall;
+pub mod pck;
+pub mod pcs;
+
+use anyhow::Result;
+
+use crate::config::ChainConfig;
+
+use alloy::{
+    eips::BlockId,
+    primitives::{Address, B256},
+    providers::{ProviderBuilder, RootProvider},
+    sol_types::SolValue,
+    transports::http::{Client, Http},
+};
+
+pub type HttpProvider = RootProvider<Http<Client>>;
+
+/// Connection to the on-chain PCCS, built once and shared by every DAO read.
+/// Every read is issued at `block`.
+pub struct PccsClient {
+    pub provider: HttpProvider,
+    pub pcs_dao: Address,
+    pub fmspc_tcb_dao: Address,
+    pub enclave_id_dao: Address,
+    pub pck_dao: Address,
+    pub multicall3: Address,
+    pub block: BlockId,
+}
+
+impl PccsClient {
+    pub fn new(config: &ChainConfig, block: BlockId) -> Result<Self> {
+        let rpc_url = config.rpc_url.parse()?;
+
+        Ok(PccsClient {
+            provider: ProviderBuilder::new().on_http(rpc_url),
+            pcs_dao: config.pcs_dao.parse()?,
+            fmspc_tcb_dao: config.fmspc_tcb_dao.parse()?,
+            enclave_id_dao: config.enclave_id_dao.parse()?,
+            pck_dao: config.pck_dao.parse()?,
+            multicall3: config.multicall3.parse()?,
+            block,
+        })
+    }
+}
+
+/// Decodes the attestation ID an upsert to a PCCS DAO returns
+pub fn decode_attestation_id(ret: &[u8]) -> Result<B256> {
+    Ok(B256::abi_decode(ret, true)?)
+}
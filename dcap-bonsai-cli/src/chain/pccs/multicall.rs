@@ -0,0 +1,153 @@
+use anyhow::Result;
+
+use super::enclave_id::{
+    enclave_id_type_uint256, enclave_identity_to_json, EnclaveIdType, IEnclaveIdentityDao,
+};
+use super::fmspc_tcb::{tcb_info_to_json, IFmspcTcbDao};
+use super::pcs::IPCSDao::{self, CA};
+use super::PccsClient;
+
+use alloy::{
+    primitives::{Address, Bytes, U256},
+    providers::Provider,
+    sol,
+    sol_types::SolCall,
+};
+
+sol! {
+    #[sol(rpc)]
+    interface IMulticall3 {
+        #[derive(Debug)]
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        // Named `Result` in Multicall3.sol, renamed so it does not shadow `anyhow::Result`
+        #[derive(Debug)]
+        struct Call3Result {
+            bool success;
+            bytes returnData;
+        }
+
+        #[derive(Debug)]
+        function aggregate3(Call3[] calldata calls) external payable returns (Call3Result[] memory returnData);
+    }
+}
+
+/// Everything read by [`get_collaterals`], taken from the same block.
+#[derive(Debug)]
+pub struct PccsReads {
+    /// Intel SGX Root CA and its CRL
+    pub root_ca: (Vec<u8>, Vec<u8>),
+    /// Intel TCB Signing CA, the DAO stores no CRL for it
+    pub signing_ca: Vec<u8>,
+    pub pck_crl: Vec<u8>,
+    pub tcb_info: Vec<u8>,
+    pub qe_identity: Vec<u8>,
+}
+
+/// Returns whether there is a contract at the configured Multicall3 address
+pub async fn is_multicall3_deployed(client: &PccsClient) -> Result<bool> {
+    let code = client
+        .provider
+        .get_code_at(client.multicall3)
+        .block_id(client.block)
+        .await?;
+    Ok(!code.is_empty())
+}
+
+/// Reads the certificates, CRLs, TCBInfo and QEIdentity from the PCCS DAOs
+/// with a single Multicall3 `aggregate3` call.
+pub async fn get_collaterals(
+    client: &PccsClient,
+    pck_ca: CA,
+    tcb_type: u8,
+    fmspc: &str,
+    tcb_version: u32,
+    qe_id_type: EnclaveIdType,
+    qe_id_version: u32,
+) -> Result<PccsReads> {
+    let certificate_call = |ca: CA| IPCSDao::getCertificateByIdCall { ca }.abi_encode();
+    let calls = vec![
+        call3(client.pcs_dao, certificate_call(CA::ROOT)),
+        call3(client.pcs_dao, certificate_call(CA::SIGNING)),
+        call3(client.pcs_dao, certificate_call(pck_ca)),
+        call3(
+            client.fmspc_tcb_dao,
+            IFmspcTcbDao::getTcbInfoCall {
+                tcbType: U256::from(tcb_type),
+                fmspc: String::from(fmspc),
+                version: U256::from(tcb_version),
+            }
+            .abi_encode(),
+        ),
+        call3(
+            client.enclave_id_dao,
+            IEnclaveIdentityDao::getEnclaveIdentityCall {
+                id: enclave_id_type_uint256(qe_id_type),
+                version: U256::from(qe_id_version),
+            }
+            .abi_encode(),
+        ),
+    ];
+
+    let multicall = IMulticall3::new(client.multicall3, &client.provider);
+    let results = multicall
+        .aggregate3(calls)
+        .block(client.block)
+        .call()
+        .await?
+        .returnData;
+    if results.len() != 5 {
+        return Err(anyhow::Error::msg(format!(
+            "Multicall3 returned {} results for 5 calls",
+            results.len()
+        )));
+    }
+
+    let root = IPCSDao::getCertificateByIdCall::abi_decode_returns(
+        return_data(&results[0], "getCertificateById(ROOT)")?,
+        true,
+    )?;
+    let signing = IPCSDao::getCertificateByIdCall::abi_decode_returns(
+        return_data(&results[1], "getCertificateById(SIGNING)")?,
+        true,
+    )?;
+    let pck = IPCSDao::getCertificateByIdCall::abi_decode_returns(
+        return_data(&results[2], "getCertificateById")?,
+        true,
+    )?;
+    let tcb_info = IFmspcTcbDao::getTcbInfoCall::abi_decode_returns(
+        return_data(&results[3], "getTcbInfo")?,
+        true,
+    )?;
+    let qe_identity = IEnclaveIdentityDao::getEnclaveIdentityCall::abi_decode_returns(
+        return_data(&results[4], "getEnclaveIdentity")?,
+        true,
+    )?;
+
+    Ok(PccsReads {
+        root_ca: (root.cert.to_vec(), root.crl.to_vec()),
+        signing_ca: signing.cert.to_vec(),
+        pck_crl: pck.crl.to_vec(),
+        tcb_info: tcb_info_to_json(tcb_info.tcbObj, fmspc, tcb_version)?,
+        qe_identity: enclave_identity_to_json(qe_identity.enclaveIdObj, qe_id_type, qe_id_version)?,
+    })
+}
+
+fn call3(target: Address, call_data: Vec<u8>) -> IMulticall3::Call3 {
+    IMulticall3::Call3 {
+        target,
+        allowFailure: true,
+        callData: Bytes::from(call_data),
+    }
+}
+
+fn return_data<'a>(result: &'a IMulticall3::Call3Result, call: &str) -> Result<&'a [u8]> {
+    if !result.success {
+        return Err(anyhow::Error::msg(format!("{} reverted", call)));
+    }
+    Ok(&result.returnData[..])
+}
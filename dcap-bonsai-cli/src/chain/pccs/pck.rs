@@ -0,0 +1,70 @@
+use anyhow::Result;
+
+use super::pcs::{get_certificate_by_id, IPCSDao::CA};
+use super::PccsClient;
+use crate::der_to_pem;
+use crate::parser::{get_pck_issuer, PckIdentifier};
+
+use alloy::sol;
+
+sol! {
+    #[sol(rpc)]
+    interface IPckDao {
+        #[derive(Debug)]
+        function getCert(string calldata qeid, string calldata platformCpuSvn, string calldata platformPceSvn, string calldata pceid) external view returns (bytes memory pckCert);
+    }
+}
+
+/// Returns the DER-encoded PCK certificate the PCK DAO holds for `id`.
+///
+/// The DAO is keyed by hex strings, with PCESVN and PCE ID little-endian as in the quote.
+pub async fn get_pck_cert(client: &PccsClient, id: &PckIdentifier) -> Result<Vec<u8>> {
+    let pck_dao_contract = IPckDao::new(client.pck_dao, &client.provider);
+
+    let qe_id = hex::encode(id.qe_id);
+    let cpu_svn = hex::encode(id.cpu_svn);
+    let pce_svn = hex::encode(id.pce_svn.to_le_bytes());
+    let pce_id = hex::encode(id.pce_id.to_le_bytes());
+
+    let call_builder = pck_dao_contract.getCert(
+        qe_id.clone(),
+        cpu_svn.clone(),
+        pce_svn.clone(),
+        pce_id.clone(),
+    );
+
+    let call_return = call_builder.block(client.block).call().await?;
+    let pck_cert = call_return.pckCert.to_vec();
+
+    if pck_cert.is_empty() {
+        return Err(anyhow::Error::msg(format!(
+            "PCK certificate for QE ID: {}; PCE ID: {}; CPUSVN: {}; PCESVN: {} is missing and must be upserted to on-chain pccs",
+            qe_id, pce_id, cpu_svn, pce_svn
+        )));
+    }
+
+    Ok(pck_cert)
+}
+
+/// Builds the PEM PCK certificate chain (PCK certificate, PCK CA, Root CA) for `id`,
+/// the same chain a quote with certification data type 5 embeds.
+pub async fn get_pck_cert_chain(client: &PccsClient, id: &PckIdentifier) -> Result<Vec<u8>> {
+    let pck_cert = get_pck_cert(client, id).await?;
+    let (pck_ca, _) = get_pck_issuer(&pck_cert)?;
+
+    let ((pck_ca_cert, _), (root_ca, _)) = tokio::try_join!(
+        get_certificate_by_id(client, pck_ca),
+        get_certificate_by_id(client, CA::ROOT)
+    )?;
+    if pck_ca_cert.is_empty() || root_ca.is_empty() {
+        return Err(anyhow::Error::msg(
+            "The PCK CA or the Intel SGX Root CA is missing from on-chain pccs",
+        ));
+    }
+
+    let chain = [pck_cert, pck_ca_cert, root_ca]
+        .iter()
+        .map(|der| der_to_pem(der, "CERTIFICATE"))
+        .collect::<String>();
+    Ok(chain.into_bytes())
+}
@@ -1,3 +1,8 @@
+use anyhow::Result;
+
+use crate::chain::pccs::pcs::IPCSDao::CA;
+use crate::pem_chain_to_der;
+
 #[derive(Debug)]
 pub struct Collaterals {
     pub tcb_info: Vec<u8>,
@@ -5,7 +10,9 @@ pub struct Collaterals {
     pub root_ca: Vec<u8>,
     pub tcb_signing_ca: Vec<u8>,
     pub root_ca_crl: Vec<u8>,
-    pub pck_crl: Vec<u8>
+    pub pck_crl: Vec<u8>,
+    /// PEM PCK certificate chain as read from the PCK DAO, only set when the quote does not embed it
+    pub pck_certchain: Vec<u8>
 }
 
 impl Collaterals {
@@ -23,7 +30,184 @@ impl Collaterals {
             root_ca,
             tcb_signing_ca,
             root_ca_crl,
-            pck_crl
+            pck_crl,
+            pck_certchain: Vec::new()
+        }
+    }
+
+    /// Returns the PCK certificate chain as concatenated DER certificates, leaf first,
+    /// or nothing when the quote embeds the chain
+    pub fn pck_certchain_der(&self) -> Result<Vec<u8>> {
+        Ok(pem_chain_to_der(&self.pck_certchain)?.concat())
+    }
+}
+
+// Modified from https://github.com/automata-network/dcap-rs/blob/b218a9dcdf2aec8ee05f4d2bd055116947ddfced/src/types/collaterals.rs#L35-L105
+pub fn serialize_collaterals(collaterals: &Collaterals, pck_type: CA) -> Result<Vec<u8>> {
+    // The guest expects the PCK certificate chain as concatenated DER certificates
+    let pck_certchain = collaterals.pck_certchain_der()?;
+
+    // get the total length
+    let total_length = 4 * 8
+        + collaterals.tcb_info.len()
+        + collaterals.qe_identity.len()
+        + collaterals.root_ca.len()
+        + collaterals.tcb_signing_ca.len()
+        + pck_certchain.len()
+        + collaterals.root_ca_crl.len()
+        + collaterals.pck_crl.len();
+
+    // create the vec and copy the data
+    let mut data = Vec::with_capacity(total_length);
+    data.extend_from_slice(&(collaterals.tcb_info.len() as u32).to_le_bytes());
+    data.extend_from_slice(&(collaterals.qe_identity.len() as u32).to_le_bytes());
+    data.extend_from_slice(&(collaterals.root_ca.len() as u32).to_le_bytes());
+    data.extend_from_slice(&(collaterals.tcb_signing_ca.len() as u32).to_le_bytes());
+    data.extend_from_slice(&(pck_certchain.len() as u32).to_le_bytes());
+    data.extend_from_slice(&(collaterals.root_ca_crl.len() as u32).to_le_bytes());
+
+    match pck_type {
+        CA::PLATFORM => {
+            data.extend_from_slice(&0u32.to_le_bytes());
+            data.extend_from_slice(&(collaterals.pck_crl.len() as u32).to_le_bytes());
+        }
+        CA::PROCESSOR => {
+            data.extend_from_slice(&(collaterals.pck_crl.len() as u32).to_le_bytes());
+            data.extend_from_slice(&0u32.to_le_bytes());
+        }
+        _ => {
+            return Err(anyhow::Error::msg(
+                "The PCK certificate must be issued by the Platform or Processor CA",
+            ))
+        }
+    }
+
+    // collateral should only hold one PCK CRL
+
+    data.extend_from_slice(&collaterals.tcb_info);
+    data.extend_from_slice(&collaterals.qe_identity);
+    data.extend_from_slice(&collaterals.root_ca);
+    data.extend_from_slice(&collaterals.tcb_signing_ca);
+    data.extend_from_slice(&pck_certchain);
+    data.extend_from_slice(&collaterals.root_ca_crl);
+    data.extend_from_slice(&collaterals.pck_crl);
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use x509_parser::prelude::*;
+
+    use super::*;
+    use crate::parser::{get_pck_certification_data, get_pck_fmspc_and_issuer, PckCertificationData};
+    use crate::quote::{
+        Quote, ENCLAVE_REPORT_SIZE, PPID_RSA3072_ENCRYPTED_CERT_DATA_TYPE, QUOTE_HEADER_SIZE,
+        TD10_REPORT_SIZE,
+    };
+
+    const QUOTE_HEX: &str = include_str!("../../data/quote.hex");
+
+    /// Swaps the PCK certificate chain embedded in the sample v4 TDX quote for RSA-3072 encrypted
+    /// PPID certification data, returns that quote and the chain it no longer carries
+    fn identifier_quote() -> (Vec<u8>, Vec<u8>) {
+        let quote = hex::decode(QUOTE_HEX.trim()).unwrap();
+        let parsed = Quote::from_bytes(&quote).unwrap();
+        let qe_auth_data_len = parsed
+            .signature
+            .cert_data
+            .qe_report_cert_data()
+            .unwrap()
+            .qe_auth_data
+            .len();
+        let pem_chain = match get_pck_certification_data(&parsed).unwrap() {
+            PckCertificationData::CertChain(chain) => chain,
+            PckCertificationData::Identifier(_) => panic!("The sample quote embeds its PCK chain"),
+        };
+
+        // The signature data follows its length, and starts with the quote signature and the
+        // attestation key. The QE report certification data then follows its type and size.
+        let signature_data_offset = QUOTE_HEADER_SIZE + TD10_REPORT_SIZE + 4;
+        let qe_report_cert_data_offset = signature_data_offset + 64 + 64 + 2 + 4;
+        let pck_cert_data_offset =
+            qe_report_cert_data_offset + ENCLAVE_REPORT_SIZE + 64 + 2 + qe_auth_data_len;
+
+        // Encrypted PPID, CPUSVN, PCESVN and PCE ID
+        let mut quote = quote[..pck_cert_data_offset].to_vec();
+        quote.extend_from_slice(&PPID_RSA3072_ENCRYPTED_CERT_DATA_TYPE.to_le_bytes());
+        quote.extend_from_slice(&(384u32 + 16 + 2 + 2).to_le_bytes());
+        quote.extend_from_slice(&[0xaa; 384]);
+        quote.extend_from_slice(&[0x01; 16]);
+        quote.extend_from_slice(&13u16.to_le_bytes());
+        quote.extend_from_slice(&0u16.to_le_bytes());
+
+        let qe_report_cert_data_size = (quote.len() - qe_report_cert_data_offset) as u32;
+        quote[qe_report_cert_data_offset - 4..qe_report_cert_data_offset]
+            .copy_from_slice(&qe_report_cert_data_size.to_le_bytes());
+        let signature_data_len = (quote.len() - signature_data_offset) as u32;
+        quote[signature_data_offset - 4..signature_data_offset]
+            .copy_from_slice(&signature_data_len.to_le_bytes());
+
+        (quote, pem_chain)
+    }
+
+    #[test]
+    fn serializes_the_pck_dao_chain_of_identifier_quotes_as_der() {
+        let (quote, pem_chain) = identifier_quote();
+        let parsed = Quote::from_bytes(&quote).unwrap();
+        match get_pck_certification_data(&parsed).unwrap() {
+            PckCertificationData::Identifier(id) => assert_eq!(id.pce_svn, 13),
+            PckCertificationData::CertChain(_) => panic!("The quote should carry a PCK identifier"),
         }
+
+        // The PCK DAO returns the chain the quote identifies in PEM
+        let (_, pck_type, _) = get_pck_fmspc_and_issuer(&pem_chain).unwrap();
+        let mut collaterals = Collaterals::new(
+            b"tcb_info".to_vec(),
+            b"qe_identity".to_vec(),
+            b"root_ca".to_vec(),
+            b"tcb_signing_ca".to_vec(),
+            b"root_ca_crl".to_vec(),
+            b"pck_crl".to_vec(),
+        );
+        collaterals.pck_certchain = pem_chain.clone();
+        let serialized = serialize_collaterals(&collaterals, pck_type).unwrap();
+
+        // Read the PCK certificate chain back the way IntelCollateral::from_bytes does
+        let lengths = serialized[..32]
+            .chunks(4)
+            .map(|len| u32::from_le_bytes(len.try_into().unwrap()) as usize)
+            .collect::<Vec<_>>();
+        assert_eq!(serialized.len(), 32 + lengths.iter().sum::<usize>());
+        let offset = 32 + lengths[..4].iter().sum::<usize>();
+        let mut pck_certchain = &serialized[offset..offset + lengths[4]];
+        assert_eq!(pck_certchain, pem_chain_to_der(&pem_chain).unwrap().concat());
+
+        let mut subjects = Vec::new();
+        while !pck_certchain.is_empty() {
+            let (rest, cert) = X509Certificate::from_der(pck_certchain).unwrap();
+            subjects.push(cert.subject().to_string());
+            pck_certchain = rest;
+        }
+        assert_eq!(subjects.len(), 3);
+        assert!(subjects[0].contains("Intel SGX PCK Certificate"));
+        assert!(subjects[2].contains("Intel SGX Root CA"));
+    }
+
+    #[test]
+    fn fails_for_pck_ca_types_other_than_platform_and_processor() {
+        let collaterals = Collaterals::new(
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        );
+        let err = serialize_collaterals(&collaterals, CA::ROOT).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The PCK certificate must be issued by the Platform or Processor CA"
+        );
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,204 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use crate::constants::*;
+
+/// Endpoints and contract addresses used by every chain-facing call.
+/// Addresses may be given with or without the `0x` prefix.
+#[derive(Debug, Clone)]
+pub struct ChainConfig {
+    pub rpc_url: String,
+    pub chain_id: Option<u64>,
+    pub dcap_contract: String,
+    pub explorer_url: String,
+    pub pcs_dao: String,
+    pub fmspc_tcb_dao: String,
+    pub enclave_id_dao: String,
+    pub pck_dao: String,
+    pub multicall3: String,
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        ChainConfig {
+            rpc_url: DEFAULT_RPC_URL.to_string(),
+            chain_id: Some(DEFAULT_CHAIN_ID),
+            dcap_contract: DEFAULT_DCAP_CONTRACT.to_string(),
+            explorer_url: DEFAULT_EXPLORER_URL.to_string(),
+            pcs_dao: PCS_DAO_ADDRESS.to_string(),
+            fmspc_tcb_dao: FMSPC_TCB_DAO_ADDRESS.to_string(),
+            enclave_id_dao: ENCLAVE_ID_DAO_ADDRESS.to_string(),
+            pck_dao: PCK_DAO_ADDRESS.to_string(),
+            multicall3: MULTICALL3_ADDRESS.to_string(),
+        }
+    }
+}
+
+/// A named network as written in the networks config file.
+/// Every field is optional so that a profile only needs to list what it changes,
+/// and so the same type can carry per-flag overrides.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkProfile {
+    pub rpc_url: Option<String>,
+    pub chain_id: Option<u64>,
+    pub dcap_contract: Option<String>,
+    pub explorer_url: Option<String>,
+    pub pcs_dao: Option<String>,
+    pub fmspc_tcb_dao: Option<String>,
+    pub enclave_id_dao: Option<String>,
+    pub pck_dao: Option<String>,
+    pub multicall3: Option<String>,
+}
+
+impl NetworkProfile {
+    /// Replaces every field of `self` that is set in `other`.
+    pub fn overlay(&mut self, other: &NetworkProfile) {
+        macro_rules! take {
+            ($($field:ident),*) => {
+                $(if other.$field.is_some() {
+                    self.$field = other.$field.clone();
+                })*
+            };
+        }
+        take!(
+            rpc_url,
+            chain_id,
+            dcap_contract,
+            explorer_url,
+            pcs_dao,
+            fmspc_tcb_dao,
+            enclave_id_dao,
+            pck_dao,
+            multicall3
+        );
+    }
+
+    pub fn into_chain_config(self, network: &str) -> Result<ChainConfig> {
+        let missing = |field: &str, flag: &str| {
+            anyhow::Error::msg(format!(
+                "Network \"{}\" does not define {}; set it in the networks config file or pass --{}",
+                network, field, flag
+            ))
+        };
+
+        Ok(ChainConfig {
+            rpc_url: self.rpc_url.ok_or_else(|| missing("rpc_url", "rpc-url"))?,
+            chain_id: self.chain_id,
+            dcap_contract: self
+                .dcap_contract
+                .ok_or_else(|| missing("dcap_contract", "dcap-contract"))?,
+            explorer_url: self.explorer_url.unwrap_or_default(),
+            pcs_dao: self.pcs_dao.ok_or_else(|| missing("pcs_dao", "pcs-dao"))?,
+            fmspc_tcb_dao: self
+                .fmspc_tcb_dao
+                .ok_or_else(|| missing("fmspc_tcb_dao", "fmspc-tcb-dao"))?,
+            enclave_id_dao: self
+                .enclave_id_dao
+                .ok_or_else(|| missing("enclave_id_dao", "enclave-id-dao"))?,
+            pck_dao: self.pck_dao.ok_or_else(|| missing("pck_dao", "pck-dao"))?,
+            multicall3: self
+                .multicall3
+                .unwrap_or_else(|| MULTICALL3_ADDRESS.to_string()),
+        })
+    }
+}
+
+impl From<ChainConfig> for NetworkProfile {
+    fn from(config: ChainConfig) -> Self {
+        NetworkProfile {
+            rpc_url: Some(config.rpc_url),
+            chain_id: config.chain_id,
+            dcap_contract: Some(config.dcap_contract),
+            explorer_url: Some(config.explorer_url),
+            pcs_dao: Some(config.pcs_dao),
+            fmspc_tcb_dao: Some(config.fmspc_tcb_dao),
+            enclave_id_dao: Some(config.enclave_id_dao),
+            pck_dao: Some(config.pck_dao),
+            multicall3: Some(config.multicall3),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NetworksFile {
+    #[serde(default)]
+    networks: BTreeMap<String, NetworkProfile>,
+}
+
+/// Profiles that are available without a config file.
+pub fn builtin_profiles() -> BTreeMap<String, NetworkProfile> {
+    let mut profiles = BTreeMap::new();
+    profiles.insert(
+        DEFAULT_NETWORK.to_string(),
+        NetworkProfile::from(ChainConfig::default()),
+    );
+    profiles.insert(
+        LOCAL_NETWORK.to_string(),
+        NetworkProfile {
+            rpc_url: Some(LOCAL_RPC_URL.to_string()),
+            chain_id: Some(LOCAL_CHAIN_ID),
+            ..Default::default()
+        },
+    );
+    profiles
+}
+
+/// Returns the built-in profiles overlaid with the ones defined in the config file.
+///
+/// When no path is given, the first of `DEFAULT_CONFIG_PATHS` that exists is used.
+/// The file format is picked from its extension: `.yaml`/`.yml` for YAML, TOML otherwise.
+pub fn load_profiles(path: Option<&Path>) -> Result<BTreeMap<String, NetworkProfile>> {
+    let mut profiles = builtin_profiles();
+
+    let path = match path {
+        Some(p) => Some(p.to_path_buf()),
+        _ => DEFAULT_CONFIG_PATHS
+            .iter()
+            .map(PathBuf::from)
+            .find(|p| p.exists()),
+    };
+
+    if let Some(path) = path {
+        let content = read_to_string(&path)
+            .with_context(|| format!("Failed to read networks config {}", path.display()))?;
+        let file: NetworksFile = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&content)?,
+            _ => toml::from_str(&content)?,
+        };
+        log::info!("Loaded networks config from {}", path.display());
+
+        for (name, profile) in file.networks {
+            profiles.entry(name).or_default().overlay(&profile);
+        }
+    }
+
+    Ok(profiles)
+}
+
+/// Resolves the configuration for `network`: per-flag `overrides` take precedence
+/// over the config file, which takes precedence over the built-in profiles.
+pub fn resolve_chain_config(
+    network: &str,
+    config_path: Option<&Path>,
+    overrides: &NetworkProfile,
+) -> Result<ChainConfig> {
+    let profiles = load_profiles(config_path)?;
+    let mut profile = match profiles.get(network) {
+        Some(profile) => profile.clone(),
+        _ => {
+            let known: Vec<&str> = profiles.keys().map(|k| k.as_str()).collect();
+            return Err(anyhow::Error::msg(format!(
+                "Unknown network \"{}\", available networks: {}",
+                network,
+                known.join(", ")
+            )));
+        }
+    };
+    profile.overlay(overrides);
+    profile.into_chain_config(network)
+}
@@ -7,8 +7,16 @@ pub const TDX_TEE_TYPE: u32 = 0x00000081;
 // Collateral Path Defaults
 pub const DEFAULT_QUOTE_PATH: &str = "../data/quote.hex";
 
+// Network Profile Defaults
+pub const DEFAULT_NETWORK: &str = "automata-testnet";
+pub const DEFAULT_CONFIG_PATHS: [&str; 3] = ["networks.toml", "networks.yaml", "networks.yml"];
+pub const LOCAL_NETWORK: &str = "local";
+pub const LOCAL_RPC_URL: &str = "http://127.0.0.1:8545";
+pub const LOCAL_CHAIN_ID: u64 = 31337;
+
 // Chain Defaults
 pub const DEFAULT_RPC_URL: &str = "https://1rpc.io/ata/testnet";
+pub const DEFAULT_CHAIN_ID: u64 = 1398243;
 pub const DEFAULT_DCAP_CONTRACT: &str = "6D67Ae70d99A4CcE500De44628BCB4DaCfc1A145";
 pub const DEFAULT_EXPLORER_URL: &str = "https://explorer-testnet.ata.network/tx";
 
@@ -16,4 +24,17 @@ pub const DEFAULT_EXPLORER_URL: &str = "https://explorer-testnet.ata.network/tx"
 pub const ENCLAVE_ID_DAO_ADDRESS: &str = "45f91C0d9Cf651785d93fcF7e9E97dE952CdB910";
 pub const FMSPC_TCB_DAO_ADDRESS: &str = "9c54C72867b07caF2e6255CE32983c28aFE40F26";
 pub const PCS_DAO_ADDRESS: &str = "cf171ACd6c0a776f9d3E1F6Cac8067c982Ac6Ce1";
-pub const PCK_DAO_ADDRESS: &str = "722525B96b62e182F8A095af0a79d4EA2037795C";
\ No newline at end of file
+pub const PCK_DAO_ADDRESS: &str = "722525B96b62e182F8A095af0a79d4EA2037795C";
+
+// Multicall3, deployed at the same address on most EVM chains
+pub const MULTICALL3_ADDRESS: &str = "cA11bde05977b3631167028862bE2a173976CA11";
+
+// Intel PCS
+pub const INTEL_PCS_URL: &str = "https://api.trustedservices.intel.com";
+// CRL distribution point of the Intel SGX Root CA, referenced by the PCK and TCB Signing CAs
+pub const INTEL_ROOT_CA_CRL_URL: &str =
+    "https://certificates.trustedservices.intel.com/IntelSGXRootCA.der";
+// Intel SGX Root CA, pinned so that a PCCS cannot swap the root of trust
+pub const INTEL_ROOT_CA_SHA256: &str =
+    "44a0196b2b99f889b8e149e95b807a350e7424964399e885a7cbb8ccfab674d3";
+pub const INTEL_ROOT_CA_PUBLIC_KEY: &str = "040ba9c4c0c0c86193a3fe23d6b02cda10a8bbd4e88e48b4458561a36e705525f567918e2edc88e40d860bd0cc4ee26aacc988e505a953558c453f6b0904ae7394";
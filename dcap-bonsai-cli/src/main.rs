@@ -1,5 +1,5 @@
-use anyhow::{Error, Result};
-use clap::{Args, Parser, Subcommand};
+use anyhow::{Context, Error, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use risc0_ethereum_contracts::groth16;
 use risc0_zkvm::{
     compute_image_id, default_prover, ExecutorEnv, InnerReceipt::Groth16, ProverOpts,
@@ -7,23 +7,60 @@ use risc0_zkvm::{
 use std::fs::read_to_string;
 use std::path::PathBuf;
 
+use dcap_bonsai_cli::bundle::CollateralBundle;
 use dcap_bonsai_cli::chain::{
     attestation::{decode_attestation_ret_data, generate_attestation_calldata},
-    get_evm_address_from_key,
+    block::{parse_block_id, resolve_block, PinnedBlock},
+    check_chain_id, get_evm_address_from_key,
     pccs::{
-        enclave_id::{get_enclave_identity, EnclaveIdType},
-        fmspc_tcb::get_tcb_info,
-        pcs::{get_certificate_by_id, IPCSDao::CA},
+        decode_attestation_id,
+        enclave_id::upsert_enclave_identity_calldata,
+        fmspc_tcb::upsert_tcb_info_calldata,
+        pck::{get_pck_cert, get_pck_cert_chain},
+        pcs::{
+            pck_crl_ca, upsert_certificate_calldata, upsert_pck_crl_calldata,
+            upsert_root_ca_crl_calldata, IPCSDao::CA,
+        },
+        PccsClient,
     },
     TxSender,
 };
 use dcap_bonsai_cli::code::DCAP_GUEST_ELF;
-use dcap_bonsai_cli::collaterals::Collaterals;
+use dcap_bonsai_cli::collaterals::{serialize_collaterals, Collaterals};
+use dcap_bonsai_cli::config::{resolve_chain_config, ChainConfig, NetworkProfile};
 use dcap_bonsai_cli::constants::*;
-use dcap_bonsai_cli::parser::get_pck_fmspc_and_issuer;
-use dcap_bonsai_cli::remove_prefix_if_found;
+use dcap_bonsai_cli::parser::{
+    get_pck_certification_data, get_pck_extensions, get_pck_fmspc_and_issuer, get_pck_issuer,
+    PckCertificationData,
+};
+use dcap_bonsai_cli::provider::{
+    ca_name,
+    cache::{now, CachedProvider, CollateralCache},
+    dir::DirProvider,
+    fallback::FallbackProvider,
+    fetch_collaterals,
+    http::PcsProvider,
+    onchain::OnChainProvider,
+    overrides::{CollateralFiles, OverrideProvider},
+    CollateralProvider, CollateralRequest,
+};
+use dcap_bonsai_cli::quote::Quote;
+use dcap_bonsai_cli::signed::{EnclaveIdentity, Signed, TcbInfo};
+use dcap_bonsai_cli::verify::{
+    freshness::check_freshness,
+    native::{check_supported_quote, verify_quote},
+    pck::verify_pck_chain,
+    qe::evaluate_qe_identity,
+    report::TcbReport,
+    root::check_root_ca,
+    signature::verify_collateral_signatures,
+    tcb::evaluate_tcb,
+    tdx::evaluate_tdx_module,
+};
+use dcap_bonsai_cli::{format_timestamp, pem_chain_to_der, remove_prefix_if_found, to_der};
 
-use dcap_rs::types::VerifiedOutput;
+use alloy::eips::{BlockId, BlockNumberOrTag};
+use dcap_rs::types::{collaterals::IntelCollateral, VerifiedOutput};
 
 #[derive(Parser)]
 #[command(name = "DcapBonsaiApp")]
@@ -39,11 +76,32 @@ enum Commands {
     /// Fetches proof from Bonsai and sends them on-chain to verify DCAP quote
     Prove(DcapArgs),
 
+    /// Verifies a DCAP quote natively with dcap-rs, without generating a proof
+    Verify(VerifyArgs),
+
     /// Computes the Image ID of the Guest application
     ImageId,
 
+    /// Decodes a quote and prints its header, report body and signature data
+    Inspect(InspectArgs),
+
     /// De-serializes and prints information about the Output
     Deserialize(OutputArgs),
+
+    /// Lists or clears the on-disk collateral cache
+    Cache(CacheArgs),
+
+    /// Decodes PCK certificates
+    Pck(PckArgs),
+
+    /// Evaluates the TCB level of a quote's platform and QE against its TCBInfo and QE identity
+    Tcb(TcbArgs),
+
+    /// Fetches, exports and inspects the collaterals used for a quote
+    Collaterals(CollateralsArgs),
+
+    /// Publishes collaterals missing from the on-chain PCCS
+    Pccs(PccsArgs),
 }
 
 #[derive(Args)]
@@ -59,108 +117,515 @@ struct DcapArgs {
     /// Optional: A transaction will not be sent if left blank.
     #[arg(short = 'k', long = "wallet-key")]
     wallet_private_key: Option<String>,
+
+    /// Proves even if a collateral has expired, warning instead of failing
+    #[arg(long = "allow-stale")]
+    allow_stale: bool,
+
+    #[command(flatten)]
+    chain: ChainArgs,
+
+    #[command(flatten)]
+    collaterals: CollateralArgs,
 }
 
 #[derive(Args)]
-struct OutputArgs {
-    #[arg(short = 'o', long = "output")]
-    output: String,
+struct VerifyArgs {
+    /// The input quote provided as a hex string, this overwrites the --quote-path argument
+    #[arg(short = 'q', long = "quote-hex")]
+    quote_hex: Option<String>,
+
+    /// Optional: The path to a quote.hex file. Default: /data/quote.hex or overwritten by the --quote-hex argument if provided.
+    #[arg(short = 'p', long = "quote-path")]
+    quote_path: Option<PathBuf>,
+
+    /// Optional: Verification time in seconds since epoch. Default: now
+    #[arg(long = "timestamp")]
+    timestamp: Option<u64>,
+
+    #[command(flatten)]
+    chain: ChainArgs,
+
+    #[command(flatten)]
+    collaterals: CollateralArgs,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+#[derive(Args)]
+struct TcbArgs {
+    /// The input quote provided as a hex string, this overwrites the --quote-path argument
+    #[arg(short = 'q', long = "quote-hex")]
+    quote_hex: Option<String>,
 
-    env_logger::init();
+    /// Optional: The path to a quote.hex file. Default: /data/quote.hex or overwritten by the --quote-hex argument if provided.
+    #[arg(short = 'p', long = "quote-path")]
+    quote_path: Option<PathBuf>,
 
-    match &cli.command {
-        Commands::Prove(args) => {
-            // Step 0: Read quote
-            println!("Begin reading quote and fetching the necessary collaterals...");
-            let quote = get_quote(&args.quote_path, &args.quote_hex).expect("Failed to read quote");
+    /// Prints the TCB evaluation as JSON
+    #[arg(long = "json")]
+    json: bool,
 
-            // Step 1: Determine quote version and TEE type
-            let quote_version = u16::from_le_bytes([quote[0], quote[1]]);
-            let tee_type = u32::from_le_bytes([quote[4], quote[5], quote[6], quote[7]]);
+    #[command(flatten)]
+    chain: ChainArgs,
 
-            log::info!("Quote version: {}", quote_version);
-            log::info!("TEE Type: {}", tee_type);
+    #[command(flatten)]
+    collaterals: CollateralArgs,
+}
 
-            if quote_version < 3 || quote_version > 4 {
-                panic!("Unsupported quote version");
-            }
+#[derive(Args)]
+struct InspectArgs {
+    /// The input quote provided as a hex string, this overwrites the --quote-path argument
+    #[arg(short = 'q', long = "quote-hex")]
+    quote_hex: Option<String>,
 
-            if tee_type != SGX_TEE_TYPE && tee_type != TDX_TEE_TYPE {
-                panic!("Unsupported tee type");
-            }
+    /// Optional: The path to a quote.hex file. Default: /data/quote.hex or overwritten by the --quote-hex argument if provided.
+    #[arg(short = 'p', long = "quote-path")]
+    quote_path: Option<PathBuf>,
 
-            // Step 2: Load collaterals
-            println!("Quote read successfully. Begin fetching collaterals from the on-chain PCCS");
+    /// Prints the decoded quote as JSON
+    #[arg(long = "json")]
+    json: bool,
+}
 
-            let (root_ca, root_ca_crl) = get_certificate_by_id(CA::ROOT).await?;
-            if root_ca.is_empty() || root_ca_crl.is_empty() {
-                panic!("Intel SGX Root CA is missing");
-            } else {
-                log::info!("Fetched Intel SGX RootCA and CRL");
-            }
+/// Enum representing the available collateral sources
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+enum CollateralSource {
+    /// The on-chain PCCS DAOs of the selected network
+    Onchain,
+    /// The Intel PCS, or a PCCS instance, at --pcs-url
+    Pcs,
+    /// Collateral files in --collaterals-dir
+    Dir,
+}
 
-            let (fmspc, pck_type, pck_issuer) =
-                get_pck_fmspc_and_issuer(&quote, quote_version, tee_type);
+#[derive(Args, Clone)]
+struct CollateralArgs {
+    /// Collateral sources to query, in order of preference. Default: dir if --collaterals-dir is provided, onchain otherwise
+    #[arg(long = "collateral-source", value_enum, value_delimiter = ',')]
+    sources: Vec<CollateralSource>,
 
-            let tcb_type: u8;
-            if tee_type == TDX_TEE_TYPE {
-                tcb_type = 1;
-            } else {
-                tcb_type = 0;
-            }
-            let tcb_version: u32;
-            if quote_version < 4 {
-                tcb_version = 2
-            } else {
-                tcb_version = 3
-            }
-            let tcb_info = get_tcb_info(tcb_type, fmspc.as_str(), tcb_version).await?;
+    /// Base URL of the Intel PCS or of a PCCS instance, used by the `pcs` source
+    #[arg(long = "pcs-url", env = "PCS_URL", default_value = INTEL_PCS_URL)]
+    pcs_url: String,
 
-            log::info!("Fetched TCBInfo JSON for FMSPC: {}", fmspc);
+    /// Directory holding collateral files. On its own, collaterals are read from this directory only and no RPC call is made
+    #[arg(long = "collaterals-dir")]
+    collaterals_dir: Option<PathBuf>,
 
-            let qe_id_type: EnclaveIdType;
-            if tee_type == TDX_TEE_TYPE {
-                qe_id_type = EnclaveIdType::TDQE
-            } else {
-                qe_id_type = EnclaveIdType::QE
-            }
-            let qe_identity = get_enclave_identity(qe_id_type, quote_version as u32).await?;
-            log::info!("Fetched QEIdentity JSON");
+    /// Optional: TCBInfo JSON file in Intel's signed format, replaces the fetched TCBInfo
+    #[arg(long = "tcb-info-file")]
+    tcb_info_file: Option<PathBuf>,
 
-            let (signing_ca, _) = get_certificate_by_id(CA::SIGNING).await?;
-            if signing_ca.is_empty() {
-                panic!("Intel TCB Signing CA is missing");
-            } else {
-                log::info!("Fetched Intel TCB Signing CA");
+    /// Optional: QEIdentity JSON file in Intel's signed format, replaces the fetched QEIdentity
+    #[arg(long = "qe-identity-file")]
+    qe_identity_file: Option<PathBuf>,
+
+    /// Optional: Intel SGX Root CA certificate (DER or PEM), replaces the fetched Root CA
+    #[arg(long = "root-ca-file")]
+    root_ca_file: Option<PathBuf>,
+
+    /// Optional: Intel SGX Root CA CRL (DER or PEM), replaces the fetched Root CA CRL
+    #[arg(long = "root-ca-crl-file")]
+    root_ca_crl_file: Option<PathBuf>,
+
+    /// Optional: Intel TCB Signing CA certificate (DER or PEM), replaces the fetched Signing CA
+    #[arg(long = "signing-ca-file")]
+    signing_ca_file: Option<PathBuf>,
+
+    /// Optional: PCK CRL (DER or PEM), replaces the fetched PCK CRL
+    #[arg(long = "pck-crl-file")]
+    pck_crl_file: Option<PathBuf>,
+
+    /// Optional: Root certificate (DER or PEM) to trust instead of the pinned Intel SGX Root CA, for test PKIs
+    #[arg(long = "trusted-root")]
+    trusted_root: Option<PathBuf>,
+
+    /// Reads on-chain collaterals with individual concurrent calls instead of one Multicall3 call
+    #[arg(long = "no-multicall")]
+    no_multicall: bool,
+
+    /// Optional: Reads on-chain collaterals at a block number, hash or tag (e.g. finalized), bypassing the cache
+    #[arg(long = "block", value_parser = parse_block_id)]
+    block: Option<BlockId>,
+
+    /// Disables the on-disk cache of on-chain collaterals
+    #[arg(long = "no-cache")]
+    no_cache: bool,
+
+    /// Optional: Collateral cache directory. Default: $XDG_CACHE_HOME/dcap-bonsai-cli/collaterals
+    #[arg(long = "cache-dir", env = "COLLATERAL_CACHE_DIR")]
+    cache_dir: Option<PathBuf>,
+}
+
+impl CollateralArgs {
+    fn sources(&self) -> Vec<CollateralSource> {
+        if !self.sources.is_empty() {
+            self.sources.clone()
+        } else if self.collaterals_dir.is_some() {
+            vec![CollateralSource::Dir]
+        } else {
+            vec![CollateralSource::Onchain]
+        }
+    }
+
+    /// Whether the collaterals come from local files only
+    fn is_offline(&self) -> bool {
+        self.sources()
+            .iter()
+            .all(|source| *source == CollateralSource::Dir)
+    }
+
+    /// Resolves `--block` to a concrete block when collaterals are read on-chain
+    async fn pinned_block(&self, chain_config: &ChainConfig) -> Result<Option<PinnedBlock>> {
+        match self.block {
+            Some(block) if self.sources().contains(&CollateralSource::Onchain) => {
+                Ok(Some(resolve_block(chain_config, block).await?))
             }
+            _ => Ok(None),
+        }
+    }
 
-            let (_, pck_crl) = get_certificate_by_id(pck_type).await?;
-            if pck_crl.is_empty() {
-                panic!("CRL for {} is missing", pck_issuer);
-            } else {
-                log::info!("Fetched Intel PCK CRL for {}", pck_issuer);
+    /// Reads the DER certificate given with --trusted-root
+    fn trusted_root(&self) -> Result<Option<Vec<u8>>> {
+        match &self.trusted_root {
+            Some(path) => Ok(Some(to_der(&std::fs::read(path)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn files(&self) -> CollateralFiles {
+        CollateralFiles {
+            tcb_info: self.tcb_info_file.clone(),
+            qe_identity: self.qe_identity_file.clone(),
+            root_ca: self.root_ca_file.clone(),
+            root_ca_crl: self.root_ca_crl_file.clone(),
+            signing_ca: self.signing_ca_file.clone(),
+            pck_crl: self.pck_crl_file.clone(),
+        }
+    }
+
+    fn build_provider(
+        &self,
+        chain_config: &ChainConfig,
+        pinned_block: Option<&PinnedBlock>,
+    ) -> Result<Box<dyn CollateralProvider>> {
+        let mut providers: Vec<Box<dyn CollateralProvider>> = Vec::new();
+        for source in self.sources().iter() {
+            match source {
+                CollateralSource::Onchain => {
+                    let block = pinned_block
+                        .map(PinnedBlock::id)
+                        .unwrap_or(BlockNumberOrTag::Latest.into());
+                    let onchain = Box::new(OnChainProvider::new(
+                        chain_config,
+                        !self.no_multicall,
+                        block,
+                    )?);
+                    // The cache only holds the latest collaterals, of a known chain
+                    match chain_config.chain_id {
+                        Some(chain_id) if !self.no_cache && pinned_block.is_none() => {
+                            let cache = CollateralCache::new(&cache_dir(&self.cache_dir)?);
+                            providers.push(Box::new(CachedProvider::new(
+                                onchain,
+                                cache,
+                                chain_id,
+                                chain_config,
+                            )))
+                        }
+                        _ => providers.push(onchain),
+                    }
+                }
+                CollateralSource::Pcs => providers.push(Box::new(PcsProvider::new(&self.pcs_url))),
+                CollateralSource::Dir => {
+                    let dir = self.collaterals_dir.as_ref().ok_or_else(|| {
+                        anyhow::Error::msg("The dir collateral source requires --collaterals-dir")
+                    })?;
+                    providers.push(Box::new(DirProvider::new(dir)))
+                }
             }
+        }
 
-            let collaterals = Collaterals::new(
-                tcb_info,
-                qe_identity,
-                root_ca,
-                signing_ca,
-                root_ca_crl,
-                pck_crl,
-            );
-            let serialized_collaterals = serialize_collaterals(&collaterals, pck_type);
+        let provider: Box<dyn CollateralProvider> = if providers.len() == 1 {
+            providers.remove(0)
+        } else {
+            Box::new(FallbackProvider::new(providers))
+        };
+
+        let files = self.files();
+        if files.is_empty() {
+            Ok(provider)
+        } else {
+            Ok(Box::new(OverrideProvider::new(provider, files)))
+        }
+    }
+}
+
+#[derive(Args)]
+struct ChainArgs {
+    /// Named network profile, built-in or defined in the networks config file
+    #[arg(short = 'n', long = "network", env = "NETWORK", default_value = DEFAULT_NETWORK)]
+    network: String,
+
+    /// Optional: Path to a TOML or YAML networks config file. Default: ./networks.toml if present
+    #[arg(long = "config", env = "NETWORKS_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Overrides the RPC endpoint of the selected network
+    #[arg(long = "rpc-url", env = "RPC_URL")]
+    rpc_url: Option<String>,
+
+    /// Overrides the address of the DCAP attestation contract
+    #[arg(long = "dcap-contract", env = "DCAP_CONTRACT")]
+    dcap_contract: Option<String>,
+
+    /// Overrides the block explorer URL used to print transaction links
+    #[arg(long = "explorer-url", env = "EXPLORER_URL")]
+    explorer_url: Option<String>,
+
+    /// Overrides the address of the PCS DAO
+    #[arg(long = "pcs-dao", env = "PCS_DAO")]
+    pcs_dao: Option<String>,
+
+    /// Overrides the address of the FMSPC TCB DAO
+    #[arg(long = "fmspc-tcb-dao", env = "FMSPC_TCB_DAO")]
+    fmspc_tcb_dao: Option<String>,
+
+    /// Overrides the address of the Enclave Identity DAO
+    #[arg(long = "enclave-id-dao", env = "ENCLAVE_ID_DAO")]
+    enclave_id_dao: Option<String>,
+
+    /// Overrides the address of the PCK DAO
+    #[arg(long = "pck-dao", env = "PCK_DAO")]
+    pck_dao: Option<String>,
+
+    /// Overrides the address of the Multicall3 contract used to batch PCCS reads
+    #[arg(long = "multicall3", env = "MULTICALL3")]
+    multicall3: Option<String>,
+}
+
+impl ChainArgs {
+    fn to_config(&self) -> Result<ChainConfig> {
+        let overrides = NetworkProfile {
+            rpc_url: self.rpc_url.clone(),
+            chain_id: None,
+            dcap_contract: self.dcap_contract.clone(),
+            explorer_url: self.explorer_url.clone(),
+            pcs_dao: self.pcs_dao.clone(),
+            fmspc_tcb_dao: self.fmspc_tcb_dao.clone(),
+            enclave_id_dao: self.enclave_id_dao.clone(),
+            pck_dao: self.pck_dao.clone(),
+            multicall3: self.multicall3.clone(),
+        };
+        resolve_chain_config(&self.network, self.config.as_deref(), &overrides)
+    }
+}
+
+#[derive(Args)]
+struct CacheArgs {
+    #[command(subcommand)]
+    command: CacheCommands,
+
+    /// Optional: Collateral cache directory. Default: $XDG_CACHE_HOME/dcap-bonsai-cli/collaterals
+    #[arg(long = "cache-dir", env = "COLLATERAL_CACHE_DIR", global = true)]
+    cache_dir: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum CacheCommands {
+    /// Lists the cached collaterals and when they expire
+    List,
+
+    /// Removes cached collaterals
+    Clear {
+        /// Only removes the collaterals past their nextUpdate
+        #[arg(long = "expired")]
+        expired: bool,
+    },
+}
+
+#[derive(Args)]
+struct PckArgs {
+    #[command(subcommand)]
+    command: PckCommands,
+}
+
+#[derive(Subcommand)]
+enum PckCommands {
+    /// Prints the SGX extensions of a PCK certificate: PPID, TCB, PCE-ID, FMSPC, SGX type and configuration
+    Show(PckShowArgs),
+}
+
+#[derive(Args)]
+struct PckShowArgs {
+    /// PCK certificate (DER or PEM, the first certificate of a PEM chain is used), instead of the quote's
+    #[arg(long = "cert")]
+    cert: Option<PathBuf>,
+
+    /// The input quote provided as a hex string, this overwrites the --quote-path argument
+    #[arg(short = 'q', long = "quote-hex")]
+    quote_hex: Option<String>,
+
+    /// Optional: The path to a quote.hex file. Default: /data/quote.hex or overwritten by the --quote-hex argument if provided.
+    #[arg(short = 'p', long = "quote-path")]
+    quote_path: Option<PathBuf>,
+
+    /// Prints the SGX extensions as JSON
+    #[arg(long = "json")]
+    json: bool,
+
+    /// Used to look up the PCK certificate in the PCK DAO for quotes that carry no PCK certificate chain
+    #[command(flatten)]
+    chain: ChainArgs,
+}
+
+#[derive(Args)]
+struct CollateralsArgs {
+    #[command(subcommand)]
+    command: CollateralsCommands,
+}
+
+#[derive(Subcommand)]
+enum CollateralsCommands {
+    /// Writes the collaterals used for a quote to a JSON bundle, with their source, block and hashes
+    Fetch(CollateralsFetchArgs),
+
+    /// Prints the TCB levels, QE identity levels, certificates and CRLs of a bundle, or of the live collaterals of a quote
+    Show(CollateralsShowArgs),
+}
+
+#[derive(Args)]
+struct CollateralsFetchArgs {
+    /// The input quote provided as a hex string, this overwrites the --quote-path argument
+    #[arg(short = 'q', long = "quote-hex")]
+    quote_hex: Option<String>,
+
+    /// Optional: The path to a quote.hex file. Default: /data/quote.hex or overwritten by the --quote-hex argument if provided.
+    #[arg(short = 'p', long = "quote-path")]
+    quote_path: Option<PathBuf>,
+
+    /// Path of the JSON bundle to write
+    #[arg(short = 'o', long = "out")]
+    out: PathBuf,
+
+    #[command(flatten)]
+    chain: ChainArgs,
+
+    #[command(flatten)]
+    collaterals: CollateralArgs,
+}
+
+#[derive(Args)]
+struct CollateralsShowArgs {
+    /// JSON bundle written by `collaterals fetch`, instead of fetching the quote's collaterals
+    #[arg(short = 'b', long = "bundle")]
+    bundle: Option<PathBuf>,
+
+    /// The input quote provided as a hex string, this overwrites the --quote-path argument
+    #[arg(short = 'q', long = "quote-hex")]
+    quote_hex: Option<String>,
+
+    /// Optional: The path to a quote.hex file. Default: /data/quote.hex or overwritten by the --quote-hex argument if provided.
+    #[arg(short = 'p', long = "quote-path")]
+    quote_path: Option<PathBuf>,
+
+    #[command(flatten)]
+    chain: ChainArgs,
+
+    #[command(flatten)]
+    collaterals: CollateralArgs,
+}
+
+#[derive(Args)]
+struct PccsArgs {
+    #[command(subcommand)]
+    command: PccsCommands,
+}
+
+#[derive(Subcommand)]
+enum PccsCommands {
+    /// Upserts Intel PCS collaterals to the PCS, FMSPC TCB and Enclave Identity DAOs and prints their attestation IDs
+    Upsert(PccsUpsertArgs),
+}
+
+#[derive(Args)]
+struct PccsUpsertArgs {
+    /// TCBInfo JSON as returned by the PCS tcb endpoint
+    #[arg(long = "tcb-info")]
+    tcb_info: Option<PathBuf>,
+
+    /// QEIdentity JSON as returned by the PCS qe/identity endpoint
+    #[arg(long = "qe-identity")]
+    qe_identity: Option<PathBuf>,
+
+    /// PCS API version the QEIdentity was fetched from, which the DAO stores it under
+    #[arg(long = "qe-identity-version", default_value_t = 4)]
+    qe_identity_version: u32,
+
+    /// Issuer chain of the TCBInfo or QEIdentity (the TCB-Info-Issuer-Chain or SGX-Enclave-Identity-Issuer-Chain header, URL-encoded or not), whose TCB Signing CA is upserted first
+    #[arg(long = "issuer-chain")]
+    issuer_chain: Option<PathBuf>,
+
+    /// PCK CRL (DER, PEM or hex) as returned by the PCS pckcrl endpoint, its CA is read from its issuer
+    #[arg(long = "pck-crl")]
+    pck_crl: Option<PathBuf>,
+
+    /// Root CA CRL (DER, PEM or hex)
+    #[arg(long = "root-ca-crl")]
+    root_ca_crl: Option<PathBuf>,
+
+    /// Optional: The upserts are only simulated if left blank.
+    #[arg(short = 'k', long = "wallet-key")]
+    wallet_private_key: Option<String>,
+
+    #[command(flatten)]
+    chain: ChainArgs,
+}
+
+#[derive(Args)]
+struct OutputArgs {
+    #[arg(short = 'o', long = "output")]
+    output: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+
+    env_logger::init();
 
-            // Step 3: Generate the input to upload to Bonsai
+    match &cli.command {
+        Commands::Prove(args) => {
+            // Steps 0 to 2: Read the quote and load its collaterals, checked at the time
+            // the guest will verify the quote at
+            let current_time = now() as u64;
+            let QuoteInput {
+                quote,
+                chain_config,
+                pinned_block,
+                pck_type,
+                collaterals,
+                tcb_report,
+                ..
+            } = load_quote_input(
+                &args.quote_path,
+                &args.quote_hex,
+                &args.chain,
+                &args.collaterals,
+                current_time,
+                true,
+            )
+            .await?;
+            print!("{}", tcb_report);
+            let serialized_collaterals = serialize_collaterals(&collaterals, pck_type)?;
+
+            // Step 3: Check the collaterals at the time the guest will verify the quote at
+            check_freshness(&collaterals, current_time, args.allow_stale)?;
+
+            // Step 4: Generate the input to upload to Bonsai
             let image_id = compute_image_id(DCAP_GUEST_ELF)?;
             log::info!("Image ID: {}", image_id.to_string());
 
-            let input = generate_input(&quote, &serialized_collaterals);
+            let input = generate_input(&quote, &serialized_collaterals, current_time);
             println!("All collaterals found! Begin uploading input to Bonsai...");
 
             // Set RISC0_PROVER env to bonsai
@@ -212,63 +677,165 @@ async fn main() -> Result<()> {
             log::info!("Signing Cert Hash: {}", hex::encode(&signing_cert_hash));
             log::info!("Root CRL hash: {}", hex::encode(&root_crl_hash));
             log::info!("PCK CRL hash: {}", hex::encode(&pck_crl_hash));
+            if let Some(block) = &pinned_block {
+                println!("Collaterals Block: {} ({})", block.number, block.hash);
+            }
 
             println!("Journal: {}", hex::encode(&output));
             println!("seal: {}", hex::encode(&seal));
 
-            // Send the calldata to Ethereum.
-            log::info!("Submitting proofs to on-chain DCAP contract to be verified...");
-            let calldata = generate_attestation_calldata(&output, &seal);
-            log::info!("Calldata: {}", hex::encode(&calldata));
-
-            let mut tx_sender = TxSender::new(DEFAULT_RPC_URL, DEFAULT_DCAP_CONTRACT)
-                .expect("Failed to create txSender");
-
-            // staticcall to the DCAP verifier contract to verify proof
-            let call_output = (tx_sender.call(calldata.clone()).await?).to_vec();
-            let (chain_verified, chain_raw_verified_output) =
-                decode_attestation_ret_data(call_output);
-
-            if chain_verified && raw_verified_output == chain_raw_verified_output {
-                let wallet_key = args.wallet_private_key.as_deref();
-                println!("Successfully verified on-chain!");
-                match wallet_key {
-                    Some(wallet_key) => {
-                        tx_sender
-                            .set_wallet(wallet_key)
-                            .expect("Failed to configure wallet");
-
-                        println!(
-                            "Wallet found! Address: {}",
-                            get_evm_address_from_key(wallet_key)
-                        );
+            if args.collaterals.is_offline() {
+                println!("Collaterals were read from local files, skipping on-chain verification");
+            } else {
+                verify_on_chain(
+                    &chain_config,
+                    &output,
+                    &seal,
+                    raw_verified_output,
+                    args.wallet_private_key.as_deref(),
+                )
+                .await?;
+            }
+        }
+        Commands::Verify(args) => {
+            let timestamp = args.timestamp.unwrap_or(now() as u64);
+            let QuoteInput {
+                quote,
+                version,
+                pck_type,
+                collaterals,
+                ..
+            } = load_quote_input(
+                &args.quote_path,
+                &args.quote_hex,
+                &args.chain,
+                &args.collaterals,
+                timestamp,
+                false,
+            )
+            .await?;
+            let intel_collaterals =
+                IntelCollateral::from_bytes(&serialize_collaterals(&collaterals, pck_type)?);
 
-                        log::info!("Sending the transaction...");
+            let verified_output = verify_quote(&quote, version, &intel_collaterals, timestamp)?;
 
-                        let tx_receipt = tx_sender.send(calldata.clone()).await?;
-                        let hash = tx_receipt.transaction_hash;
-                        println!(
-                            "See transaction at: {}/0x{}",
-                            DEFAULT_EXPLORER_URL,
-                            hex::encode(hash.as_slice())
-                        );
-                    }
-                    _ => {
-                        log::info!("No wallet key provided");
-                    }
-                }
+            println!("Timestamp: {}", format_timestamp(timestamp as i64));
+            println!("Verified Output: {:?}", verified_output);
+            println!("Output: {}", hex::encode(verified_output.to_vec()));
+        }
+        Commands::Tcb(args) => {
+            let QuoteInput { tcb_report, .. } = load_quote_input(
+                &args.quote_path,
+                &args.quote_hex,
+                &args.chain,
+                &args.collaterals,
+                now() as u64,
+                false,
+            )
+            .await?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&tcb_report)?);
+            } else {
+                print!("{}", tcb_report);
             }
         }
         Commands::ImageId => {
             let image_id = compute_image_id(DCAP_GUEST_ELF).unwrap().to_string();
             println!("ImageID: {}", image_id);
         }
+        Commands::Inspect(args) => {
+            let quote = get_quote(&args.quote_path, &args.quote_hex)?;
+            let parsed_quote = Quote::from_bytes(&quote)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&parsed_quote)?);
+            } else {
+                print!("{}", parsed_quote);
+            }
+        }
         Commands::Deserialize(args) => {
             let output_vec =
                 hex::decode(remove_prefix_if_found(&args.output)).expect("Failed to parse output");
             let deserialized_output = VerifiedOutput::from_bytes(&output_vec);
             println!("Deserialized output: {:?}", deserialized_output);
         }
+        Commands::Cache(args) => {
+            let cache = CollateralCache::new(&cache_dir(&args.cache_dir)?);
+            match &args.command {
+                CacheCommands::List => {
+                    let now = now();
+                    let entries = cache.list()?;
+                    println!(
+                        "{} cached collaterals in {}",
+                        entries.len(),
+                        cache.dir().display()
+                    );
+                    for entry in entries {
+                        println!(
+                            "{}  nextUpdate: {}{}",
+                            entry.key,
+                            format_timestamp(entry.next_update),
+                            if entry.is_expired(now) {
+                                " (expired)"
+                            } else {
+                                ""
+                            }
+                        );
+                    }
+                }
+                CacheCommands::Clear { expired } => {
+                    let removed = cache.clear(*expired)?;
+                    println!(
+                        "Removed {} cached collaterals from {}",
+                        removed,
+                        cache.dir().display()
+                    );
+                }
+            }
+        }
+        Commands::Pck(args) => match &args.command {
+            PckCommands::Show(args) => {
+                let pck_cert = get_pck_cert_for_show(args).await?;
+                let extensions = get_pck_extensions(&pck_cert)?;
+                if args.json {
+                    println!("{}", serde_json::to_string_pretty(&extensions)?);
+                } else {
+                    let (_, pck_issuer) = get_pck_issuer(&pck_cert)?;
+                    println!("PCK issuer: {}", pck_issuer);
+                    print!("{}", extensions);
+                }
+            }
+        },
+        Commands::Collaterals(args) => match &args.command {
+            CollateralsCommands::Fetch(args) => {
+                let bundle = fetch_bundle(
+                    &args.quote_path,
+                    &args.quote_hex,
+                    &args.chain,
+                    &args.collaterals,
+                )
+                .await?;
+                std::fs::write(&args.out, bundle.to_json()?)?;
+                println!("Collaterals written to {}", args.out.display());
+            }
+            CollateralsCommands::Show(args) => {
+                let bundle = match &args.bundle {
+                    Some(path) => CollateralBundle::from_json(&std::fs::read(path)?)?,
+                    None => {
+                        fetch_bundle(
+                            &args.quote_path,
+                            &args.quote_hex,
+                            &args.chain,
+                            &args.collaterals,
+                        )
+                        .await?
+                    }
+                };
+                print!("{}", bundle.summary()?);
+            }
+        },
+        Commands::Pccs(args) => match &args.command {
+            PccsCommands::Upsert(args) => upsert_collaterals(args).await?,
+        },
     }
 
     println!("Job completed!");
@@ -278,6 +845,311 @@ async fn main() -> Result<()> {
 
 // Helper functions go here
 
+/// A quote and the collaterals needed to verify it
+struct QuoteInput {
+    quote: Vec<u8>,
+    version: u16,
+    chain_config: ChainConfig,
+    pinned_block: Option<PinnedBlock>,
+    source: String,
+    request: CollateralRequest,
+    pck_type: CA,
+    collaterals: Collaterals,
+    tcb_report: TcbReport,
+}
+
+/// Reads the quote, fetches its collaterals, checks their signatures and its PCK certificate chain,
+/// and evaluates its TCB level, QE identity and TDX module identity. The PCK certificate chain
+/// is checked at `timestamp` (seconds since epoch), the time the quote is verified at.
+/// With `for_guest`, quotes the guest program cannot verify are refused before anything is fetched.
+async fn load_quote_input(
+    quote_path: &Option<PathBuf>,
+    quote_hex: &Option<String>,
+    chain_args: &ChainArgs,
+    collateral_args: &CollateralArgs,
+    timestamp: u64,
+    for_guest: bool,
+) -> Result<QuoteInput> {
+    // Step 0: Read quote
+    println!("Begin reading quote and fetching the necessary collaterals...");
+    let quote = get_quote(quote_path, quote_hex).context("Failed to read quote")?;
+
+    // Step 1: Determine quote version and TEE type
+    let parsed_quote = Quote::from_bytes(&quote)?;
+    let quote_version = parsed_quote.header.version;
+    let tee_type = parsed_quote.header.tee_type;
+
+    log::info!("Quote version: {}", quote_version);
+    log::info!("TEE Type: {}", tee_type);
+
+    // The guest only verifies v3 and v4 quotes that embed their PCK certificate chain,
+    // don't pay for a proof bound to fail
+    if for_guest {
+        check_supported_quote(&parsed_quote)?;
+    }
+
+    // Step 2: Load collaterals
+    let chain_config = chain_args.to_config()?;
+    if !collateral_args.is_offline() {
+        check_chain_id(&chain_config).await?;
+    }
+    let pinned_block = collateral_args.pinned_block(&chain_config).await?;
+    if let Some(block) = &pinned_block {
+        log::info!(
+            "Reading on-chain collaterals at block {} ({})",
+            block.number,
+            block.hash
+        );
+    }
+    let provider = collateral_args.build_provider(&chain_config, pinned_block.as_ref())?;
+    let source = provider.name();
+    println!(
+        "Quote read successfully. Begin fetching collaterals from {}",
+        source
+    );
+
+    let (pck_cert_chain, pck_from_dao) = match get_pck_certification_data(&parsed_quote)? {
+        PckCertificationData::CertChain(chain) => (chain, false),
+        PckCertificationData::Identifier(id) => {
+            if collateral_args.is_offline() {
+                return Err(Error::msg(
+                    "The quote carries no PCK certificate chain, which must be read from the on-chain PCK DAO",
+                ));
+            }
+            let block = pinned_block
+                .map(|block| block.id())
+                .unwrap_or(BlockNumberOrTag::Latest.into());
+            let client = PccsClient::new(&chain_config, block)?;
+            log::info!(
+                "Reading the PCK certificate for QE ID {} from the PCK DAO",
+                hex::encode(id.qe_id)
+            );
+            (get_pck_cert_chain(&client, &id).await?, true)
+        }
+    };
+    let (fmspc, pck_type, pck_issuer) = get_pck_fmspc_and_issuer(&pck_cert_chain)?;
+    log::info!("FMSPC: {}, PCK issuer: {}", fmspc, pck_issuer);
+
+    let request = CollateralRequest::new(quote_version, tee_type, &fmspc, pck_type);
+    let mut collaterals = fetch_collaterals(provider.as_ref(), &request).await?;
+    if pck_from_dao {
+        collaterals.pck_certchain = pck_cert_chain.clone();
+    }
+
+    // Hand the guest the TCBInfo and QEIdentity bodies exactly as signed, whatever the source
+    let tcb_info = Signed::<TcbInfo>::from_json(&collaterals.tcb_info)?;
+    let qe_identity = Signed::<EnclaveIdentity>::from_json(&collaterals.qe_identity)?;
+    collaterals.tcb_info = tcb_info.to_json();
+    collaterals.qe_identity = qe_identity.to_json();
+
+    // Catch a swapped root of trust before anything is verified against it
+    check_root_ca(
+        &collaterals.root_ca,
+        collateral_args.trusted_root()?.as_deref(),
+    )?;
+
+    // Catch tampered or mis-stored collaterals before paying for a proof
+    verify_collateral_signatures(&collaterals)?;
+    log::info!("TCBInfo and QEIdentity signatures verified");
+
+    // Catch a revoked or expired PCK certificate before paying for a proof
+    verify_pck_chain(&pck_cert_chain, &collaterals, timestamp as i64)?;
+    log::info!("PCK certificate chain verified");
+
+    let pck_extensions = get_pck_extensions(&pem_chain_to_der(&pck_cert_chain)?[0])?;
+    let tcb_evaluation = evaluate_tcb(
+        &tcb_info.body,
+        &pck_extensions,
+        parsed_quote.body.tee_tcb_svn(),
+    )?;
+    let qe_report_cert_data = parsed_quote
+        .signature
+        .cert_data
+        .qe_report_cert_data()
+        .ok_or_else(|| anyhow::Error::msg("The quote carries no QE report"))?;
+    let qe_identity_evaluation =
+        evaluate_qe_identity(&qe_identity.body, &qe_report_cert_data.qe_report)?;
+    let tdx_module_evaluation = match parsed_quote.body.td_report() {
+        Some(td_report) => Some(evaluate_tdx_module(&tcb_info.body, td_report)?),
+        None => None,
+    };
+    let tcb_report = TcbReport::new(
+        tcb_evaluation,
+        qe_identity_evaluation,
+        tdx_module_evaluation,
+    );
+
+    Ok(QuoteInput {
+        quote,
+        version: quote_version,
+        chain_config,
+        pinned_block,
+        pck_type,
+        source,
+        request,
+        collaterals,
+        tcb_report,
+    })
+}
+
+/// Loads the quote's collaterals into a bundle, reading on-chain collaterals at a pinned
+/// block, the latest one unless --block is given, so that the bundle records it
+async fn fetch_bundle(
+    quote_path: &Option<PathBuf>,
+    quote_hex: &Option<String>,
+    chain_args: &ChainArgs,
+    collateral_args: &CollateralArgs,
+) -> Result<CollateralBundle> {
+    let mut collateral_args = collateral_args.clone();
+    collateral_args
+        .block
+        .get_or_insert(BlockNumberOrTag::Latest.into());
+
+    let QuoteInput {
+        source,
+        pinned_block,
+        request,
+        collaterals,
+        ..
+    } = load_quote_input(
+        quote_path,
+        quote_hex,
+        chain_args,
+        &collateral_args,
+        now() as u64,
+        false,
+    )
+    .await?;
+    CollateralBundle::new(&source, pinned_block.as_ref(), &request, &collaterals)
+}
+
+/// Upserts the given collaterals to the PCCS DAOs, certificates and CRLs first since
+/// the DAOs verify the TCBInfo and QEIdentity against them. Without a wallet key nothing
+/// is sent, so every upsert is simulated against the DAOs as they are on-chain, without
+/// the certificates and CRLs simulated before it
+async fn upsert_collaterals(args: &PccsUpsertArgs) -> Result<()> {
+    let chain_config = args.chain.to_config()?;
+    check_chain_id(&chain_config).await?;
+
+    let mut upserts: Vec<(String, &str, Vec<u8>)> = Vec::new();
+    if let Some(path) = &args.issuer_chain {
+        upserts.push((
+            String::from("Intel TCB Signing CA"),
+            chain_config.pcs_dao.as_str(),
+            upsert_certificate_calldata(CA::SIGNING, &read_signing_ca(path)?),
+        ));
+    }
+    if let Some(path) = &args.root_ca_crl {
+        let crl = to_der(&std::fs::read(path)?)?;
+        upserts.push((
+            String::from("Root CA CRL"),
+            chain_config.pcs_dao.as_str(),
+            upsert_root_ca_crl_calldata(&crl),
+        ));
+    }
+    if let Some(path) = &args.pck_crl {
+        let crl = to_der(&std::fs::read(path)?)?;
+        let ca = pck_crl_ca(&crl)?;
+        upserts.push((
+            format!("PCK CRL ({})", ca_name(ca)),
+            chain_config.pcs_dao.as_str(),
+            upsert_pck_crl_calldata(ca, &crl),
+        ));
+    }
+    if let Some(path) = &args.tcb_info {
+        let tcb_info = Signed::<TcbInfo>::from_json(&std::fs::read(path)?)?;
+        upserts.push((
+            format!("TCBInfo for FMSPC: {}", tcb_info.body.fmspc),
+            chain_config.fmspc_tcb_dao.as_str(),
+            upsert_tcb_info_calldata(&tcb_info),
+        ));
+    }
+    if let Some(path) = &args.qe_identity {
+        let identity = Signed::<EnclaveIdentity>::from_json(&std::fs::read(path)?)?;
+        upserts.push((
+            format!(
+                "{} identity; Version: {}",
+                identity.body.id, args.qe_identity_version
+            ),
+            chain_config.enclave_id_dao.as_str(),
+            upsert_enclave_identity_calldata(&identity, args.qe_identity_version)?,
+        ));
+    }
+    if upserts.is_empty() {
+        return Err(anyhow::Error::msg(
+            "Nothing to upsert, pass --tcb-info, --qe-identity, --issuer-chain, --pck-crl or --root-ca-crl",
+        ));
+    }
+
+    let mut simulated: Vec<String> = Vec::new();
+    for (collateral, dao, calldata) in upserts {
+        let mut tx_sender = TxSender::new(&chain_config.rpc_url, dao)?;
+
+        // staticcall first, to read the attestation ID and catch a rejected collateral before paying gas
+        let attestation_id = tx_sender
+            .call(calldata.clone())
+            .await
+            .and_then(|output| decode_attestation_id(&output))
+            .with_context(|| {
+                if simulated.is_empty() {
+                    format!("The DAO rejects the upsert of {}", collateral)
+                } else {
+                    format!(
+                        "The DAO rejects the upsert of {}, note that it was simulated without the {} of this run, which were not sent",
+                        collateral,
+                        simulated.join(", ")
+                    )
+                }
+            })?;
+        match &args.wallet_private_key {
+            Some(wallet_key) => {
+                tx_sender.set_wallet(wallet_key)?;
+                let tx_receipt = tx_sender.send(calldata).await?;
+                let hash = tx_receipt.transaction_hash;
+                if !tx_receipt.status() {
+                    return Err(anyhow::Error::msg(format!(
+                        "The upsert of {} reverted in transaction 0x{}",
+                        collateral,
+                        hex::encode(hash.as_slice())
+                    )));
+                }
+                println!(
+                    "Upserted {}, attestation ID: {}",
+                    collateral, attestation_id
+                );
+                println!(
+                    "See transaction at: {}/0x{}",
+                    chain_config.explorer_url,
+                    hex::encode(hash.as_slice())
+                );
+            }
+            None => {
+                println!(
+                    "{} would be upserted, attestation ID: {}",
+                    collateral, attestation_id
+                );
+                simulated.push(collateral);
+            }
+        }
+    }
+    if args.wallet_private_key.is_none() {
+        log::info!("No wallet key provided, the upserts were only simulated");
+    }
+
+    Ok(())
+}
+
+/// Reads the TCB Signing CA, the first certificate of a PEM issuer chain as the PCS
+/// returns it in its issuer chain headers, URL-encoded or not
+fn read_signing_ca(path: &PathBuf) -> Result<Vec<u8>> {
+    let encoded = read_to_string(path)?;
+    let decoded = percent_encoding::percent_decode_str(encoded.trim()).decode_utf8()?;
+    pem_chain_to_der(decoded.as_bytes())?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::Error::msg("The issuer chain holds no certificate"))
+}
+
 fn get_quote(path: &Option<PathBuf>, hex: &Option<String>) -> Result<Vec<u8>> {
     let error_msg: &str = "Failed to read quote from the provided path";
     match hex {
@@ -287,14 +1159,14 @@ fn get_quote(path: &Option<PathBuf>, hex: &Option<String>) -> Result<Vec<u8>> {
         }
         _ => match path {
             Some(p) => {
-                let quote_string = read_to_string(p).expect(error_msg);
+                let quote_string = read_to_string(p).context(error_msg)?;
                 let processed = remove_prefix_if_found(&quote_string);
                 let quote_hex = hex::decode(processed)?;
                 Ok(quote_hex)
             }
             _ => {
                 let default_path = PathBuf::from(DEFAULT_QUOTE_PATH);
-                let quote_string = read_to_string(default_path).expect(error_msg);
+                let quote_string = read_to_string(default_path).context(error_msg)?;
                 let processed = remove_prefix_if_found(&quote_string);
                 let quote_hex = hex::decode(processed)?;
                 Ok(quote_hex)
@@ -303,56 +1175,89 @@ fn get_quote(path: &Option<PathBuf>, hex: &Option<String>) -> Result<Vec<u8>> {
     }
 }
 
-// Modified from https://github.com/automata-network/dcap-rs/blob/b218a9dcdf2aec8ee05f4d2bd055116947ddfced/src/types/collaterals.rs#L35-L105
-fn serialize_collaterals(collaterals: &Collaterals, pck_type: CA) -> Vec<u8> {
-    // get the total length
-    let total_length = 4 * 8
-        + collaterals.tcb_info.len()
-        + collaterals.qe_identity.len()
-        + collaterals.root_ca.len()
-        + collaterals.tcb_signing_ca.len()
-        + collaterals.root_ca_crl.len()
-        + collaterals.pck_crl.len();
-
-    // create the vec and copy the data
-    let mut data = Vec::with_capacity(total_length);
-    data.extend_from_slice(&(collaterals.tcb_info.len() as u32).to_le_bytes());
-    data.extend_from_slice(&(collaterals.qe_identity.len() as u32).to_le_bytes());
-    data.extend_from_slice(&(collaterals.root_ca.len() as u32).to_le_bytes());
-    data.extend_from_slice(&(collaterals.tcb_signing_ca.len() as u32).to_le_bytes());
-    data.extend_from_slice(&(0 as u32).to_le_bytes()); // pck_certchain_len == 0
-    data.extend_from_slice(&(collaterals.root_ca_crl.len() as u32).to_le_bytes());
-
-    match pck_type {
-        CA::PLATFORM => {
-            data.extend_from_slice(&(0 as u32).to_le_bytes());
-            data.extend_from_slice(&(collaterals.pck_crl.len() as u32).to_le_bytes());
-        }
-        CA::PROCESSOR => {
-            data.extend_from_slice(&(collaterals.pck_crl.len() as u32).to_le_bytes());
-            data.extend_from_slice(&(0 as u32).to_le_bytes());
+/// Returns the DER PCK certificate given with --cert, or else the one of the quote,
+/// read from the PCK DAO if the quote carries no PCK certificate chain
+async fn get_pck_cert_for_show(args: &PckShowArgs) -> Result<Vec<u8>> {
+    if let Some(path) = &args.cert {
+        return to_der(&std::fs::read(path)?);
+    }
+
+    let quote = get_quote(&args.quote_path, &args.quote_hex)?;
+    let parsed_quote = Quote::from_bytes(&quote)?;
+    match get_pck_certification_data(&parsed_quote)? {
+        PckCertificationData::CertChain(chain) => pem_chain_to_der(&chain)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::Error::msg("The quote's PCK certificate chain is empty")),
+        PckCertificationData::Identifier(id) => {
+            let chain_config = args.chain.to_config()?;
+            let client = PccsClient::new(&chain_config, BlockNumberOrTag::Latest.into())?;
+            get_pck_cert(&client, &id).await
         }
-        _ => unreachable!(),
     }
+}
+
+fn cache_dir(dir: &Option<PathBuf>) -> Result<PathBuf> {
+    match dir {
+        Some(dir) => Ok(dir.clone()),
+        _ => CollateralCache::default_dir(),
+    }
+}
+
+async fn verify_on_chain(
+    chain_config: &ChainConfig,
+    output: &[u8],
+    seal: &[u8],
+    raw_verified_output: &[u8],
+    wallet_key: Option<&str>,
+) -> Result<()> {
+    // Send the calldata to Ethereum.
+    log::info!("Submitting proofs to on-chain DCAP contract to be verified...");
+    let calldata = generate_attestation_calldata(output, seal);
+    log::info!("Calldata: {}", hex::encode(&calldata));
+
+    let mut tx_sender = TxSender::new(&chain_config.rpc_url, &chain_config.dcap_contract)
+        .expect("Failed to create txSender");
 
-    // collateral should only hold one PCK CRL
+    // staticcall to the DCAP verifier contract to verify proof
+    let call_output = (tx_sender.call(calldata.clone()).await?).to_vec();
+    let (chain_verified, chain_raw_verified_output) = decode_attestation_ret_data(call_output);
 
-    data.extend_from_slice(&collaterals.tcb_info);
-    data.extend_from_slice(&collaterals.qe_identity);
-    data.extend_from_slice(&collaterals.root_ca);
-    data.extend_from_slice(&collaterals.tcb_signing_ca);
-    data.extend_from_slice(&collaterals.root_ca_crl);
-    data.extend_from_slice(&collaterals.pck_crl);
+    if chain_verified && raw_verified_output == chain_raw_verified_output {
+        println!("Successfully verified on-chain!");
+        match wallet_key {
+            Some(wallet_key) => {
+                tx_sender
+                    .set_wallet(wallet_key)
+                    .expect("Failed to configure wallet");
 
-    data
+                println!(
+                    "Wallet found! Address: {}",
+                    get_evm_address_from_key(wallet_key)
+                );
+
+                log::info!("Sending the transaction...");
+
+                let tx_receipt = tx_sender.send(calldata.clone()).await?;
+                let hash = tx_receipt.transaction_hash;
+                println!(
+                    "See transaction at: {}/0x{}",
+                    chain_config.explorer_url,
+                    hex::encode(hash.as_slice())
+                );
+            }
+            _ => {
+                log::info!("No wallet key provided");
+            }
+        }
+    }
+
+    Ok(())
 }
 
-fn generate_input(quote: &[u8], collaterals: &[u8]) -> Vec<u8> {
-    // get current time in seconds since epoch
-    let current_time = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+/// Serializes the guest input, `current_time` (seconds since epoch) being the time
+/// the guest verifies the quote at
+fn generate_input(quote: &[u8], collaterals: &[u8], current_time: u64) -> Vec<u8> {
     let current_time_bytes = current_time.to_le_bytes();
 
     let quote_len = quote.len() as u32;
@@ -1,105 +1,533 @@
+use serde::Serialize;
+use std::fmt;
+use thiserror::Error;
 use x509_parser::oid_registry::asn1_rs::{
-    oid, FromDer, OctetString, Oid, Sequence,
+    oid, Boolean, Enumerated, FromDer, Integer, OctetString, Oid, Sequence,
 };
 
 use super::chain::pccs::pcs::IPCSDao::CA;
-use super::constants::SGX_TEE_TYPE;
+use super::quote::{
+    as_hex, field, Quote, PCK_CERT_CHAIN_CERT_DATA_TYPE, PPID_CLEARTEXT_CERT_DATA_TYPE,
+    PPID_RSA2048_ENCRYPTED_CERT_DATA_TYPE, PPID_RSA3072_ENCRYPTED_CERT_DATA_TYPE,
+};
 use x509_parser::prelude::*;
 
-// 48 + 384 + 4 + 64 + 64 + 384 + 64
-const V3_SGX_QE_AUTH_DATA_SIZE_OFFSET: usize = 1012;
-// 48 + 384 + 4 + 64 + 64 + 2 + 4 + 384 + 64
-const V4_SGX_QE_AUTH_DATA_SIZE_OFFSET: usize = 1018;
-// 48 + 584 + 4 + 64 + 64 + 2 + 4 + 384 + 64
-const V4_TDX_QE_AUTH_DATA_SIZE_OFFSET: usize = 1218;
-
-pub fn get_pck_fmspc_and_issuer(quote: &[u8], version: u16, tee_type: u32) -> (String, CA, String) {
-    
-    let offset: usize;
-    if version < 4 {
-        offset = V3_SGX_QE_AUTH_DATA_SIZE_OFFSET;
-    } else {
-        if tee_type == SGX_TEE_TYPE {
-            offset = V4_SGX_QE_AUTH_DATA_SIZE_OFFSET;
-        } else {
-            offset = V4_TDX_QE_AUTH_DATA_SIZE_OFFSET;
-        }
+/// Why a quote could not be parsed.
+#[derive(Debug, Error)]
+pub enum QuoteParseError {
+    #[error("{structure} is truncated: expected {len} bytes at offset {offset}, but the quote is {quote_len} bytes long")]
+    Truncated {
+        structure: &'static str,
+        offset: usize,
+        len: usize,
+        quote_len: usize,
+    },
+
+    #[error("{structure} overruns {enclosing}: expected {len} bytes at offset {offset}, but {enclosing} ends at offset {end}")]
+    Overrun {
+        structure: &'static str,
+        enclosing: &'static str,
+        offset: usize,
+        len: usize,
+        end: usize,
+    },
+
+    #[error("Unsupported quote version {0}")]
+    UnsupportedVersion(u16),
+
+    #[error("Unsupported TEE type {0:#010x}")]
+    UnsupportedTeeType(u32),
+
+    #[error("Unsupported quote body type {0}")]
+    UnsupportedBodyType(u16),
+
+    #[error("Quote body of type {body_type} is {size} bytes long, expected {expected}")]
+    InvalidBodySize {
+        body_type: u16,
+        size: u32,
+        expected: usize,
+    },
+
+    #[error("Quote body of type {body_type} does not match TEE type {tee_type:#010x}")]
+    BodyTypeMismatch { body_type: u16, tee_type: u32 },
+
+    #[error("Unsupported certification data type {found}, expected {expected}")]
+    UnsupportedCertDataType { found: u16, expected: u16 },
+
+    #[error(
+        "Certification data type {0} holds neither a PCK certificate chain nor a PCK identifier"
+    )]
+    UnsupportedPckCertDataType(u16),
+
+    #[error(
+        "Certification data of type {cert_data_type} is {size} bytes long, expected {expected}"
+    )]
+    InvalidCertDataSize {
+        cert_data_type: u16,
+        size: usize,
+        expected: usize,
+    },
+
+    #[error("Certification data nests more than {0} levels deep")]
+    CertDataTooDeep(usize),
+
+    #[error("Invalid PEM in the certification data: {0}")]
+    InvalidPem(String),
+
+    #[error("The certification data holds no certificate")]
+    EmptyCertChain,
+
+    #[error("Invalid certificate in the certification data: {0}")]
+    InvalidCertificate(String),
+
+    #[error("The PCK certificate has no issuer common name")]
+    MissingIssuer,
+
+    #[error("Unknown PCK issuer \"{0}\"")]
+    UnknownPckIssuer(String),
+
+    #[error("The PCK certificate has no SGX extensions")]
+    MissingSgxExtensions,
+
+    #[error("Invalid SGX extensions in the PCK certificate: {0}")]
+    InvalidSgxExtensions(String),
+
+    #[error("The SGX extensions of the PCK certificate have no FMSPC")]
+    MissingFmspc,
+
+    #[error("The SGX extensions of the PCK certificate have no {0}")]
+    MissingSgxExtension(&'static str),
+}
+
+/// Identifies a PCK certificate in the PCK DAO, for quotes that carry no PCK certificate chain.
+#[derive(Debug, Clone)]
+pub struct PckIdentifier {
+    pub qe_id: [u8; 16],
+    pub cpu_svn: [u8; 16],
+    pub pce_svn: u16,
+    pub pce_id: u16,
+}
+
+/// How the certification data of a quote leads to its PCK certificate.
+#[derive(Debug, Clone)]
+pub enum PckCertificationData {
+    /// PEM PCK certificate chain embedded in the quote (type 5)
+    CertChain(Vec<u8>),
+    /// PPID-based certification data (types 1 to 3), the PCK certificate has to be looked up
+    Identifier(PckIdentifier),
+}
+
+/// Reads the PCK certificate chain or the PCK identifier from the quote's certification data.
+pub fn get_pck_certification_data(quote: &Quote) -> Result<PckCertificationData, QuoteParseError> {
+    let (cert_data_type, data) = quote.signature.cert_data.pck_cert_data();
+    let ppid_len = match cert_data_type {
+        PCK_CERT_CHAIN_CERT_DATA_TYPE => return Ok(PckCertificationData::CertChain(data.to_vec())),
+        PPID_CLEARTEXT_CERT_DATA_TYPE => 16,
+        PPID_RSA2048_ENCRYPTED_CERT_DATA_TYPE => 256,
+        PPID_RSA3072_ENCRYPTED_CERT_DATA_TYPE => 384,
+        other => return Err(QuoteParseError::UnsupportedPckCertDataType(other)),
+    };
+
+    // PPID, CPUSVN, PCESVN and PCE ID
+    let expected = ppid_len + 16 + 2 + 2;
+    if data.len() != expected {
+        return Err(QuoteParseError::InvalidCertDataSize {
+            cert_data_type,
+            size: data.len(),
+            expected,
+        });
     }
-    
-    let cert_data_offset = get_cert_data_offset(quote, offset);
-    let cert_data: Vec<u8> = (quote[cert_data_offset..]).to_vec();
 
-    let pem = parse_pem(&cert_data).expect("Failed to parse cert data");
-    let cert_chain = parse_certchain(&pem);
-    let pck = &cert_chain[0];
+    let mut cpu_svn = [0; 16];
+    cpu_svn.copy_from_slice(&data[ppid_len..ppid_len + 16]);
+    let pce_svn = u16::from_le_bytes([data[ppid_len + 16], data[ppid_len + 17]]);
+    let pce_id = u16::from_le_bytes([data[ppid_len + 18], data[ppid_len + 19]]);
+
+    // The Intel QE puts its QE ID in the first 16 bytes of the user data
+    let mut qe_id = [0; 16];
+    qe_id.copy_from_slice(&quote.header.user_data[..16]);
+
+    Ok(PckCertificationData::Identifier(PckIdentifier {
+        qe_id,
+        cpu_svn,
+        pce_svn,
+        pce_id,
+    }))
+}
+
+/// Returns the FMSPC and the issuing CA of the leaf of a PEM PCK certificate chain.
+pub fn get_pck_fmspc_and_issuer(
+    pck_cert_chain: &[u8],
+) -> Result<(String, CA, String), QuoteParseError> {
+    let pem = parse_pem(pck_cert_chain).map_err(|e| QuoteParseError::InvalidPem(e.to_string()))?;
+    let cert_chain = parse_certchain(&pem)?;
+    let pck = cert_chain.first().ok_or(QuoteParseError::EmptyCertChain)?;
+
+    let (pck_ca, pck_issuer) = get_pck_ca(pck)?;
+
+    let fmspc = hex::encode(PckExtensions::from_cert(pck)?.fmspc);
+
+    Ok((fmspc, pck_ca, pck_issuer))
+}
+
+/// Returns the PCK CA and its common name for a DER-encoded PCK certificate.
+pub fn get_pck_issuer(pck_der: &[u8]) -> Result<(CA, String), QuoteParseError> {
+    let (_, pck) = parse_x509_certificate(pck_der)
+        .map_err(|e| QuoteParseError::InvalidCertificate(e.to_string()))?;
+    get_pck_ca(&pck)
+}
 
-    let pck_issuer = get_x509_issuer_cn(pck);
+fn get_pck_ca(pck: &X509Certificate) -> Result<(CA, String), QuoteParseError> {
+    let pck_issuer = get_x509_issuer_cn(pck)?;
 
     let pck_ca = match pck_issuer.as_str() {
         "Intel SGX PCK Platform CA" => CA::PLATFORM,
         "Intel SGX PCK Processor CA" => CA::PROCESSOR,
-        _ => panic!("Unknown PCK Issuer"),
+        _ => return Err(QuoteParseError::UnknownPckIssuer(pck_issuer)),
     };
 
-    let fmspc_slice = extract_fmspc_from_extension(pck);
-    let fmspc = hex::encode(fmspc_slice);
+    Ok((pck_ca, pck_issuer))
+}
+
+/// The SGX extensions (OID 1.2.840.113741.1.13.1) of a PCK certificate.
+#[derive(Debug, Clone, Serialize)]
+pub struct PckExtensions {
+    #[serde(serialize_with = "as_hex")]
+    pub ppid: [u8; 16],
+    pub tcb: PckTcb,
+    #[serde(serialize_with = "as_hex")]
+    pub pce_id: [u8; 2],
+    #[serde(serialize_with = "as_hex")]
+    pub fmspc: [u8; 6],
+    /// 0: Standard, 1: Scalable, 2: Scalable with Integrity
+    pub sgx_type: u32,
+    /// Certificates issued by the Platform CA only
+    #[serde(serialize_with = "as_optional_hex")]
+    pub platform_instance_id: Option<[u8; 16]>,
+    /// Certificates issued by the Platform CA only
+    pub configuration: Option<PckConfiguration>,
+}
+
+/// The TCB the PCK certificate was issued for.
+#[derive(Debug, Clone, Serialize)]
+pub struct PckTcb {
+    /// SGX TCB Comp01 SVN to SGX TCB Comp16 SVN
+    pub sgx_tcb_comp_svns: [u8; 16],
+    pub pce_svn: u16,
+    #[serde(serialize_with = "as_hex")]
+    pub cpu_svn: [u8; 16],
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PckConfiguration {
+    pub dynamic_platform: Option<bool>,
+    pub cached_keys: Option<bool>,
+    pub smt_enabled: Option<bool>,
+}
+
+const SGX_EXTENSIONS_OID: &str = "1.2.840.113741.1.13.1";
+const SGX_TCB_OID: &str = "1.2.840.113741.1.13.1.2";
+const SGX_CONFIGURATION_OID: &str = "1.2.840.113741.1.13.1.7";
+
+/// Decodes the SGX extensions of a DER-encoded PCK certificate.
+pub fn get_pck_extensions(pck_der: &[u8]) -> Result<PckExtensions, QuoteParseError> {
+    let (_, pck) = parse_x509_certificate(pck_der)
+        .map_err(|e| QuoteParseError::InvalidCertificate(e.to_string()))?;
+    PckExtensions::from_cert(&pck)
+}
+
+impl PckExtensions {
+    fn from_cert(cert: &X509Certificate) -> Result<Self, QuoteParseError> {
+        let sgx_extensions_bytes = cert
+            .get_extension_unique(&oid!(1.2.840 .113741 .1 .13 .1))
+            .map_err(invalid_sgx_extensions)?
+            .ok_or(QuoteParseError::MissingSgxExtensions)?
+            .value;
+
+        let mut ppid = None;
+        let mut tcb = None;
+        let mut pce_id = None;
+        let mut fmspc = None;
+        let mut sgx_type = None;
+        let mut platform_instance_id = None;
+        let mut configuration = None;
+        for (oid, value) in oid_value_pairs(sgx_extensions_bytes)? {
+            let Some(index) = oid_index(&oid, SGX_EXTENSIONS_OID) else {
+                continue;
+            };
+            match index {
+                1 => ppid = Some(octets(&value, "PPID")?),
+                2 => tcb = Some(PckTcb::from_der(&value)?),
+                3 => pce_id = Some(octets(&value, "PCE-ID")?),
+                4 => fmspc = Some(octets(&value, "FMSPC")?),
+                5 => {
+                    let (_, value) =
+                        Enumerated::from_der(&value).map_err(invalid_sgx_extensions)?;
+                    sgx_type = Some(value.0)
+                }
+                6 => platform_instance_id = Some(octets(&value, "Platform Instance ID")?),
+                7 => configuration = Some(PckConfiguration::from_der(&value)?),
+                _ => {}
+            }
+        }
+
+        Ok(PckExtensions {
+            ppid: ppid.ok_or(QuoteParseError::MissingSgxExtension("PPID"))?,
+            tcb: tcb.ok_or(QuoteParseError::MissingSgxExtension("TCB"))?,
+            pce_id: pce_id.ok_or(QuoteParseError::MissingSgxExtension("PCE-ID"))?,
+            fmspc: fmspc.ok_or(QuoteParseError::MissingFmspc)?,
+            sgx_type: sgx_type.ok_or(QuoteParseError::MissingSgxExtension("SGX Type"))?,
+            platform_instance_id,
+            configuration,
+        })
+    }
+}
+
+impl PckTcb {
+    fn from_der(der: &[u8]) -> Result<Self, QuoteParseError> {
+        let mut sgx_tcb_comp_svns = [None; 16];
+        let mut pce_svn = None;
+        let mut cpu_svn = None;
+        for (oid, value) in oid_value_pairs(der)? {
+            match oid_index(&oid, SGX_TCB_OID) {
+                Some(index @ 1..=16) => {
+                    let (_, svn) = Integer::from_der(&value).map_err(invalid_sgx_extensions)?;
+                    sgx_tcb_comp_svns[index as usize - 1] =
+                        Some(svn.as_u8().map_err(invalid_sgx_extensions)?);
+                }
+                Some(17) => {
+                    let (_, svn) = Integer::from_der(&value).map_err(invalid_sgx_extensions)?;
+                    pce_svn = Some(svn.as_u16().map_err(invalid_sgx_extensions)?);
+                }
+                Some(18) => cpu_svn = Some(octets(&value, "CPUSVN")?),
+                _ => {}
+            }
+        }
+
+        let mut svns = [0; 16];
+        for (svn, found) in svns.iter_mut().zip(sgx_tcb_comp_svns) {
+            *svn = found.ok_or(QuoteParseError::MissingSgxExtension(
+                "SGX TCB component SVN",
+            ))?;
+        }
+        Ok(PckTcb {
+            sgx_tcb_comp_svns: svns,
+            pce_svn: pce_svn.ok_or(QuoteParseError::MissingSgxExtension("PCESVN"))?,
+            cpu_svn: cpu_svn.ok_or(QuoteParseError::MissingSgxExtension("CPUSVN"))?,
+        })
+    }
+}
+
+impl PckConfiguration {
+    fn from_der(der: &[u8]) -> Result<Self, QuoteParseError> {
+        let mut configuration = PckConfiguration {
+            dynamic_platform: None,
+            cached_keys: None,
+            smt_enabled: None,
+        };
+        for (oid, value) in oid_value_pairs(der)? {
+            let flag = match oid_index(&oid, SGX_CONFIGURATION_OID) {
+                Some(1) => &mut configuration.dynamic_platform,
+                Some(2) => &mut configuration.cached_keys,
+                Some(3) => &mut configuration.smt_enabled,
+                _ => continue,
+            };
+            let (_, value) = Boolean::from_der(&value).map_err(invalid_sgx_extensions)?;
+            *flag = Some(value.bool());
+        }
+        Ok(configuration)
+    }
+}
+
+/// Splits a DER `SEQUENCE OF SEQUENCE { OID, value }` into OIDs and DER-encoded values
+fn oid_value_pairs(der: &[u8]) -> Result<Vec<(String, Vec<u8>)>, QuoteParseError> {
+    let (_, sequence) = Sequence::from_der(der).map_err(invalid_sgx_extensions)?;
+
+    let mut pairs = Vec::new();
+    let mut i = sequence.content.as_ref();
+    while !i.is_empty() {
+        let (j, current_sequence) = Sequence::from_der(i).map_err(invalid_sgx_extensions)?;
+        i = j;
+        let (value, current_oid) =
+            Oid::from_der(current_sequence.content.as_ref()).map_err(invalid_sgx_extensions)?;
+        pairs.push((current_oid.to_id_string(), value.to_vec()));
+    }
+    Ok(pairs)
+}
 
-    (fmspc, pck_ca, pck_issuer)
+/// Returns the last arc of `oid` if it is directly under `parent`
+fn oid_index(oid: &str, parent: &str) -> Option<u32> {
+    oid.strip_prefix(parent)?.strip_prefix('.')?.parse().ok()
 }
 
-fn get_cert_data_offset(quote: &[u8], offset: usize) -> usize {
-    let auth_data_size = u16::from_le_bytes([
-        quote[offset],
-        quote[offset + 1],
-    ]);
+fn octets<const N: usize>(der: &[u8], name: &str) -> Result<[u8; N], QuoteParseError> {
+    let (rest, octets) = OctetString::from_der(der).map_err(invalid_sgx_extensions)?;
+    if !rest.is_empty() {
+        return Err(invalid_sgx_extensions(format!(
+            "trailing data after the {}",
+            name
+        )));
+    }
+    octets.as_ref().try_into().map_err(|_| {
+        invalid_sgx_extensions(format!(
+            "{} is {} bytes long, expected {}",
+            name,
+            octets.as_ref().len(),
+            N
+        ))
+    })
+}
+
+fn as_optional_hex<S: serde::Serializer, const N: usize>(
+    bytes: &Option<[u8; N]>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => as_hex(bytes, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn sgx_type_name(sgx_type: u32) -> &'static str {
+    match sgx_type {
+        0 => "Standard",
+        1 => "Scalable",
+        2 => "Scalable with Integrity",
+        _ => "unknown",
+    }
+}
+
+impl fmt::Display for PckExtensions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "SGX Extensions")?;
+        field(f, "PPID", hex::encode(self.ppid))?;
+        field(f, "FMSPC", hex::encode(self.fmspc))?;
+        field(f, "PCE-ID", hex::encode(self.pce_id))?;
+        field(
+            f,
+            "SGX Type",
+            format!("{} ({})", self.sgx_type, sgx_type_name(self.sgx_type)),
+        )?;
+        if let Some(platform_instance_id) = self.platform_instance_id {
+            field(f, "Platform Instance ID", hex::encode(platform_instance_id))?;
+        }
+
+        writeln!(f, "TCB")?;
+        for (i, svn) in self.tcb.sgx_tcb_comp_svns.iter().enumerate() {
+            field(f, &format!("SGX TCB Comp{:02} SVN", i + 1), svn)?;
+        }
+        field(f, "PCESVN", self.tcb.pce_svn)?;
+        field(f, "CPUSVN", hex::encode(self.tcb.cpu_svn))?;
 
-    offset + 2 + auth_data_size as usize + 2 + 4
+        if let Some(configuration) = &self.configuration {
+            writeln!(f, "Configuration")?;
+            let flags = [
+                ("Dynamic Platform", configuration.dynamic_platform),
+                ("Cached Keys", configuration.cached_keys),
+                ("SMT Enabled", configuration.smt_enabled),
+            ];
+            for (name, flag) in flags {
+                if let Some(flag) = flag {
+                    field(f, name, flag)?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
-fn parse_pem(raw_bytes: &[u8]) -> Result<Vec<Pem>, PEMError> {
+pub fn parse_pem(raw_bytes: &[u8]) -> Result<Vec<Pem>, PEMError> {
     Pem::iter_from_buffer(raw_bytes).collect()
 }
 
-fn parse_certchain<'a>(pem_certs: &'a [Pem]) -> Vec<X509Certificate<'a>> {
+pub fn parse_certchain<'a>(
+    pem_certs: &'a [Pem],
+) -> Result<Vec<X509Certificate<'a>>, QuoteParseError> {
     pem_certs
         .iter()
-        .map(|pem| pem.parse_x509().unwrap())
+        .map(|pem| {
+            pem.parse_x509()
+                .map_err(|e| QuoteParseError::InvalidCertificate(e.to_string()))
+        })
         .collect()
 }
 
-fn get_x509_issuer_cn(cert: &X509Certificate) -> String {
+fn get_x509_issuer_cn(cert: &X509Certificate) -> Result<String, QuoteParseError> {
     let issuer = cert.issuer();
-    let cn = issuer.iter_common_name().next().unwrap();
-    cn.as_str().unwrap().to_string()
+    let cn = issuer
+        .iter_common_name()
+        .next()
+        .ok_or(QuoteParseError::MissingIssuer)?;
+    let cn = cn.as_str().map_err(|_| QuoteParseError::MissingIssuer)?;
+    Ok(cn.to_string())
 }
 
-fn extract_fmspc_from_extension<'a>(cert: &'a X509Certificate<'a>) -> [u8; 6] {
-    let sgx_extensions_bytes = cert
-        .get_extension_unique(&oid!(1.2.840 .113741 .1 .13 .1))
-        .unwrap()
-        .unwrap()
-        .value;
-
-    let (_, sgx_extensions) = Sequence::from_der(sgx_extensions_bytes).unwrap();
+fn invalid_sgx_extensions(e: impl std::fmt::Display) -> QuoteParseError {
+    QuoteParseError::InvalidSgxExtensions(e.to_string())
+}
 
-    let mut fmspc = [0; 6];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pem_chain_to_der;
 
-    let mut i = sgx_extensions.content.as_ref();
+    const QUOTE_HEX: &str = include_str!("../../data/quote.hex");
 
-    while i.len() > 0 {
-        let (j, current_sequence) = Sequence::from_der(i).unwrap();
-        i = j;
-        let (j, current_oid) = Oid::from_der(current_sequence.content.as_ref()).unwrap();
-        match current_oid.to_id_string().as_str() {
-            "1.2.840.113741.1.13.1.4" => {
-                let (k, fmspc_bytes) = OctetString::from_der(j).unwrap();
-                assert_eq!(k.len(), 0);
-                fmspc.copy_from_slice(fmspc_bytes.as_ref());
-                break;
-            }
-            _ => continue,
+    /// The DER PCK certificate chain of the sample quote, issued by the Platform CA
+    fn pck_cert_chain() -> Vec<Vec<u8>> {
+        let quote = Quote::from_bytes(&hex::decode(QUOTE_HEX.trim()).unwrap()).unwrap();
+        match get_pck_certification_data(&quote).unwrap() {
+            PckCertificationData::CertChain(chain) => pem_chain_to_der(&chain).unwrap(),
+            PckCertificationData::Identifier(_) => panic!("The sample quote embeds its PCK chain"),
         }
     }
 
-    fmspc
+    #[test]
+    fn decodes_platform_ca_pck_extensions() {
+        let chain = pck_cert_chain();
+        let (ca, issuer) = get_pck_issuer(&chain[0]).unwrap();
+        assert!(matches!(ca, CA::PLATFORM));
+        assert_eq!(issuer, "Intel SGX PCK Platform CA");
+
+        let extensions = get_pck_extensions(&chain[0]).unwrap();
+        assert_eq!(
+            hex::encode(extensions.ppid),
+            "8b14ca31fb0e964986048fb3b7394977"
+        );
+        assert_eq!(
+            extensions.tcb.sgx_tcb_comp_svns,
+            [2, 2, 2, 2, 3, 1, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(extensions.tcb.pce_svn, 13);
+        assert_eq!(
+            hex::encode(extensions.tcb.cpu_svn),
+            "02020202030100030000000000000000"
+        );
+        assert_eq!(extensions.pce_id, [0, 0]);
+        assert_eq!(hex::encode(extensions.fmspc), "90c06f000000");
+        assert_eq!(sgx_type_name(extensions.sgx_type), "Scalable");
+
+        // Only in certificates of the Platform CA
+        assert_eq!(
+            hex::encode(extensions.platform_instance_id.unwrap()),
+            "98c8b2e2bf708128075ac8567d86bd5c"
+        );
+        let configuration = extensions.configuration.unwrap();
+        assert_eq!(configuration.dynamic_platform, Some(true));
+        assert_eq!(configuration.cached_keys, Some(true));
+        assert_eq!(configuration.smt_enabled, Some(true));
+    }
+
+    #[test]
+    fn fails_on_certificates_without_sgx_extensions() {
+        let chain = pck_cert_chain();
+        assert!(matches!(
+            get_pck_extensions(&chain[1]),
+            Err(QuoteParseError::MissingSgxExtensions)
+        ));
+        assert!(matches!(
+            get_pck_extensions(b"not a certificate"),
+            Err(QuoteParseError::InvalidCertificate(_))
+        ));
+    }
 }
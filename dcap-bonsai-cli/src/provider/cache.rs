@@ -0,0 +1,568 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use x509_parser::prelude::*;
+
+use super::dir::{ENCLAVE_IDENTITY_KEY, TCB_INFO_KEY};
+use super::{ca_name, CollateralProvider, CollateralRequest};
+use crate::chain::pccs::{enclave_id::EnclaveIdType, pcs::IPCSDao::CA};
+use crate::collaterals::Collaterals;
+use crate::config::ChainConfig;
+use crate::remove_prefix_if_found;
+use crate::verify::signature::verify_collateral_signatures;
+
+/// A cached collateral, valid until `next_update` (seconds since epoch).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub key: String,
+    pub fetched_at: i64,
+    pub next_update: i64,
+    /// Hex-encoded collateral items, e.g. a certificate followed by its CRL
+    pub data: Vec<String>,
+}
+
+impl CacheEntry {
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.next_update
+    }
+}
+
+/// On-disk collateral cache, one JSON file per entry.
+pub struct CollateralCache {
+    dir: PathBuf,
+}
+
+impl CollateralCache {
+    pub fn new(dir: &Path) -> Self {
+        CollateralCache {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Opens the cache under the user's cache directory, i.e. `$XDG_CACHE_HOME/<crate>/collaterals`
+    pub fn default_dir() -> Result<PathBuf> {
+        let cache_dir = dirs::cache_dir()
+            .ok_or_else(|| anyhow::Error::msg("Unable to determine the user cache directory"))?;
+        Ok(cache_dir.join(env!("CARGO_PKG_NAME")).join("collaterals"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let file_name: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.json", file_name))
+    }
+
+    /// Returns the cached items for `key` unless the entry is missing, unreadable or expired
+    pub fn get(&self, key: &str) -> Option<Vec<Vec<u8>>> {
+        let raw = fs::read(self.path(key)).ok()?;
+        let entry: CacheEntry = serde_json::from_slice(&raw).ok()?;
+        if entry.key != key || entry.is_expired(now()) {
+            return None;
+        }
+        entry
+            .data
+            .iter()
+            .map(|item| hex::decode(item).ok())
+            .collect()
+    }
+
+    pub fn put(&self, key: &str, next_update: i64, data: &[&[u8]]) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let entry = CacheEntry {
+            key: key.to_string(),
+            fetched_at: now(),
+            next_update,
+            data: data.iter().map(hex::encode).collect(),
+        };
+        fs::write(self.path(key), serde_json::to_vec_pretty(&entry)?)?;
+        Ok(())
+    }
+
+    /// Lists all readable entries, ordered by key
+    pub fn list(&self) -> Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        if !self.dir.exists() {
+            return Ok(entries);
+        }
+        for file in fs::read_dir(&self.dir)? {
+            let path = file?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            if let Ok(entry) = serde_json::from_slice::<CacheEntry>(&fs::read(&path)?) {
+                entries.push(entry);
+            }
+        }
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(entries)
+    }
+
+    /// Removes cached entries, or only the expired ones, and returns how many were removed
+    pub fn clear(&self, expired_only: bool) -> Result<usize> {
+        let now = now();
+        let mut removed = 0;
+        for entry in self.list()? {
+            if !expired_only || entry.is_expired(now) {
+                fs::remove_file(self.path(&entry.key))?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+pub fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// Reads `nextUpdate` from a TCBInfo or QEIdentity in Intel's signed format
+pub fn json_next_update(collateral: &[u8], body_key: &str) -> Result<i64> {
+    json_date(collateral, body_key, "nextUpdate")
+}
+
+/// Reads `issueDate` from a TCBInfo or QEIdentity in Intel's signed format
+pub fn json_issue_date(collateral: &[u8], body_key: &str) -> Result<i64> {
+    json_date(collateral, body_key, "issueDate")
+}
+
+fn json_date(collateral: &[u8], body_key: &str, date_key: &str) -> Result<i64> {
+    let json: serde_json::Value = serde_json::from_slice(collateral)?;
+    let date = json[body_key][date_key]
+        .as_str()
+        .ok_or_else(|| anyhow::Error::msg(format!("{} has no {}", body_key, date_key)))?;
+    Ok(chrono::DateTime::parse_from_rfc3339(date)?.timestamp())
+}
+
+/// Reads `nextUpdate` from a DER-encoded CRL
+pub fn crl_next_update(der: &[u8]) -> Result<i64> {
+    let (_, crl) = parse_x509_crl(der).map_err(|e| anyhow::Error::msg(e.to_string()))?;
+    let next_update = crl
+        .next_update()
+        .ok_or_else(|| anyhow::Error::msg("CRL has no nextUpdate"))?;
+    Ok(next_update.timestamp())
+}
+
+/// Reads `thisUpdate` from a DER-encoded CRL
+pub fn crl_this_update(der: &[u8]) -> Result<i64> {
+    let (_, crl) = parse_x509_crl(der).map_err(|e| anyhow::Error::msg(e.to_string()))?;
+    Ok(crl.last_update().timestamp())
+}
+
+/// Reads `notAfter` from a DER-encoded certificate
+pub fn cert_not_after(der: &[u8]) -> Result<i64> {
+    let (_, cert) = parse_x509_certificate(der).map_err(|e| anyhow::Error::msg(e.to_string()))?;
+    Ok(cert.validity().not_after.timestamp())
+}
+
+/// Caches what the on-chain PCCS returns, keyed by the chain ID, the DAO address and the
+/// collateral identifiers, until the collateral's own `nextUpdate`.
+///
+/// Only whole collateral sets whose signatures verify are cached. Single collaterals are
+/// served from the cache, but not cached when read, since they cannot be verified on their own.
+pub struct CachedProvider {
+    inner: Box<dyn CollateralProvider>,
+    cache: CollateralCache,
+    chain_id: u64,
+    pcs_dao: String,
+    fmspc_tcb_dao: String,
+    enclave_id_dao: String,
+}
+
+impl CachedProvider {
+    pub fn new(
+        inner: Box<dyn CollateralProvider>,
+        cache: CollateralCache,
+        chain_id: u64,
+        config: &ChainConfig,
+    ) -> Self {
+        let normalize = |address: &str| remove_prefix_if_found(address).to_lowercase();
+        CachedProvider {
+            inner,
+            cache,
+            chain_id,
+            pcs_dao: normalize(&config.pcs_dao),
+            fmspc_tcb_dao: normalize(&config.fmspc_tcb_dao),
+            enclave_id_dao: normalize(&config.enclave_id_dao),
+        }
+    }
+
+    fn store(&self, key: &str, next_update: Result<i64>, data: &[&[u8]]) {
+        let stored = next_update.and_then(|next_update| self.cache.put(key, next_update, data));
+        if let Err(e) = stored {
+            log::warn!("Not caching {}: {}", key, e);
+        }
+    }
+
+    fn root_ca_key(&self) -> String {
+        format!("chain{}-pcs-{}-ca-root", self.chain_id, self.pcs_dao)
+    }
+
+    fn signing_ca_key(&self) -> String {
+        format!("chain{}-pcs-{}-ca-signing", self.chain_id, self.pcs_dao)
+    }
+
+    fn pck_crl_key(&self, ca: CA) -> String {
+        format!(
+            "chain{}-pcs-{}-crl-{}",
+            self.chain_id,
+            self.pcs_dao,
+            ca_name(ca)
+        )
+    }
+
+    fn tcb_info_key(&self, tcb_type: u8, fmspc: &str, version: u32) -> String {
+        format!(
+            "chain{}-fmspc-tcb-{}-type{}-{}-v{}",
+            self.chain_id,
+            self.fmspc_tcb_dao,
+            tcb_type,
+            fmspc.to_lowercase(),
+            version
+        )
+    }
+
+    fn qe_identity_key(&self, id: EnclaveIdType, version: u32) -> String {
+        format!(
+            "chain{}-enclave-id-{}-{:?}-v{}",
+            self.chain_id, self.enclave_id_dao, id, version
+        )
+        .to_lowercase()
+    }
+
+    fn get_one(&self, key: &str) -> Option<Vec<u8>> {
+        let mut items = self.cache.get(key).filter(|items| items.len() == 1)?;
+        Some(items.remove(0))
+    }
+
+    fn cached_root_ca(&self) -> Option<(Vec<u8>, Vec<u8>)> {
+        let mut items = self
+            .cache
+            .get(&self.root_ca_key())
+            .filter(|items| items.len() == 2)?;
+        let root_ca_crl = items.pop().unwrap();
+        Some((items.pop().unwrap(), root_ca_crl))
+    }
+
+    fn store_collaterals(&self, request: &CollateralRequest, collaterals: &Collaterals) {
+        self.store(
+            &self.root_ca_key(),
+            crl_next_update(&collaterals.root_ca_crl),
+            &[&collaterals.root_ca, &collaterals.root_ca_crl],
+        );
+        self.store(
+            &self.signing_ca_key(),
+            cert_not_after(&collaterals.tcb_signing_ca),
+            &[&collaterals.tcb_signing_ca],
+        );
+        self.store(
+            &self.pck_crl_key(request.pck_ca),
+            crl_next_update(&collaterals.pck_crl),
+            &[&collaterals.pck_crl],
+        );
+        self.store(
+            &self.tcb_info_key(request.tcb_type, &request.fmspc, request.tcb_version),
+            json_next_update(&collaterals.tcb_info, TCB_INFO_KEY),
+            &[&collaterals.tcb_info],
+        );
+        self.store(
+            &self.qe_identity_key(request.qe_id_type, request.qe_id_version),
+            json_next_update(&collaterals.qe_identity, ENCLAVE_IDENTITY_KEY),
+            &[&collaterals.qe_identity],
+        );
+    }
+}
+
+#[async_trait]
+impl CollateralProvider for CachedProvider {
+    fn name(&self) -> String {
+        format!(
+            "{} (cached in {})",
+            self.inner.name(),
+            self.cache.dir().display()
+        )
+    }
+
+    async fn root_ca(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+        if let Some(cached) = self.cached_root_ca() {
+            log::info!("Using cached Intel SGX Root CA and CRL");
+            return Ok(cached);
+        }
+
+        self.inner.root_ca().await
+    }
+
+    async fn signing_ca(&self) -> Result<Vec<u8>> {
+        if let Some(cached) = self.get_one(&self.signing_ca_key()) {
+            log::info!("Using cached Intel TCB Signing CA");
+            return Ok(cached);
+        }
+
+        self.inner.signing_ca().await
+    }
+
+    async fn pck_crl(&self, ca: CA) -> Result<Vec<u8>> {
+        if let Some(cached) = self.get_one(&self.pck_crl_key(ca)) {
+            log::info!("Using cached {} PCK CRL", ca_name(ca));
+            return Ok(cached);
+        }
+
+        self.inner.pck_crl(ca).await
+    }
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<Vec<u8>> {
+        if let Some(cached) = self.get_one(&self.tcb_info_key(tcb_type, fmspc, version)) {
+            log::info!("Using cached TCBInfo for FMSPC: {}", fmspc);
+            return Ok(cached);
+        }
+
+        self.inner.tcb_info(tcb_type, fmspc, version).await
+    }
+
+    async fn qe_identity(&self, id: EnclaveIdType, version: u32) -> Result<Vec<u8>> {
+        if let Some(cached) = self.get_one(&self.qe_identity_key(id, version)) {
+            log::info!("Using cached {:?} identity", id);
+            return Ok(cached);
+        }
+
+        self.inner.qe_identity(id, version).await
+    }
+
+    async fn collaterals(&self, request: &CollateralRequest) -> Result<Collaterals> {
+        let cached = (
+            self.cached_root_ca(),
+            self.get_one(&self.signing_ca_key()),
+            self.get_one(&self.pck_crl_key(request.pck_ca)),
+            self.get_one(&self.tcb_info_key(request.tcb_type, &request.fmspc, request.tcb_version)),
+            self.get_one(&self.qe_identity_key(request.qe_id_type, request.qe_id_version)),
+        );
+        if let (
+            Some((root_ca, root_ca_crl)),
+            Some(signing_ca),
+            Some(pck_crl),
+            Some(tcb_info),
+            Some(qe_identity),
+        ) = cached
+        {
+            log::info!("Using cached collaterals");
+            return Ok(Collaterals::new(
+                tcb_info,
+                qe_identity,
+                root_ca,
+                signing_ca,
+                root_ca_crl,
+                pck_crl,
+            ));
+        }
+
+        // Refresh the whole set at once so that the source can read it consistently
+        let collaterals = self.inner.collaterals(request).await?;
+        // A set that fails verification would otherwise be served until its nextUpdate
+        match verify_collateral_signatures(&collaterals) {
+            Ok(()) => self.store_collaterals(request, &collaterals),
+            Err(e) => log::warn!("Not caching the collaterals: {}", e),
+        }
+        Ok(collaterals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::pem_chain_to_der;
+    use crate::provider::fallback::FallbackProvider;
+
+    // Signed by the test Signing CA, issued by the test Root CA
+    const TCB_INFO: &[u8] = include_bytes!("../../../data/collaterals/tcb_info_v3_tdx.json");
+    const QE_IDENTITY: &[u8] = include_bytes!("../../../data/collaterals/td_qe_identity_v2.json");
+    const TEST_ROOT_CA: &[u8] = include_bytes!("../../../data/collaterals/test_root_ca.pem");
+    const TEST_SIGNING_CA: &[u8] =
+        include_bytes!("../../../data/collaterals/test_tcb_signing_ca.pem");
+    const TEST_ROOT_CA_CRL: &[u8] =
+        include_bytes!("../../../data/collaterals/test_root_ca_crl.pem");
+
+    /// Serves the fixtures with the test PKI, or with empty certificates so that their
+    /// signatures cannot verify, and counts the reads
+    struct Source {
+        signed: bool,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl Source {
+        fn new(signed: bool) -> (Box<dyn CollateralProvider>, Arc<AtomicUsize>) {
+            let reads = Arc::new(AtomicUsize::new(0));
+            let source = Source {
+                signed,
+                reads: reads.clone(),
+            };
+            (Box::new(source), reads)
+        }
+
+        fn read(&self, pem: &[u8]) -> Vec<u8> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            if self.signed {
+                pem_chain_to_der(pem).unwrap().remove(0)
+            } else {
+                Vec::new()
+            }
+        }
+    }
+
+    #[async_trait]
+    impl CollateralProvider for Source {
+        fn name(&self) -> String {
+            "source".to_string()
+        }
+
+        async fn root_ca(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+            Ok((self.read(TEST_ROOT_CA), self.read(TEST_ROOT_CA_CRL)))
+        }
+
+        async fn signing_ca(&self) -> Result<Vec<u8>> {
+            Ok(self.read(TEST_SIGNING_CA))
+        }
+
+        async fn pck_crl(&self, _ca: CA) -> Result<Vec<u8>> {
+            Ok(self.read(TEST_ROOT_CA_CRL))
+        }
+
+        async fn tcb_info(&self, _tcb_type: u8, _fmspc: &str, _version: u32) -> Result<Vec<u8>> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            Ok(TCB_INFO.to_vec())
+        }
+
+        async fn qe_identity(&self, _id: EnclaveIdType, _version: u32) -> Result<Vec<u8>> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            Ok(QE_IDENTITY.to_vec())
+        }
+    }
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dcap-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn cached(dir: &Path, chain_id: u64, signed: bool) -> (CachedProvider, Arc<AtomicUsize>) {
+        let (source, reads) = Source::new(signed);
+        let cached = CachedProvider::new(
+            source,
+            CollateralCache::new(dir),
+            chain_id,
+            &ChainConfig::default(),
+        );
+        (cached, reads)
+    }
+
+    fn request() -> CollateralRequest {
+        CollateralRequest {
+            fmspc: "90C06F000000".to_string(),
+            pck_ca: CA::PLATFORM,
+            tcb_type: 1,
+            tcb_version: 3,
+            qe_id_type: EnclaveIdType::TDQE,
+            qe_id_version: 2,
+        }
+    }
+
+    #[tokio::test]
+    async fn keys_cached_collaterals_by_chain_id() {
+        let dir = cache_dir("chain-id");
+        let (chain_a, _) = cached(&dir, 1, false);
+        let (chain_b, _) = cached(&dir, 2, false);
+        let key = chain_a.tcb_info_key(1, "90C06F000000", 3);
+        assert!(key.starts_with("chain1-"));
+        assert_ne!(key, chain_b.tcb_info_key(1, "90C06F000000", 3));
+
+        chain_a.cache.put(&key, now() + 3600, &[b"cached"]).unwrap();
+        assert_eq!(
+            chain_a.tcb_info(1, "90C06F000000", 3).await.unwrap(),
+            b"cached"
+        );
+        // The other chain's DAO may hold other collaterals at the same address
+        assert_eq!(
+            chain_b.tcb_info(1, "90C06F000000", 3).await.unwrap(),
+            TCB_INFO
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn does_not_cache_unverified_collaterals() {
+        let dir = cache_dir("unverified");
+        let (cached, _) = cached(&dir, 1, false);
+
+        let collaterals = cached.collaterals(&request()).await.unwrap();
+        assert_eq!(collaterals.tcb_info, TCB_INFO);
+        assert!(cached.cache.list().unwrap().is_empty());
+        // Reading a single collateral does not cache it either
+        cached.tcb_info(1, "90C06F000000", 3).await.unwrap();
+        assert!(cached.cache.list().unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn caches_verified_collaterals_read_through_fallback() {
+        let dir = cache_dir("fallback");
+        let (cached, reads) = cached(&dir, 1, true);
+        let (pcs, pcs_reads) = Source::new(true);
+        let fallback = FallbackProvider::new(vec![Box::new(cached), pcs]);
+
+        let collaterals = fallback.collaterals(&request()).await.unwrap();
+        assert_eq!(collaterals.tcb_info, TCB_INFO);
+        assert_eq!(reads.load(Ordering::SeqCst), 6);
+        assert_eq!(pcs_reads.load(Ordering::SeqCst), 0);
+        // Served from the cache from now on
+        fallback.signing_ca().await.unwrap();
+        assert_eq!(reads.load(Ordering::SeqCst), 6);
+
+        let keys: Vec<String> = CollateralCache::new(&dir)
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect();
+        let defaults = ChainConfig::default();
+        let dao_of = |address: &str| remove_prefix_if_found(address).to_lowercase();
+        assert_eq!(
+            keys,
+            [
+                format!(
+                    "chain1-enclave-id-{}-tdqe-v2",
+                    dao_of(&defaults.enclave_id_dao)
+                ),
+                format!(
+                    "chain1-fmspc-tcb-{}-type1-90c06f000000-v3",
+                    dao_of(&defaults.fmspc_tcb_dao)
+                ),
+                format!("chain1-pcs-{}-ca-root", dao_of(&defaults.pcs_dao)),
+                format!("chain1-pcs-{}-ca-signing", dao_of(&defaults.pcs_dao)),
+                format!("chain1-pcs-{}-crl-platform", dao_of(&defaults.pcs_dao)),
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,209 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::{CollateralProvider, CollateralRequest};
+use crate::chain::pccs::{enclave_id::EnclaveIdType, pcs::IPCSDao::CA};
+use crate::collaterals::Collaterals;
+
+/// Queries each provider in order and returns the first collateral that was found.
+///
+/// A whole collateral set is read from a single provider, so that a source that reads
+/// everything at once, like the on-chain PCCS, does so.
+pub struct FallbackProvider {
+    providers: Vec<Box<dyn CollateralProvider>>,
+}
+
+impl FallbackProvider {
+    pub fn new(providers: Vec<Box<dyn CollateralProvider>>) -> Self {
+        FallbackProvider { providers }
+    }
+}
+
+macro_rules! first_ok {
+    ($self:ident, $what:expr, |$provider:ident| $call:expr) => {{
+        let mut errors = Vec::new();
+        for $provider in $self.providers.iter() {
+            match $call.await {
+                Ok(collateral) => {
+                    log::info!("Fetched {} from {}", $what, $provider.name());
+                    return Ok(collateral);
+                }
+                Err(e) => {
+                    log::warn!("Failed to fetch {} from {}: {}", $what, $provider.name(), e);
+                    errors.push(format!("{}: {}", $provider.name(), e));
+                }
+            }
+        }
+        Err(anyhow::Error::msg(format!(
+            "Failed to fetch {} from any source [{}]",
+            $what,
+            errors.join("; ")
+        )))
+    }};
+}
+
+#[async_trait]
+impl CollateralProvider for FallbackProvider {
+    fn name(&self) -> String {
+        let names: Vec<String> = self.providers.iter().map(|p| p.name()).collect();
+        names.join(" -> ")
+    }
+
+    async fn root_ca(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+        first_ok!(self, "Intel SGX Root CA", |provider| provider.root_ca())
+    }
+
+    async fn signing_ca(&self) -> Result<Vec<u8>> {
+        first_ok!(self, "Intel TCB Signing CA", |provider| provider
+            .signing_ca())
+    }
+
+    async fn pck_crl(&self, ca: CA) -> Result<Vec<u8>> {
+        first_ok!(self, "PCK CRL", |provider| provider.pck_crl(ca))
+    }
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<Vec<u8>> {
+        first_ok!(self, format!("TCBInfo for FMSPC {}", fmspc), |provider| {
+            provider.tcb_info(tcb_type, fmspc, version)
+        })
+    }
+
+    async fn qe_identity(&self, id: EnclaveIdType, version: u32) -> Result<Vec<u8>> {
+        first_ok!(self, format!("{:?} identity", id), |provider| provider
+            .qe_identity(id, version))
+    }
+
+    async fn collaterals(&self, request: &CollateralRequest) -> Result<Collaterals> {
+        first_ok!(self, "collaterals", |provider| provider
+            .collaterals(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Calls {
+        collaterals: AtomicUsize,
+        single: AtomicUsize,
+    }
+
+    /// Serves collaterals tagged with its name, or fails, and counts the calls made to it
+    struct Source {
+        name: &'static str,
+        fails: bool,
+        calls: Arc<Calls>,
+    }
+
+    impl Source {
+        fn new(name: &'static str, fails: bool) -> (Box<dyn CollateralProvider>, Arc<Calls>) {
+            let calls = Arc::new(Calls::default());
+            let source = Source {
+                name,
+                fails,
+                calls: calls.clone(),
+            };
+            (Box::new(source), calls)
+        }
+
+        fn single(&self) -> Result<Vec<u8>> {
+            self.calls.single.fetch_add(1, Ordering::SeqCst);
+            Ok(self.name.as_bytes().to_vec())
+        }
+    }
+
+    #[async_trait]
+    impl CollateralProvider for Source {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        async fn root_ca(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+            Ok((self.single()?, self.single()?))
+        }
+
+        async fn signing_ca(&self) -> Result<Vec<u8>> {
+            self.single()
+        }
+
+        async fn pck_crl(&self, _ca: CA) -> Result<Vec<u8>> {
+            self.single()
+        }
+
+        async fn tcb_info(&self, _tcb_type: u8, _fmspc: &str, _version: u32) -> Result<Vec<u8>> {
+            self.single()
+        }
+
+        async fn qe_identity(&self, _id: EnclaveIdType, _version: u32) -> Result<Vec<u8>> {
+            self.single()
+        }
+
+        async fn collaterals(&self, _request: &CollateralRequest) -> Result<Collaterals> {
+            self.calls.collaterals.fetch_add(1, Ordering::SeqCst);
+            if self.fails {
+                return Err(anyhow::Error::msg("not found"));
+            }
+            let tag = self.name.as_bytes().to_vec();
+            Ok(Collaterals::new(
+                tag.clone(),
+                tag.clone(),
+                tag.clone(),
+                tag.clone(),
+                tag.clone(),
+                tag,
+            ))
+        }
+    }
+
+    fn request() -> CollateralRequest {
+        CollateralRequest::new(4, 0, "90C06F000000", CA::PLATFORM)
+    }
+
+    fn count(calls: &Calls) -> (usize, usize) {
+        (
+            calls.collaterals.load(Ordering::SeqCst),
+            calls.single.load(Ordering::SeqCst),
+        )
+    }
+
+    #[tokio::test]
+    async fn reads_whole_set_from_first_source() {
+        let (onchain, onchain_calls) = Source::new("onchain", false);
+        let (pcs, pcs_calls) = Source::new("pcs", false);
+        let fallback = FallbackProvider::new(vec![onchain, pcs]);
+
+        let collaterals = fallback.collaterals(&request()).await.unwrap();
+        assert_eq!(collaterals.tcb_info, b"onchain");
+        assert_eq!(count(&onchain_calls), (1, 0));
+        assert_eq!(count(&pcs_calls), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_next_source_for_whole_set() {
+        let (onchain, onchain_calls) = Source::new("onchain", true);
+        let (pcs, pcs_calls) = Source::new("pcs", false);
+        let fallback = FallbackProvider::new(vec![onchain, pcs]);
+
+        let collaterals = fallback.collaterals(&request()).await.unwrap();
+        assert_eq!(collaterals.pck_crl, b"pcs");
+        assert_eq!(count(&onchain_calls), (1, 0));
+        assert_eq!(count(&pcs_calls), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn reports_every_failed_source() {
+        let (onchain, _) = Source::new("onchain", true);
+        let (pcs, _) = Source::new("pcs", true);
+        let fallback = FallbackProvider::new(vec![onchain, pcs]);
+
+        let err = fallback.collaterals(&request()).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to fetch collaterals from any source [onchain: not found; pcs: not found]"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+pub mod cache;
+pub mod dir;
+pub mod fallback;
+pub mod http;
+pub mod onchain;
+pub mod overrides;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::future::Future;
+use std::time::Instant;
+
+use crate::chain::pccs::{enclave_id::EnclaveIdType, pcs::IPCSDao::CA};
+use crate::collaterals::Collaterals;
+use crate::constants::TDX_TEE_TYPE;
+
+/// A source of the Intel collaterals needed to verify a DCAP quote.
+///
+/// Certificates and CRLs are returned DER-encoded. TCBInfo and QEIdentity are returned
+/// in Intel's signed JSON format, i.e. `{"tcbInfo": {...}, "signature": "..."}`.
+#[async_trait]
+pub trait CollateralProvider: Send + Sync {
+    /// Short human-readable name of the source, used in logs
+    fn name(&self) -> String;
+
+    /// Returns the Intel SGX Root CA certificate and the Root CA CRL
+    async fn root_ca(&self) -> Result<(Vec<u8>, Vec<u8>)>;
+
+    /// Returns the Intel TCB Signing CA certificate
+    async fn signing_ca(&self) -> Result<Vec<u8>>;
+
+    /// Returns the CRL issued by the given PCK CA (`CA::PLATFORM` or `CA::PROCESSOR`)
+    async fn pck_crl(&self, ca: CA) -> Result<Vec<u8>>;
+
+    /// Returns the TCBInfo for the given TCB type (0: SGX, 1: TDX), FMSPC and TCBInfo version
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<Vec<u8>>;
+
+    /// Returns the identity of the given enclave for the given PCS API version
+    async fn qe_identity(&self, id: EnclaveIdType, version: u32) -> Result<Vec<u8>>;
+
+    /// Returns all collaterals for `request`. By default the individual reads run
+    /// concurrently; sources that can read everything at once override this.
+    async fn collaterals(&self, request: &CollateralRequest) -> Result<Collaterals> {
+        fetch_each(self, request).await
+    }
+}
+
+pub fn ca_name(ca: CA) -> &'static str {
+    match ca {
+        CA::ROOT => "root",
+        CA::PROCESSOR => "processor",
+        CA::PLATFORM => "platform",
+        CA::SIGNING => "signing",
+        _ => unreachable!(),
+    }
+}
+
+/// Identifies the collaterals needed to verify a particular quote.
+#[derive(Debug, Clone)]
+pub struct CollateralRequest {
+    pub fmspc: String,
+    pub pck_ca: CA,
+    pub tcb_type: u8,
+    pub tcb_version: u32,
+    pub qe_id_type: EnclaveIdType,
+    pub qe_id_version: u32,
+}
+
+impl CollateralRequest {
+    pub fn new(quote_version: u16, tee_type: u32, fmspc: &str, pck_ca: CA) -> Self {
+        let (tcb_type, qe_id_type) = if tee_type == TDX_TEE_TYPE {
+            (1, EnclaveIdType::TDQE)
+        } else {
+            (0, EnclaveIdType::QE)
+        };
+        let tcb_version = if quote_version < 4 { 2 } else { 3 };
+        // v5 quotes use the same QEIdentity as v4 quotes, there is no v5 API
+        let qe_id_version = quote_version.min(4) as u32;
+
+        CollateralRequest {
+            fmspc: fmspc.to_string(),
+            pck_ca,
+            tcb_type,
+            tcb_version,
+            qe_id_type,
+            qe_id_version,
+        }
+    }
+}
+
+/// Fetches all collaterals for `request` from `provider`, logging how long it took.
+pub async fn fetch_collaterals(
+    provider: &dyn CollateralProvider,
+    request: &CollateralRequest,
+) -> Result<Collaterals> {
+    let start = Instant::now();
+    let collaterals = provider.collaterals(request).await?;
+    log::info!(
+        "Fetched all collaterals in {} ms",
+        start.elapsed().as_millis()
+    );
+    Ok(collaterals)
+}
+
+/// Runs the individual collateral reads concurrently, logging how long each read took.
+pub async fn fetch_each<P: CollateralProvider + ?Sized>(
+    provider: &P,
+    request: &CollateralRequest,
+) -> Result<Collaterals> {
+    let pck_crl_what = format!("Intel PCK CRL ({})", ca_name(request.pck_ca));
+    let tcb_info_what = format!("TCBInfo for FMSPC: {}", request.fmspc);
+    let qe_identity_what = format!("{:?} identity", request.qe_id_type);
+    let ((root_ca, root_ca_crl), signing_ca, pck_crl, tcb_info, qe_identity) = tokio::try_join!(
+        timed("Intel SGX Root CA and CRL", provider.root_ca()),
+        timed("Intel TCB Signing CA", provider.signing_ca()),
+        timed(&pck_crl_what, provider.pck_crl(request.pck_ca)),
+        timed(
+            &tcb_info_what,
+            provider.tcb_info(request.tcb_type, &request.fmspc, request.tcb_version)
+        ),
+        timed(
+            &qe_identity_what,
+            provider.qe_identity(request.qe_id_type, request.qe_id_version)
+        ),
+    )?;
+
+    Ok(Collaterals::new(
+        tcb_info,
+        qe_identity,
+        root_ca,
+        signing_ca,
+        root_ca_crl,
+        pck_crl,
+    ))
+}
+
+async fn timed<T>(what: &str, future: impl Future<Output = Result<T>>) -> Result<T> {
+    let start = Instant::now();
+    let result = future.await;
+    match &result {
+        Ok(_) => log::info!("Fetched {} in {} ms", what, start.elapsed().as_millis()),
+        Err(e) => log::warn!(
+            "Failed to fetch {} after {} ms: {}",
+            what,
+            start.elapsed().as_millis(),
+            e
+        ),
+    }
+    result
+}
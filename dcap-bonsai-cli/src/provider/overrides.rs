@@ -0,0 +1,259 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::PathBuf;
+
+use super::dir::{read_cert_file, read_signed_json_file, ENCLAVE_IDENTITY_KEY, TCB_INFO_KEY};
+use super::{fetch_each, CollateralProvider, CollateralRequest};
+use crate::chain::pccs::{enclave_id::EnclaveIdType, pcs::IPCSDao::CA};
+use crate::collaterals::Collaterals;
+
+/// Individual collateral files that take the place of what a provider would return.
+#[derive(Debug, Clone, Default)]
+pub struct CollateralFiles {
+    pub tcb_info: Option<PathBuf>,
+    pub qe_identity: Option<PathBuf>,
+    pub root_ca: Option<PathBuf>,
+    pub root_ca_crl: Option<PathBuf>,
+    pub signing_ca: Option<PathBuf>,
+    pub pck_crl: Option<PathBuf>,
+}
+
+impl CollateralFiles {
+    pub fn is_empty(&self) -> bool {
+        self.tcb_info.is_none()
+            && self.qe_identity.is_none()
+            && self.root_ca.is_none()
+            && self.root_ca_crl.is_none()
+            && self.signing_ca.is_none()
+            && self.pck_crl.is_none()
+    }
+}
+
+/// Serves the collaterals given in `CollateralFiles` from disk and everything else
+/// from the inner provider.
+///
+/// A whole collateral set is read from the inner provider at once and the overridden
+/// items are then replaced. Only if that fails, e.g. because an overridden item is missing
+/// from the source, are the items that are not overridden read one by one.
+pub struct OverrideProvider {
+    inner: Box<dyn CollateralProvider>,
+    files: CollateralFiles,
+}
+
+impl OverrideProvider {
+    pub fn new(inner: Box<dyn CollateralProvider>, files: CollateralFiles) -> Self {
+        OverrideProvider { inner, files }
+    }
+}
+
+#[async_trait]
+impl CollateralProvider for OverrideProvider {
+    fn name(&self) -> String {
+        format!("{} with file overrides", self.inner.name())
+    }
+
+    async fn root_ca(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+        match (&self.files.root_ca, &self.files.root_ca_crl) {
+            (Some(root_ca), Some(root_ca_crl)) => {
+                Ok((read_cert_file(root_ca)?, read_cert_file(root_ca_crl)?))
+            }
+            (Some(root_ca), None) => {
+                let (_, root_ca_crl) = self.inner.root_ca().await?;
+                Ok((read_cert_file(root_ca)?, root_ca_crl))
+            }
+            (None, Some(root_ca_crl)) => {
+                let (root_ca, _) = self.inner.root_ca().await?;
+                Ok((root_ca, read_cert_file(root_ca_crl)?))
+            }
+            (None, None) => self.inner.root_ca().await,
+        }
+    }
+
+    async fn signing_ca(&self) -> Result<Vec<u8>> {
+        match &self.files.signing_ca {
+            Some(path) => read_cert_file(path),
+            _ => self.inner.signing_ca().await,
+        }
+    }
+
+    async fn pck_crl(&self, ca: CA) -> Result<Vec<u8>> {
+        match &self.files.pck_crl {
+            Some(path) => read_cert_file(path),
+            _ => self.inner.pck_crl(ca).await,
+        }
+    }
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<Vec<u8>> {
+        match &self.files.tcb_info {
+            Some(path) => read_signed_json_file(path, TCB_INFO_KEY),
+            _ => self.inner.tcb_info(tcb_type, fmspc, version).await,
+        }
+    }
+
+    async fn qe_identity(&self, id: EnclaveIdType, version: u32) -> Result<Vec<u8>> {
+        match &self.files.qe_identity {
+            Some(path) => read_signed_json_file(path, ENCLAVE_IDENTITY_KEY),
+            _ => self.inner.qe_identity(id, version).await,
+        }
+    }
+
+    async fn collaterals(&self, request: &CollateralRequest) -> Result<Collaterals> {
+        let mut collaterals = match self.inner.collaterals(request).await {
+            Ok(collaterals) => collaterals,
+            Err(e) => {
+                log::warn!(
+                    "Failed to fetch the collaterals from {}, reading them one by one: {}",
+                    self.inner.name(),
+                    e
+                );
+                return fetch_each(self, request).await;
+            }
+        };
+
+        if let Some(path) = &self.files.root_ca {
+            collaterals.root_ca = read_cert_file(path)?;
+        }
+        if let Some(path) = &self.files.root_ca_crl {
+            collaterals.root_ca_crl = read_cert_file(path)?;
+        }
+        if let Some(path) = &self.files.signing_ca {
+            collaterals.tcb_signing_ca = read_cert_file(path)?;
+        }
+        if let Some(path) = &self.files.pck_crl {
+            collaterals.pck_crl = read_cert_file(path)?;
+        }
+        if let Some(path) = &self.files.tcb_info {
+            collaterals.tcb_info = read_signed_json_file(path, TCB_INFO_KEY)?;
+        }
+        if let Some(path) = &self.files.qe_identity {
+            collaterals.qe_identity = read_signed_json_file(path, ENCLAVE_IDENTITY_KEY)?;
+        }
+        Ok(collaterals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::pem_chain_to_der;
+
+    const TCB_INFO_V3_TDX: &str = "../data/collaterals/tcb_info_v3_tdx.json";
+    const TEST_SIGNING_CA: &str = "../data/collaterals/test_tcb_signing_ca.pem";
+
+    #[derive(Default)]
+    struct Calls {
+        collaterals: AtomicUsize,
+        single: AtomicUsize,
+    }
+
+    /// Serves collaterals made of its name, or fails to read them at once, and counts the
+    /// calls made to it
+    struct Inner {
+        set_fails: bool,
+        calls: Arc<Calls>,
+    }
+
+    impl Inner {
+        fn single(&self) -> Result<Vec<u8>> {
+            self.calls.single.fetch_add(1, Ordering::SeqCst);
+            Ok(b"inner".to_vec())
+        }
+    }
+
+    #[async_trait]
+    impl CollateralProvider for Inner {
+        fn name(&self) -> String {
+            "inner".to_string()
+        }
+
+        async fn root_ca(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+            Ok((self.single()?, b"inner".to_vec()))
+        }
+
+        async fn signing_ca(&self) -> Result<Vec<u8>> {
+            self.single()
+        }
+
+        async fn pck_crl(&self, _ca: CA) -> Result<Vec<u8>> {
+            self.single()
+        }
+
+        async fn tcb_info(&self, _tcb_type: u8, _fmspc: &str, _version: u32) -> Result<Vec<u8>> {
+            self.single()
+        }
+
+        async fn qe_identity(&self, _id: EnclaveIdType, _version: u32) -> Result<Vec<u8>> {
+            self.single()
+        }
+
+        async fn collaterals(&self, _request: &CollateralRequest) -> Result<Collaterals> {
+            self.calls.collaterals.fetch_add(1, Ordering::SeqCst);
+            if self.set_fails {
+                return Err(anyhow::Error::msg("TCBInfo not found"));
+            }
+            let inner = b"inner".to_vec();
+            Ok(Collaterals::new(
+                inner.clone(),
+                inner.clone(),
+                inner.clone(),
+                inner.clone(),
+                inner.clone(),
+                inner,
+            ))
+        }
+    }
+
+    fn provider(set_fails: bool) -> (OverrideProvider, Arc<Calls>) {
+        let calls = Arc::new(Calls::default());
+        let inner = Inner {
+            set_fails,
+            calls: calls.clone(),
+        };
+        let files = CollateralFiles {
+            tcb_info: Some(PathBuf::from(TCB_INFO_V3_TDX)),
+            signing_ca: Some(PathBuf::from(TEST_SIGNING_CA)),
+            ..Default::default()
+        };
+        (OverrideProvider::new(Box::new(inner), files), calls)
+    }
+
+    fn assert_overridden(collaterals: &Collaterals) {
+        let signing_ca = std::fs::read(TEST_SIGNING_CA).unwrap();
+        assert_eq!(
+            collaterals.tcb_signing_ca,
+            pem_chain_to_der(&signing_ca).unwrap()[0]
+        );
+        assert_eq!(
+            collaterals.tcb_info,
+            std::fs::read(TCB_INFO_V3_TDX).unwrap()
+        );
+        assert_eq!(collaterals.root_ca, b"inner");
+        assert_eq!(collaterals.root_ca_crl, b"inner");
+        assert_eq!(collaterals.pck_crl, b"inner");
+        assert_eq!(collaterals.qe_identity, b"inner");
+    }
+
+    #[tokio::test]
+    async fn replaces_overridden_items_of_whole_set() {
+        let (provider, calls) = provider(false);
+        let request = CollateralRequest::new(4, 0x81, "90C06F000000", CA::PLATFORM);
+
+        assert_overridden(&provider.collaterals(&request).await.unwrap());
+        assert_eq!(calls.collaterals.load(Ordering::SeqCst), 1);
+        assert_eq!(calls.single.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn reads_items_that_are_not_overridden_when_whole_set_fails() {
+        let (provider, calls) = provider(true);
+        let request = CollateralRequest::new(4, 0x81, "90C06F000000", CA::PLATFORM);
+
+        assert_overridden(&provider.collaterals(&request).await.unwrap());
+        assert_eq!(calls.collaterals.load(Ordering::SeqCst), 1);
+        // Root CA, PCK CRL and QEIdentity
+        assert_eq!(calls.single.load(Ordering::SeqCst), 3);
+    }
+}
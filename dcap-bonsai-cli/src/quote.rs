@@ -0,0 +1,1043 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+use x509_parser::prelude::*;
+
+use crate::constants::{SGX_TEE_TYPE, TDX_TEE_TYPE};
+use crate::parser::QuoteParseError;
+
+pub const QUOTE_HEADER_SIZE: usize = 48;
+pub const ENCLAVE_REPORT_SIZE: usize = 384;
+pub const TD10_REPORT_SIZE: usize = 584;
+pub const TD15_REPORT_SIZE: usize = 648;
+
+// Body types of the v5 quote body descriptor
+pub const SGX_ENCLAVE_REPORT_BODY_TYPE: u16 = 1;
+pub const TD10_REPORT_BODY_TYPE: u16 = 2;
+pub const TD15_REPORT_BODY_TYPE: u16 = 3;
+
+pub const ECDSA_256_ATTESTATION_KEY_TYPE: u16 = 2;
+pub const ECDSA_384_ATTESTATION_KEY_TYPE: u16 = 3;
+
+// Certification data identifying the PCK cert by PPID, CPUSVN, PCESVN and PCE ID,
+// with the PPID in plain text or encrypted with RSA-2048-OAEP or RSA-3072-OAEP
+pub const PPID_CLEARTEXT_CERT_DATA_TYPE: u16 = 1;
+pub const PPID_RSA2048_ENCRYPTED_CERT_DATA_TYPE: u16 = 2;
+pub const PPID_RSA3072_ENCRYPTED_CERT_DATA_TYPE: u16 = 3;
+// Certification data holding the PCK leaf cert, intermediate CA and root CA in PEM
+pub const PCK_CERT_CHAIN_CERT_DATA_TYPE: u16 = 5;
+// Certification data holding the QE report, its signature and the QE auth data,
+// followed by the PCK certification data
+pub const QE_REPORT_CERT_DATA_TYPE: u16 = 6;
+// How deep QE report certification data may nest
+pub const MAX_CERT_DATA_DEPTH: usize = 4;
+
+/// A DCAP quote, decoded with every length and offset checked against the input.
+#[derive(Debug, Clone, Serialize)]
+pub struct Quote {
+    pub header: QuoteHeader,
+    /// Type and size of the body, v5 quotes only
+    pub body_descriptor: Option<QuoteBodyDescriptor>,
+    pub body: QuoteBody,
+    pub signature: QuoteSignature,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuoteHeader {
+    pub version: u16,
+    pub attestation_key_type: u16,
+    pub tee_type: u32,
+    pub qe_svn: u16,
+    pub pce_svn: u16,
+    #[serde(serialize_with = "as_hex")]
+    pub qe_vendor_id: [u8; 16],
+    #[serde(serialize_with = "as_hex")]
+    pub user_data: [u8; 20],
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuoteBodyDescriptor {
+    pub body_type: u16,
+    pub body_size: u32,
+}
+
+/// The report of the attested enclave or TD
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuoteBody {
+    SgxEnclaveReport(EnclaveReport),
+    TdReport10(TdReport10),
+    TdReport15(TdReport15),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EnclaveReport {
+    #[serde(serialize_with = "as_hex")]
+    pub cpu_svn: [u8; 16],
+    pub misc_select: u32,
+    #[serde(serialize_with = "as_hex")]
+    pub attributes: [u8; 16],
+    #[serde(serialize_with = "as_hex")]
+    pub mr_enclave: [u8; 32],
+    #[serde(serialize_with = "as_hex")]
+    pub mr_signer: [u8; 32],
+    pub isv_prod_id: u16,
+    pub isv_svn: u16,
+    #[serde(serialize_with = "as_hex")]
+    pub report_data: [u8; 64],
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TdReport10 {
+    #[serde(serialize_with = "as_hex")]
+    pub tee_tcb_svn: [u8; 16],
+    #[serde(serialize_with = "as_hex")]
+    pub mr_seam: [u8; 48],
+    #[serde(serialize_with = "as_hex")]
+    pub mr_signer_seam: [u8; 48],
+    #[serde(serialize_with = "as_hex")]
+    pub seam_attributes: [u8; 8],
+    #[serde(serialize_with = "as_hex")]
+    pub td_attributes: [u8; 8],
+    #[serde(serialize_with = "as_hex")]
+    pub xfam: [u8; 8],
+    #[serde(serialize_with = "as_hex")]
+    pub mr_td: [u8; 48],
+    #[serde(serialize_with = "as_hex")]
+    pub mr_config_id: [u8; 48],
+    #[serde(serialize_with = "as_hex")]
+    pub mr_owner: [u8; 48],
+    #[serde(serialize_with = "as_hex")]
+    pub mr_owner_config: [u8; 48],
+    #[serde(serialize_with = "as_hex")]
+    pub rt_mr0: [u8; 48],
+    #[serde(serialize_with = "as_hex")]
+    pub rt_mr1: [u8; 48],
+    #[serde(serialize_with = "as_hex")]
+    pub rt_mr2: [u8; 48],
+    #[serde(serialize_with = "as_hex")]
+    pub rt_mr3: [u8; 48],
+    #[serde(serialize_with = "as_hex")]
+    pub report_data: [u8; 64],
+}
+
+/// TD report of TDX 1.5 modules, a TD report 1.0 followed by two more fields
+#[derive(Debug, Clone, Serialize)]
+pub struct TdReport15 {
+    #[serde(flatten)]
+    pub td_report: TdReport10,
+    #[serde(serialize_with = "as_hex")]
+    pub tee_tcb_svn_2: [u8; 16],
+    #[serde(serialize_with = "as_hex")]
+    pub mr_service_td: [u8; 48],
+}
+
+/// The ECDSA signature section.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuoteSignature {
+    pub signature_data_len: u32,
+    /// Signature over the header and body, made with the attestation key
+    #[serde(serialize_with = "as_hex")]
+    pub quote_signature: [u8; 64],
+    #[serde(serialize_with = "as_hex")]
+    pub attestation_key: [u8; 64],
+    /// QE report certification data, with the PCK certification data nested in it.
+    /// v3 quotes carry it without a type and size, these are filled in.
+    pub cert_data: CertData,
+}
+
+/// Certification data: a type, the size of the payload and the payload itself,
+/// which may nest further certification data.
+#[derive(Debug, Clone, Serialize)]
+pub struct CertData {
+    pub cert_data_type: u16,
+    pub size: u32,
+    pub payload: CertDataPayload,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum CertDataPayload {
+    /// QE report certification data (type 6)
+    QeReport(Box<QeReportCertData>),
+    /// Any other type, kept as is
+    Raw(#[serde(serialize_with = "as_hex")] Vec<u8>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QeReportCertData {
+    pub qe_report: EnclaveReport,
+    #[serde(serialize_with = "as_hex")]
+    pub qe_report_signature: [u8; 64],
+    #[serde(serialize_with = "as_hex")]
+    pub qe_auth_data: Vec<u8>,
+    pub cert_data: CertData,
+}
+
+impl CertData {
+    /// Returns the QE report certification data, if this is type 6
+    pub fn qe_report_cert_data(&self) -> Option<&QeReportCertData> {
+        match &self.payload {
+            CertDataPayload::QeReport(qe_report_cert_data) => Some(qe_report_cert_data),
+            CertDataPayload::Raw(_) => None,
+        }
+    }
+
+    /// Returns the type and payload of the innermost certification data,
+    /// i.e. the one that leads to the PCK certificate
+    pub fn pck_cert_data(&self) -> (u16, &[u8]) {
+        match &self.payload {
+            CertDataPayload::QeReport(qe_report_cert_data) => {
+                qe_report_cert_data.cert_data.pck_cert_data()
+            }
+            CertDataPayload::Raw(data) => (self.cert_data_type, data.as_slice()),
+        }
+    }
+}
+
+impl QuoteBody {
+    /// Returns the TD report 1.0 fields of TD reports, none for SGX enclave reports
+    pub fn td_report(&self) -> Option<&TdReport10> {
+        match self {
+            QuoteBody::SgxEnclaveReport(_) => None,
+            QuoteBody::TdReport10(report) => Some(report),
+            QuoteBody::TdReport15(report) => Some(&report.td_report),
+        }
+    }
+
+    /// Returns the TEE TCB SVN of TD reports, none for SGX enclave reports
+    pub fn tee_tcb_svn(&self) -> Option<[u8; 16]> {
+        self.td_report().map(|report| report.tee_tcb_svn)
+    }
+}
+
+impl Quote {
+    pub fn from_bytes(raw: &[u8]) -> Result<Self, QuoteParseError> {
+        let mut reader = Reader::new(raw);
+
+        let header = QuoteHeader::read(&mut reader)?;
+        if !(3..=5).contains(&header.version) {
+            return Err(QuoteParseError::UnsupportedVersion(header.version));
+        }
+        if (header.tee_type != SGX_TEE_TYPE && header.tee_type != TDX_TEE_TYPE)
+            || (header.tee_type == TDX_TEE_TYPE && header.version < 4)
+        {
+            return Err(QuoteParseError::UnsupportedTeeType(header.tee_type));
+        }
+
+        // v5 quotes announce the body type, earlier ones imply it from the TEE type
+        let (body_descriptor, body_type) = if header.version > 4 {
+            let descriptor = QuoteBodyDescriptor::read(&mut reader)?;
+            let body_type = descriptor.body_type;
+            (Some(descriptor), body_type)
+        } else if header.tee_type == TDX_TEE_TYPE {
+            (None, TD10_REPORT_BODY_TYPE)
+        } else {
+            (None, SGX_ENCLAVE_REPORT_BODY_TYPE)
+        };
+
+        let expected_tee_type = match body_type {
+            SGX_ENCLAVE_REPORT_BODY_TYPE => SGX_TEE_TYPE,
+            _ => TDX_TEE_TYPE,
+        };
+        if header.tee_type != expected_tee_type {
+            return Err(QuoteParseError::BodyTypeMismatch {
+                body_type,
+                tee_type: header.tee_type,
+            });
+        }
+
+        let body = match body_type {
+            SGX_ENCLAVE_REPORT_BODY_TYPE => QuoteBody::SgxEnclaveReport(EnclaveReport::read(
+                &mut reader,
+                "SGX enclave report body",
+            )?),
+            TD10_REPORT_BODY_TYPE => QuoteBody::TdReport10(TdReport10::read(&mut reader)?),
+            _ => QuoteBody::TdReport15(TdReport15::read(&mut reader)?),
+        };
+        let signature = QuoteSignature::read(&mut reader, header.version)?;
+
+        Ok(Quote {
+            header,
+            body_descriptor,
+            body,
+            signature,
+        })
+    }
+}
+
+impl QuoteHeader {
+    fn read(reader: &mut Reader) -> Result<Self, QuoteParseError> {
+        let structure = "Quote header";
+        reader.ensure(QUOTE_HEADER_SIZE, structure)?;
+        Ok(QuoteHeader {
+            version: reader.u16(structure)?,
+            attestation_key_type: reader.u16(structure)?,
+            tee_type: reader.u32(structure)?,
+            qe_svn: reader.u16(structure)?,
+            pce_svn: reader.u16(structure)?,
+            qe_vendor_id: reader.array(structure)?,
+            user_data: reader.array(structure)?,
+        })
+    }
+}
+
+impl QuoteBodyDescriptor {
+    fn read(reader: &mut Reader) -> Result<Self, QuoteParseError> {
+        let structure = "Quote body descriptor";
+        let body_type = reader.u16(structure)?;
+        let body_size = reader.u32(structure)?;
+
+        let expected_size = match body_type {
+            SGX_ENCLAVE_REPORT_BODY_TYPE => ENCLAVE_REPORT_SIZE,
+            TD10_REPORT_BODY_TYPE => TD10_REPORT_SIZE,
+            TD15_REPORT_BODY_TYPE => TD15_REPORT_SIZE,
+            _ => return Err(QuoteParseError::UnsupportedBodyType(body_type)),
+        };
+        if body_size as usize != expected_size {
+            return Err(QuoteParseError::InvalidBodySize {
+                body_type,
+                size: body_size,
+                expected: expected_size,
+            });
+        }
+
+        Ok(QuoteBodyDescriptor {
+            body_type,
+            body_size,
+        })
+    }
+}
+
+impl EnclaveReport {
+    fn read(reader: &mut Reader, structure: &'static str) -> Result<Self, QuoteParseError> {
+        reader.ensure(ENCLAVE_REPORT_SIZE, structure)?;
+        let cpu_svn = reader.array(structure)?;
+        let misc_select = reader.u32(structure)?;
+        reader.skip(28, structure)?;
+        let attributes = reader.array(structure)?;
+        let mr_enclave = reader.array(structure)?;
+        reader.skip(32, structure)?;
+        let mr_signer = reader.array(structure)?;
+        reader.skip(96, structure)?;
+        let isv_prod_id = reader.u16(structure)?;
+        let isv_svn = reader.u16(structure)?;
+        reader.skip(60, structure)?;
+        let report_data = reader.array(structure)?;
+
+        Ok(EnclaveReport {
+            cpu_svn,
+            misc_select,
+            attributes,
+            mr_enclave,
+            mr_signer,
+            isv_prod_id,
+            isv_svn,
+            report_data,
+        })
+    }
+}
+
+impl TdReport10 {
+    fn read(reader: &mut Reader) -> Result<Self, QuoteParseError> {
+        let structure = "TD report body";
+        reader.ensure(TD10_REPORT_SIZE, structure)?;
+        Ok(TdReport10 {
+            tee_tcb_svn: reader.array(structure)?,
+            mr_seam: reader.array(structure)?,
+            mr_signer_seam: reader.array(structure)?,
+            seam_attributes: reader.array(structure)?,
+            td_attributes: reader.array(structure)?,
+            xfam: reader.array(structure)?,
+            mr_td: reader.array(structure)?,
+            mr_config_id: reader.array(structure)?,
+            mr_owner: reader.array(structure)?,
+            mr_owner_config: reader.array(structure)?,
+            rt_mr0: reader.array(structure)?,
+            rt_mr1: reader.array(structure)?,
+            rt_mr2: reader.array(structure)?,
+            rt_mr3: reader.array(structure)?,
+            report_data: reader.array(structure)?,
+        })
+    }
+}
+
+impl TdReport15 {
+    fn read(reader: &mut Reader) -> Result<Self, QuoteParseError> {
+        reader.ensure(TD15_REPORT_SIZE, "TD report 1.5 body")?;
+        Ok(TdReport15 {
+            td_report: TdReport10::read(reader)?,
+            tee_tcb_svn_2: reader.array("TD report 1.5 body")?,
+            mr_service_td: reader.array("TD report 1.5 body")?,
+        })
+    }
+}
+
+impl QuoteSignature {
+    fn read(reader: &mut Reader, version: u16) -> Result<Self, QuoteParseError> {
+        let signature_data_len = reader.u32("Quote signature data length")?;
+        let mut reader = reader.nested(signature_data_len as usize, "Quote signature data")?;
+
+        let quote_signature = reader.array("Quote signature")?;
+        let attestation_key = reader.array("Attestation key")?;
+
+        let cert_data = if version > 3 {
+            let cert_data = CertData::read(&mut reader, 0)?;
+            if cert_data.cert_data_type != QE_REPORT_CERT_DATA_TYPE {
+                return Err(QuoteParseError::UnsupportedCertDataType {
+                    found: cert_data.cert_data_type,
+                    expected: QE_REPORT_CERT_DATA_TYPE,
+                });
+            }
+            cert_data
+        } else {
+            // The QE report certification data spans the rest of the signature data
+            let size = reader.remaining();
+            let mut payload = reader.nested(size, "QE report certification data")?;
+            CertData {
+                cert_data_type: QE_REPORT_CERT_DATA_TYPE,
+                size: size as u32,
+                payload: CertData::read_payload(QE_REPORT_CERT_DATA_TYPE, &mut payload, 0)?,
+            }
+        };
+
+        Ok(QuoteSignature {
+            signature_data_len,
+            quote_signature,
+            attestation_key,
+            cert_data,
+        })
+    }
+}
+
+impl CertData {
+    fn read(reader: &mut Reader, depth: usize) -> Result<Self, QuoteParseError> {
+        let cert_data_type = reader.u16("Certification data type")?;
+        let size = reader.u32("Certification data size")?;
+        let mut payload = reader.nested(size as usize, "Certification data")?;
+
+        Ok(CertData {
+            cert_data_type,
+            size,
+            payload: Self::read_payload(cert_data_type, &mut payload, depth)?,
+        })
+    }
+
+    /// Reads a payload that must span all of `reader`
+    fn read_payload(
+        cert_data_type: u16,
+        reader: &mut Reader,
+        depth: usize,
+    ) -> Result<CertDataPayload, QuoteParseError> {
+        if cert_data_type != QE_REPORT_CERT_DATA_TYPE {
+            let size = reader.remaining();
+            return Ok(CertDataPayload::Raw(
+                reader.take(size, "Certification data")?.to_vec(),
+            ));
+        }
+        if depth >= MAX_CERT_DATA_DEPTH {
+            return Err(QuoteParseError::CertDataTooDeep(MAX_CERT_DATA_DEPTH));
+        }
+
+        let size = reader.remaining();
+        let qe_report_cert_data = QeReportCertData::read(reader, depth)?;
+        if reader.remaining() != 0 {
+            return Err(QuoteParseError::InvalidCertDataSize {
+                cert_data_type,
+                size,
+                expected: size - reader.remaining(),
+            });
+        }
+        Ok(CertDataPayload::QeReport(Box::new(qe_report_cert_data)))
+    }
+}
+
+impl QeReportCertData {
+    fn read(reader: &mut Reader, depth: usize) -> Result<Self, QuoteParseError> {
+        let qe_report = EnclaveReport::read(reader, "QE report")?;
+        let qe_report_signature = reader.array("QE report signature")?;
+        let qe_auth_data_size = reader.u16("QE authentication data size")?;
+        let qe_auth_data = reader
+            .take(qe_auth_data_size as usize, "QE authentication data")?
+            .to_vec();
+        let cert_data = CertData::read(reader, depth + 1)?;
+
+        Ok(QeReportCertData {
+            qe_report,
+            qe_report_signature,
+            qe_auth_data,
+            cert_data,
+        })
+    }
+}
+
+/// Bounds-checked little-endian reads over the raw quote
+struct Reader<'a> {
+    raw: &'a [u8],
+    offset: usize,
+    /// Where the structure being read ends, the end of the quote unless nested
+    end: usize,
+    enclosing: Option<&'static str>,
+}
+
+impl<'a> Reader<'a> {
+    fn new(raw: &'a [u8]) -> Self {
+        Reader {
+            raw,
+            offset: 0,
+            end: raw.len(),
+            enclosing: None,
+        }
+    }
+
+    /// Checks that `len` more bytes are available without consuming them
+    fn ensure(&self, len: usize, structure: &'static str) -> Result<(), QuoteParseError> {
+        if matches!(self.offset.checked_add(len), Some(end) if end <= self.end) {
+            return Ok(());
+        }
+        Err(match self.enclosing {
+            Some(enclosing) if self.end < self.raw.len() => QuoteParseError::Overrun {
+                structure,
+                enclosing,
+                offset: self.offset,
+                len,
+                end: self.end,
+            },
+            _ => QuoteParseError::Truncated {
+                structure,
+                offset: self.offset,
+                len,
+                quote_len: self.raw.len(),
+            },
+        })
+    }
+
+    /// Consumes the next `len` bytes and returns a reader over them only,
+    /// so that nothing read from it runs past `structure`
+    fn nested(
+        &mut self,
+        len: usize,
+        structure: &'static str,
+    ) -> Result<Reader<'a>, QuoteParseError> {
+        self.ensure(len, structure)?;
+        let nested = Reader {
+            raw: self.raw,
+            offset: self.offset,
+            end: self.offset + len,
+            enclosing: Some(structure),
+        };
+        self.offset += len;
+        Ok(nested)
+    }
+
+    fn remaining(&self) -> usize {
+        self.end - self.offset
+    }
+
+    fn take(&mut self, len: usize, structure: &'static str) -> Result<&'a [u8], QuoteParseError> {
+        self.ensure(len, structure)?;
+        let bytes = &self.raw[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize, structure: &'static str) -> Result<(), QuoteParseError> {
+        self.take(len, structure).map(|_| ())
+    }
+
+    fn array<const N: usize>(
+        &mut self,
+        structure: &'static str,
+    ) -> Result<[u8; N], QuoteParseError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N, structure)?);
+        Ok(array)
+    }
+
+    fn u16(&mut self, structure: &'static str) -> Result<u16, QuoteParseError> {
+        Ok(u16::from_le_bytes(self.array(structure)?))
+    }
+
+    fn u32(&mut self, structure: &'static str) -> Result<u32, QuoteParseError> {
+        Ok(u32::from_le_bytes(self.array(structure)?))
+    }
+}
+
+pub(crate) fn as_hex<S: Serializer, T: AsRef<[u8]>>(
+    bytes: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(bytes))
+}
+
+pub fn tee_type_name(tee_type: u32) -> &'static str {
+    match tee_type {
+        SGX_TEE_TYPE => "SGX",
+        TDX_TEE_TYPE => "TDX",
+        _ => "unknown",
+    }
+}
+
+pub fn attestation_key_type_name(attestation_key_type: u16) -> &'static str {
+    match attestation_key_type {
+        ECDSA_256_ATTESTATION_KEY_TYPE => "ECDSA-256-with-P-256",
+        ECDSA_384_ATTESTATION_KEY_TYPE => "ECDSA-384-with-P-384",
+        _ => "unknown",
+    }
+}
+
+pub fn body_type_name(body_type: u16) -> String {
+    let name = match body_type {
+        SGX_ENCLAVE_REPORT_BODY_TYPE => "SGX enclave report",
+        TD10_REPORT_BODY_TYPE => "TD report 1.0",
+        TD15_REPORT_BODY_TYPE => "TD report 1.5",
+        _ => "unknown",
+    };
+    format!("{} ({})", body_type, name)
+}
+
+pub fn cert_data_type_name(cert_data_type: u16) -> &'static str {
+    match cert_data_type {
+        PPID_CLEARTEXT_CERT_DATA_TYPE => "PPID in plain text, CPUSVN and PCESVN",
+        PPID_RSA2048_ENCRYPTED_CERT_DATA_TYPE => {
+            "PPID encrypted with RSA-2048-OAEP, CPUSVN and PCESVN"
+        }
+        PPID_RSA3072_ENCRYPTED_CERT_DATA_TYPE => {
+            "PPID encrypted with RSA-3072-OAEP, CPUSVN and PCESVN"
+        }
+        4 => "PCK leaf certificate",
+        PCK_CERT_CHAIN_CERT_DATA_TYPE => "PCK certificate chain",
+        QE_REPORT_CERT_DATA_TYPE => "QE report certification data",
+        7 => "PLATFORM_MANIFEST",
+        _ => "unknown",
+    }
+}
+
+pub(crate) fn field(f: &mut fmt::Formatter, name: &str, value: impl fmt::Display) -> fmt::Result {
+    writeln!(f, "  {:<24}{}", format!("{}:", name), value)
+}
+
+impl fmt::Display for Quote {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.header)?;
+        if let Some(descriptor) = &self.body_descriptor {
+            field(f, "Body Type", body_type_name(descriptor.body_type))?;
+            field(f, "Body Size", descriptor.body_size)?;
+        }
+        match &self.body {
+            QuoteBody::SgxEnclaveReport(report) => {
+                writeln!(f, "SGX Enclave Report")?;
+                write!(f, "{}", report)?;
+            }
+            QuoteBody::TdReport10(report) => {
+                writeln!(f, "TD Report 1.0")?;
+                write!(f, "{}", report)?;
+            }
+            QuoteBody::TdReport15(report) => {
+                writeln!(f, "TD Report 1.5")?;
+                write!(f, "{}", report.td_report)?;
+                field(f, "TEE TCB SVN 2", hex::encode(report.tee_tcb_svn_2))?;
+                field(f, "MRSERVICETD", hex::encode(report.mr_service_td))?;
+            }
+        }
+        write!(f, "{}", self.signature)
+    }
+}
+
+impl fmt::Display for QuoteHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Header")?;
+        field(f, "Version", self.version)?;
+        field(
+            f,
+            "Attestation Key Type",
+            format!(
+                "{} ({})",
+                self.attestation_key_type,
+                attestation_key_type_name(self.attestation_key_type)
+            ),
+        )?;
+        field(
+            f,
+            "TEE Type",
+            format!("{:#010x} ({})", self.tee_type, tee_type_name(self.tee_type)),
+        )?;
+        field(f, "QE SVN", self.qe_svn)?;
+        field(f, "PCE SVN", self.pce_svn)?;
+        field(f, "QE Vendor ID", hex::encode(self.qe_vendor_id))?;
+        field(f, "User Data", hex::encode(self.user_data))
+    }
+}
+
+impl fmt::Display for EnclaveReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        field(f, "CPU SVN", hex::encode(self.cpu_svn))?;
+        field(f, "MISCSELECT", format!("{:#010x}", self.misc_select))?;
+        field(f, "Attributes", hex::encode(self.attributes))?;
+        field(f, "MRENCLAVE", hex::encode(self.mr_enclave))?;
+        field(f, "MRSIGNER", hex::encode(self.mr_signer))?;
+        field(f, "ISV Prod ID", self.isv_prod_id)?;
+        field(f, "ISV SVN", self.isv_svn)?;
+        field(f, "Report Data", hex::encode(self.report_data))
+    }
+}
+
+impl fmt::Display for TdReport10 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        field(f, "TEE TCB SVN", hex::encode(self.tee_tcb_svn))?;
+        field(f, "MRSEAM", hex::encode(self.mr_seam))?;
+        field(f, "MRSIGNERSEAM", hex::encode(self.mr_signer_seam))?;
+        field(f, "SEAM Attributes", hex::encode(self.seam_attributes))?;
+        field(f, "TD Attributes", hex::encode(self.td_attributes))?;
+        field(f, "XFAM", hex::encode(self.xfam))?;
+        field(f, "MRTD", hex::encode(self.mr_td))?;
+        field(f, "MRCONFIGID", hex::encode(self.mr_config_id))?;
+        field(f, "MROWNER", hex::encode(self.mr_owner))?;
+        field(f, "MROWNERCONFIG", hex::encode(self.mr_owner_config))?;
+        field(f, "RTMR0", hex::encode(self.rt_mr0))?;
+        field(f, "RTMR1", hex::encode(self.rt_mr1))?;
+        field(f, "RTMR2", hex::encode(self.rt_mr2))?;
+        field(f, "RTMR3", hex::encode(self.rt_mr3))?;
+        field(f, "Report Data", hex::encode(self.report_data))
+    }
+}
+
+impl fmt::Display for QuoteSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Signature")?;
+        field(f, "Signature Data Length", self.signature_data_len)?;
+        field(f, "Quote Signature", hex::encode(self.quote_signature))?;
+        field(f, "Attestation Key", hex::encode(self.attestation_key))?;
+        write!(f, "{}", self.cert_data)
+    }
+}
+
+impl fmt::Display for CertData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Certification Data")?;
+        field(
+            f,
+            "Type",
+            format!(
+                "{} ({})",
+                self.cert_data_type,
+                cert_data_type_name(self.cert_data_type)
+            ),
+        )?;
+        field(f, "Size", self.size)?;
+
+        let data = match &self.payload {
+            CertDataPayload::QeReport(qe_report_cert_data) => {
+                return write!(f, "{}", qe_report_cert_data)
+            }
+            CertDataPayload::Raw(data) => data,
+        };
+        if self.cert_data_type != PCK_CERT_CHAIN_CERT_DATA_TYPE {
+            return field(f, "Data", hex::encode(data));
+        }
+        for (i, pem) in Pem::iter_from_buffer(data).enumerate() {
+            let cert = pem.ok().and_then(|pem| {
+                pem.parse_x509()
+                    .ok()
+                    .map(|cert| (cert.subject().to_string(), cert.issuer().to_string()))
+            });
+            match cert {
+                Some((subject, issuer)) => {
+                    field(f, &format!("Certificate {}", i), subject)?;
+                    field(f, "  Issuer", issuer)?;
+                }
+                None => field(f, &format!("Certificate {}", i), "<invalid>")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for QeReportCertData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "QE Report")?;
+        write!(f, "{}", self.qe_report)?;
+        field(f, "Signature", hex::encode(self.qe_report_signature))?;
+
+        writeln!(f, "QE Authentication Data")?;
+        field(f, "Size", self.qe_auth_data.len())?;
+        field(f, "Data", hex::encode(&self.qe_auth_data))?;
+
+        write!(f, "{}", self.cert_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUOTE_HEX: &str = include_str!("../../data/quote.hex");
+    const SIGNATURE_DATA_OFFSET: usize = QUOTE_HEADER_SIZE + TD10_REPORT_SIZE + 4;
+
+    /// The sample v4 TDX quote
+    fn v4_quote() -> Vec<u8> {
+        hex::decode(QUOTE_HEX.trim()).unwrap()
+    }
+
+    /// The sample quote as a v5 quote, with a body descriptor announcing its TD report 1.0
+    fn v5_quote() -> Vec<u8> {
+        let v4 = v4_quote();
+        let mut quote = v4[..QUOTE_HEADER_SIZE].to_vec();
+        quote[..2].copy_from_slice(&5u16.to_le_bytes());
+        quote.extend_from_slice(&TD10_REPORT_BODY_TYPE.to_le_bytes());
+        quote.extend_from_slice(&(TD10_REPORT_SIZE as u32).to_le_bytes());
+        quote.extend_from_slice(&v4[QUOTE_HEADER_SIZE..]);
+        quote
+    }
+
+    /// The signature of the sample quote over an SGX enclave report, as a v3 quote,
+    /// whose QE report certification data comes without a type and size
+    fn v3_quote() -> Vec<u8> {
+        let v4 = v4_quote();
+        let mut quote = v4[..QUOTE_HEADER_SIZE].to_vec();
+        quote[..2].copy_from_slice(&3u16.to_le_bytes());
+        quote[4..8].copy_from_slice(&SGX_TEE_TYPE.to_le_bytes());
+        quote.extend_from_slice(&[0; ENCLAVE_REPORT_SIZE]);
+
+        let signature_data = &v4[SIGNATURE_DATA_OFFSET..];
+        let size = u32::from_le_bytes(signature_data[130..134].try_into().unwrap()) as usize;
+        quote.extend_from_slice(&((128 + size) as u32).to_le_bytes());
+        quote.extend_from_slice(&signature_data[..128]);
+        quote.extend_from_slice(&signature_data[134..134 + size]);
+        quote
+    }
+
+    /// Where the sections of a quote end: header, body descriptor and body, then the signature
+    /// data length, quote signature, attestation key, QE report certification data type and
+    /// size (v4 and later), QE report and its signature, QE authentication data size and data,
+    /// PCK certification data type and size, and the PCK certification data.
+    /// The sample quote carries 70 more bytes past its signature data, which are not read.
+    fn section_ends(quote: &[u8]) -> Vec<usize> {
+        let parsed = Quote::from_bytes(quote).unwrap();
+        let mut ends = vec![QUOTE_HEADER_SIZE];
+        let mut offset = QUOTE_HEADER_SIZE;
+        if let Some(descriptor) = &parsed.body_descriptor {
+            offset += 6;
+            ends.push(offset);
+            offset += descriptor.body_size as usize;
+        } else if parsed.header.tee_type == TDX_TEE_TYPE {
+            offset += TD10_REPORT_SIZE;
+        } else {
+            offset += ENCLAVE_REPORT_SIZE;
+        }
+        ends.push(offset);
+
+        let qe_report_cert_data = parsed.signature.cert_data.qe_report_cert_data().unwrap();
+        let mut sizes = vec![4, 64, 64];
+        if parsed.header.version > 3 {
+            sizes.extend([2, 4]);
+        }
+        sizes.extend([
+            ENCLAVE_REPORT_SIZE,
+            64,
+            2,
+            qe_report_cert_data.qe_auth_data.len(),
+            2,
+            4,
+        ]);
+        sizes.push(qe_report_cert_data.cert_data.size as usize);
+        for size in sizes {
+            offset += size;
+            ends.push(offset);
+        }
+        ends
+    }
+
+    fn assert_truncations_fail(quote: &[u8]) {
+        let ends = section_ends(quote);
+        let body_end = ends[if quote[0] == 5 { 2 } else { 1 }];
+        let cert_data_end = *ends.last().unwrap();
+        for &end in &ends {
+            // Cut right before and right after each section end
+            for len in [end - 1, end] {
+                if len >= cert_data_end {
+                    continue;
+                }
+                assert!(
+                    Quote::from_bytes(&quote[..len]).is_err(),
+                    "{} bytes of a v{} quote parsed",
+                    len,
+                    quote[0]
+                );
+
+                // Once in the certification data, also cut with the signature data length shrunk
+                // to fit, so that the sections nested in it run out instead
+                if len >= body_end + 4 {
+                    let mut truncated = quote[..len].to_vec();
+                    let signature_data_len = (len - body_end - 4) as u32;
+                    truncated[body_end..body_end + 4]
+                        .copy_from_slice(&signature_data_len.to_le_bytes());
+                    assert!(
+                        Quote::from_bytes(&truncated).is_err(),
+                        "{} bytes of a v{} quote with a consistent signature data length parsed",
+                        len,
+                        quote[0]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parses_v3_v4_and_v5_quotes() {
+        let v3 = Quote::from_bytes(&v3_quote()).unwrap();
+        assert!(matches!(v3.body, QuoteBody::SgxEnclaveReport(_)));
+        let v4 = Quote::from_bytes(&v4_quote()).unwrap();
+        assert!(matches!(v4.body, QuoteBody::TdReport10(_)));
+        let v5 = Quote::from_bytes(&v5_quote()).unwrap();
+        assert_eq!(
+            v5.body_descriptor.as_ref().unwrap().body_type,
+            TD10_REPORT_BODY_TYPE
+        );
+
+        for quote in [&v3, &v4, &v5] {
+            assert_eq!(
+                quote.signature.cert_data.pck_cert_data(),
+                v4.signature.cert_data.pck_cert_data()
+            );
+        }
+    }
+
+    #[test]
+    fn fails_on_truncated_quotes() {
+        assert_truncations_fail(&v3_quote());
+        assert_truncations_fail(&v4_quote());
+        assert_truncations_fail(&v5_quote());
+        assert!(matches!(
+            Quote::from_bytes(&[]),
+            Err(QuoteParseError::Truncated {
+                structure: "Quote header",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn fails_on_body_descriptor_size_mismatch() {
+        let mut quote = v5_quote();
+        quote[QUOTE_HEADER_SIZE + 2..QUOTE_HEADER_SIZE + 6]
+            .copy_from_slice(&(TD15_REPORT_SIZE as u32).to_le_bytes());
+        assert!(matches!(
+            Quote::from_bytes(&quote),
+            Err(QuoteParseError::InvalidBodySize {
+                body_type: TD10_REPORT_BODY_TYPE,
+                size,
+                expected: TD10_REPORT_SIZE,
+            }) if size as usize == TD15_REPORT_SIZE
+        ));
+
+        let mut quote = v5_quote();
+        quote[QUOTE_HEADER_SIZE..QUOTE_HEADER_SIZE + 2].copy_from_slice(&4u16.to_le_bytes());
+        assert!(matches!(
+            Quote::from_bytes(&quote),
+            Err(QuoteParseError::UnsupportedBodyType(4))
+        ));
+    }
+
+    /// QE report certification data around `cert_data`, with an empty QE report and signature
+    /// and no QE authentication data
+    fn wrap_in_qe_report_cert_data(cert_data: &[u8]) -> Vec<u8> {
+        let size = ENCLAVE_REPORT_SIZE + 64 + 2 + cert_data.len();
+        let mut qe_report_cert_data = QE_REPORT_CERT_DATA_TYPE.to_le_bytes().to_vec();
+        qe_report_cert_data.extend_from_slice(&(size as u32).to_le_bytes());
+        qe_report_cert_data.extend_from_slice(&[0; ENCLAVE_REPORT_SIZE + 64 + 2]);
+        qe_report_cert_data.extend_from_slice(cert_data);
+        qe_report_cert_data
+    }
+
+    fn pck_cert_chain_cert_data(chain: &[u8]) -> Vec<u8> {
+        let mut cert_data = PCK_CERT_CHAIN_CERT_DATA_TYPE.to_le_bytes().to_vec();
+        cert_data.extend_from_slice(&(chain.len() as u32).to_le_bytes());
+        cert_data.extend_from_slice(chain);
+        cert_data
+    }
+
+    #[test]
+    fn reads_pck_cert_chain_nested_in_qe_report_cert_data() {
+        let quote = Quote::from_bytes(&v4_quote()).unwrap();
+        let cert_data = &quote.signature.cert_data;
+        assert_eq!(cert_data.cert_data_type, QE_REPORT_CERT_DATA_TYPE);
+        let qe_report_cert_data = cert_data.qe_report_cert_data().unwrap();
+        let pck_cert_data = &qe_report_cert_data.cert_data;
+        assert_eq!(pck_cert_data.cert_data_type, PCK_CERT_CHAIN_CERT_DATA_TYPE);
+        assert_eq!(
+            cert_data.size as usize,
+            ENCLAVE_REPORT_SIZE
+                + 64
+                + 2
+                + qe_report_cert_data.qe_auth_data.len()
+                + 6
+                + pck_cert_data.size as usize
+        );
+
+        let (cert_data_type, chain) = cert_data.pck_cert_data();
+        assert_eq!(cert_data_type, PCK_CERT_CHAIN_CERT_DATA_TYPE);
+        assert_eq!(chain.len(), pck_cert_data.size as usize);
+        assert!(chain.starts_with(b"-----BEGIN CERTIFICATE-----"));
+
+        let raw = wrap_in_qe_report_cert_data(&pck_cert_chain_cert_data(b"chain"));
+        let cert_data = CertData::read(&mut Reader::new(&raw), 0).unwrap();
+        assert_eq!(
+            cert_data.pck_cert_data(),
+            (PCK_CERT_CHAIN_CERT_DATA_TYPE, b"chain".as_slice())
+        );
+    }
+
+    #[test]
+    fn fails_on_cert_data_overrunning_its_enclosing_data() {
+        // The PCK certification data of the sample quote claims one more byte than the
+        // QE report certification data holds
+        let mut quote = v4_quote();
+        let parsed = Quote::from_bytes(&quote).unwrap();
+        let qe_report_cert_data = parsed.signature.cert_data.qe_report_cert_data().unwrap();
+        let size_offset = SIGNATURE_DATA_OFFSET
+            + 128
+            + 6
+            + ENCLAVE_REPORT_SIZE
+            + 64
+            + 2
+            + qe_report_cert_data.qe_auth_data.len()
+            + 2;
+        let size = qe_report_cert_data.cert_data.size + 1;
+        quote[size_offset..size_offset + 4].copy_from_slice(&size.to_le_bytes());
+        assert!(matches!(
+            Quote::from_bytes(&quote),
+            Err(QuoteParseError::Overrun {
+                structure: "Certification data",
+                enclosing: "Certification data",
+                ..
+            })
+        ));
+
+        // Certification data claiming more than the whole input
+        let mut raw = pck_cert_chain_cert_data(b"chain");
+        raw[2..6].copy_from_slice(&6u32.to_le_bytes());
+        assert!(matches!(
+            CertData::read(&mut Reader::new(&raw), 0),
+            Err(QuoteParseError::Truncated {
+                structure: "Certification data",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_cert_data_nested_too_deep() {
+        let mut raw = pck_cert_chain_cert_data(b"chain");
+        for _ in 0..MAX_CERT_DATA_DEPTH {
+            raw = wrap_in_qe_report_cert_data(&raw);
+        }
+        let cert_data = CertData::read(&mut Reader::new(&raw), 0).unwrap();
+        assert_eq!(cert_data.pck_cert_data().1, b"chain");
+
+        let raw = wrap_in_qe_report_cert_data(&raw);
+        assert!(matches!(
+            CertData::read(&mut Reader::new(&raw), 0),
+            Err(QuoteParseError::CertDataTooDeep(MAX_CERT_DATA_DEPTH))
+        ));
+    }
+}
@@ -0,0 +1,526 @@
+use serde::{de::DeserializeOwned, ser::SerializeMap, Deserialize, Serialize, Serializer};
+use serde_json::value::RawValue;
+use std::collections::HashMap;
+use std::fmt;
+use thiserror::Error;
+
+use crate::remove_prefix_if_found;
+
+/// Why a TCBInfo or enclave identity in Intel's signed format was rejected.
+#[derive(Debug, Error)]
+pub enum SignedCollateralError {
+    #[error("Invalid {collateral} JSON: {reason}")]
+    InvalidJson {
+        collateral: &'static str,
+        reason: String,
+    },
+
+    #[error("Invalid {collateral}: {reason}")]
+    InvalidBody {
+        collateral: &'static str,
+        reason: String,
+    },
+
+    #[error("The {collateral} body does not re-serialize to the bytes it was signed over, the guest would reject its signature")]
+    NotCanonical { collateral: &'static str },
+
+    #[error("Invalid {collateral} signature: {reason}")]
+    InvalidSignature {
+        collateral: &'static str,
+        reason: String,
+    },
+}
+
+/// The body of a collateral in Intel's signed format.
+pub trait SignedBody: Serialize + DeserializeOwned {
+    /// The key of the body next to `signature`
+    const KEY: &'static str;
+    /// The name to report the collateral under
+    const NAME: &'static str;
+
+    /// Checks what the JSON schema alone does not
+    fn validate(&self) -> Result<(), String>;
+}
+
+/// A TCBInfo or enclave identity in Intel's signed format,
+/// `{"tcbInfo": {...}, "signature": "<hex>"}` or `{"enclaveIdentity": {...}, "signature": "<hex>"}`.
+///
+/// The body is kept exactly as signed, and only accepted if its model re-serializes to the
+/// same bytes, since the guest checks the signature over the re-serialized body.
+#[derive(Debug, Clone)]
+pub struct Signed<T> {
+    pub body: T,
+    pub raw_body: Box<RawValue>,
+    /// Raw P-256 ECDSA signature (r || s) over `raw_body`
+    pub signature: Vec<u8>,
+}
+
+impl<T: SignedBody> Signed<T> {
+    /// Parses and validates a body and its signature, e.g. as stored by the PCCS DAOs
+    pub fn new(raw_body: &str, signature: &[u8]) -> Result<Self, SignedCollateralError> {
+        let invalid_json = |e: serde_json::Error| SignedCollateralError::InvalidJson {
+            collateral: T::NAME,
+            reason: e.to_string(),
+        };
+
+        let raw_body = RawValue::from_string(raw_body.to_string()).map_err(invalid_json)?;
+        let body: T = serde_json::from_str(raw_body.get()).map_err(invalid_json)?;
+        body.validate()
+            .map_err(|reason| SignedCollateralError::InvalidBody {
+                collateral: T::NAME,
+                reason,
+            })?;
+        if serde_json::to_string(&body).map_err(invalid_json)? != raw_body.get() {
+            return Err(SignedCollateralError::NotCanonical {
+                collateral: T::NAME,
+            });
+        }
+
+        if signature.len() != 64 {
+            return Err(SignedCollateralError::InvalidSignature {
+                collateral: T::NAME,
+                reason: format!("{} bytes, expected 64", signature.len()),
+            });
+        }
+
+        Ok(Signed {
+            body,
+            raw_body,
+            signature: signature.to_vec(),
+        })
+    }
+
+    /// Parses and validates a collateral in Intel's signed format
+    pub fn from_json(json: &[u8]) -> Result<Self, SignedCollateralError> {
+        let invalid_json = |reason: String| SignedCollateralError::InvalidJson {
+            collateral: T::NAME,
+            reason,
+        };
+
+        let fields: HashMap<String, &RawValue> =
+            serde_json::from_slice(json).map_err(|e| invalid_json(e.to_string()))?;
+        let body = fields
+            .get(T::KEY)
+            .ok_or_else(|| invalid_json(format!("no {}", T::KEY)))?;
+        let signature: String = fields
+            .get("signature")
+            .ok_or_else(|| invalid_json(String::from("no signature")))
+            .and_then(|signature| {
+                serde_json::from_str(signature.get()).map_err(|e| invalid_json(e.to_string()))
+            })?;
+        let signature = hex::decode(remove_prefix_if_found(&signature)).map_err(|e| {
+            SignedCollateralError::InvalidSignature {
+                collateral: T::NAME,
+                reason: e.to_string(),
+            }
+        })?;
+
+        Signed::new(body.get(), &signature)
+    }
+
+    /// Serializes the collateral in Intel's signed format, with the body as signed
+    pub fn to_json(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("a signed collateral always serializes")
+    }
+}
+
+impl<T: SignedBody> Serialize for Signed<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry(T::KEY, &self.raw_body)?;
+        map.serialize_entry("signature", &hex::encode(&self.signature))?;
+        map.end()
+    }
+}
+
+/// TCB status of a TCB level, as named in Intel's TCBInfo and enclave identities
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TcbStatus {
+    UpToDate,
+    #[serde(rename = "SWHardeningNeeded")]
+    SwHardeningNeeded,
+    #[serde(rename = "ConfigurationAndSWHardeningNeeded")]
+    ConfigurationAndSwHardeningNeeded,
+    ConfigurationNeeded,
+    OutOfDate,
+    OutOfDateConfigurationNeeded,
+    Revoked,
+    /// An unknown status, or no TCB level matches
+    #[serde(other)]
+    Unrecognized,
+}
+
+/// TCBInfo v2 (SGX) and v3 (SGX and TDX) body, fields in the order Intel signs them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TcbInfo {
+    /// SGX or TDX, v3 only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub version: u32,
+    pub issue_date: String,
+    pub next_update: String,
+    pub fmspc: String,
+    pub pce_id: String,
+    pub tcb_type: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tcb_evaluation_data_number: Option<u32>,
+    /// TDX 1.0 module identity, TDX TCBInfo v3 only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tdx_module: Option<TdxModule>,
+    /// Identities of later TDX modules, TDX TCBInfo v3 only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tdx_module_identities: Option<Vec<TdxModuleIdentity>>,
+    pub tcb_levels: Vec<TcbLevel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TcbLevel {
+    pub tcb: Tcb,
+    pub tcb_date: String,
+    pub tcb_status: TcbStatus,
+    #[serde(
+        rename = "advisoryIDs",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub advisory_ids: Option<Vec<String>>,
+}
+
+/// The TCB of a TCB level, component lists in TCBInfo v3, one field per component in v2
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Tcb {
+    V3(TcbV3),
+    V2(TcbV2),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcbV3 {
+    pub sgxtcbcomponents: Vec<TcbComponent>,
+    pub pcesvn: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tdxtcbcomponents: Option<Vec<TcbComponent>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcbV2 {
+    pub sgxtcbcomp01svn: u16,
+    pub sgxtcbcomp02svn: u16,
+    pub sgxtcbcomp03svn: u16,
+    pub sgxtcbcomp04svn: u16,
+    pub sgxtcbcomp05svn: u16,
+    pub sgxtcbcomp06svn: u16,
+    pub sgxtcbcomp07svn: u16,
+    pub sgxtcbcomp08svn: u16,
+    pub sgxtcbcomp09svn: u16,
+    pub sgxtcbcomp10svn: u16,
+    pub sgxtcbcomp11svn: u16,
+    pub sgxtcbcomp12svn: u16,
+    pub sgxtcbcomp13svn: u16,
+    pub sgxtcbcomp14svn: u16,
+    pub sgxtcbcomp15svn: u16,
+    pub sgxtcbcomp16svn: u16,
+    pub pcesvn: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcbComponent {
+    pub svn: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub component_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TdxModule {
+    pub mrsigner: String,
+    pub attributes: String,
+    pub attributes_mask: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TdxModuleIdentity {
+    /// TDX_<major version>, e.g. TDX_03
+    pub id: String,
+    #[serde(flatten)]
+    pub module: TdxModule,
+    pub tcb_levels: Vec<EnclaveTcbLevel>,
+}
+
+/// Enclave identity v2 body (QE, QVE or TD_QE), fields in the order Intel signs them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnclaveIdentity {
+    pub id: String,
+    pub version: u32,
+    pub issue_date: String,
+    pub next_update: String,
+    pub tcb_evaluation_data_number: u32,
+    pub miscselect: String,
+    pub miscselect_mask: String,
+    pub attributes: String,
+    pub attributes_mask: String,
+    pub mrsigner: String,
+    pub isvprodid: u16,
+    pub tcb_levels: Vec<EnclaveTcbLevel>,
+}
+
+/// A TCB level of an enclave identity or of a TDX module identity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnclaveTcbLevel {
+    pub tcb: EnclaveTcb,
+    pub tcb_date: String,
+    pub tcb_status: TcbStatus,
+    #[serde(
+        rename = "advisoryIDs",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub advisory_ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnclaveTcb {
+    pub isvsvn: u16,
+}
+
+impl Tcb {
+    /// The 16 SGX TCB components, without category or type in TCBInfo v2
+    pub fn sgx_components(&self) -> Vec<TcbComponent> {
+        match self {
+            Tcb::V3(tcb) => tcb.sgxtcbcomponents.clone(),
+            Tcb::V2(tcb) => [
+                tcb.sgxtcbcomp01svn,
+                tcb.sgxtcbcomp02svn,
+                tcb.sgxtcbcomp03svn,
+                tcb.sgxtcbcomp04svn,
+                tcb.sgxtcbcomp05svn,
+                tcb.sgxtcbcomp06svn,
+                tcb.sgxtcbcomp07svn,
+                tcb.sgxtcbcomp08svn,
+                tcb.sgxtcbcomp09svn,
+                tcb.sgxtcbcomp10svn,
+                tcb.sgxtcbcomp11svn,
+                tcb.sgxtcbcomp12svn,
+                tcb.sgxtcbcomp13svn,
+                tcb.sgxtcbcomp14svn,
+                tcb.sgxtcbcomp15svn,
+                tcb.sgxtcbcomp16svn,
+            ]
+            .into_iter()
+            .map(|svn| TcbComponent {
+                svn,
+                category: None,
+                component_type: None,
+            })
+            .collect(),
+        }
+    }
+
+    pub fn pce_svn(&self) -> u16 {
+        match self {
+            Tcb::V3(tcb) => tcb.pcesvn,
+            Tcb::V2(tcb) => tcb.pcesvn,
+        }
+    }
+
+    /// The 16 TDX TCB components, TDX TCBInfo v3 only
+    pub fn tdx_components(&self) -> Option<&[TcbComponent]> {
+        match self {
+            Tcb::V3(tcb) => tcb.tdxtcbcomponents.as_deref(),
+            Tcb::V2(_) => None,
+        }
+    }
+}
+
+impl SignedBody for TcbInfo {
+    const KEY: &'static str = "tcbInfo";
+    const NAME: &'static str = "TCBInfo";
+
+    fn validate(&self) -> Result<(), String> {
+        if !(2..=3).contains(&self.version) {
+            return Err(format!("unsupported version {}", self.version));
+        }
+        if self.tcb_levels.is_empty() {
+            return Err(String::from("no TCB levels"));
+        }
+        for (i, level) in self.tcb_levels.iter().enumerate() {
+            match (&level.tcb, self.version) {
+                (Tcb::V2(_), 2) => {}
+                (Tcb::V3(tcb), 3) => {
+                    if tcb.sgxtcbcomponents.len() != 16 {
+                        return Err(format!(
+                            "TCB level {} has {} SGX TCB components, expected 16",
+                            i,
+                            tcb.sgxtcbcomponents.len()
+                        ));
+                    }
+                    if let Some(components) = &tcb.tdxtcbcomponents {
+                        if components.len() != 16 {
+                            return Err(format!(
+                                "TCB level {} has {} TDX TCB components, expected 16",
+                                i,
+                                components.len()
+                            ));
+                        }
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "TCB level {} is not in the TCBInfo v{} format",
+                        i, self.version
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SignedBody for EnclaveIdentity {
+    const KEY: &'static str = "enclaveIdentity";
+    const NAME: &'static str = "enclave identity";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.version != 2 {
+            return Err(format!("unsupported version {}", self.version));
+        }
+        if self.tcb_levels.is_empty() {
+            return Err(String::from("no TCB levels"));
+        }
+        for (name, value, len) in [
+            ("miscselect", &self.miscselect, 4),
+            ("miscselectMask", &self.miscselect_mask, 4),
+            ("attributes", &self.attributes, 16),
+            ("attributesMask", &self.attributes_mask, 16),
+            ("mrsigner", &self.mrsigner, 32),
+        ] {
+            if hex::decode(value).map(|bytes| bytes.len()) != Ok(len) {
+                return Err(format!(
+                    "{} is {}, expected {} hex-encoded bytes",
+                    name, value, len
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TcbStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            TcbStatus::UpToDate => "UpToDate",
+            TcbStatus::SwHardeningNeeded => "SWHardeningNeeded",
+            TcbStatus::ConfigurationAndSwHardeningNeeded => "ConfigurationAndSWHardeningNeeded",
+            TcbStatus::ConfigurationNeeded => "ConfigurationNeeded",
+            TcbStatus::OutOfDate => "OutOfDate",
+            TcbStatus::OutOfDateConfigurationNeeded => "OutOfDateConfigurationNeeded",
+            TcbStatus::Revoked => "Revoked",
+            TcbStatus::Unrecognized => "Unrecognized",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Collaterals laid out byte for byte the way the PCS serves them, signed by a test Signing CA
+    const TCB_INFO_V2_SGX: &str = include_str!("../../data/collaterals/tcb_info_v2_sgx.json");
+    const TCB_INFO_V3_SGX: &str = include_str!("../../data/collaterals/tcb_info_v3_sgx.json");
+    const TCB_INFO_V3_TDX: &str = include_str!("../../data/collaterals/tcb_info_v3_tdx.json");
+    const QE_IDENTITY_V2: &str = include_str!("../../data/collaterals/qe_identity_v2.json");
+    const TD_QE_IDENTITY_V2: &str = include_str!("../../data/collaterals/td_qe_identity_v2.json");
+
+    fn round_trip<T: SignedBody>(json: &str) -> Signed<T> {
+        let signed = Signed::<T>::from_json(json.as_bytes()).unwrap();
+        assert_eq!(String::from_utf8(signed.to_json()).unwrap(), json);
+        signed
+    }
+
+    #[test]
+    fn round_trips_tcb_info_v2_sgx() {
+        let tcb_info = round_trip::<TcbInfo>(TCB_INFO_V2_SGX).body;
+        assert_eq!(tcb_info.version, 2);
+        assert_eq!(tcb_info.id, None);
+        assert!(matches!(tcb_info.tcb_levels[0].tcb, Tcb::V2(_)));
+        assert_eq!(
+            tcb_info.tcb_levels[0].tcb_status,
+            TcbStatus::SwHardeningNeeded
+        );
+        assert_eq!(tcb_info.tcb_levels[0].tcb.sgx_components()[0].svn, 17);
+        assert_eq!(tcb_info.tcb_levels[0].tcb.pce_svn(), 11);
+    }
+
+    #[test]
+    fn round_trips_tcb_info_v3_sgx() {
+        let tcb_info = round_trip::<TcbInfo>(TCB_INFO_V3_SGX).body;
+        assert_eq!(tcb_info.id.as_deref(), Some("SGX"));
+        assert_eq!(tcb_info.version, 3);
+        assert!(tcb_info.tdx_module.is_none());
+        assert!(tcb_info.tcb_levels[0].tcb.tdx_components().is_none());
+        let components = tcb_info.tcb_levels[0].tcb.sgx_components();
+        assert_eq!(components[0].category.as_deref(), Some("BIOS"));
+        assert_eq!(
+            components[0].component_type.as_deref(),
+            Some("Early Microcode Update")
+        );
+        assert_eq!(components[15].category, None);
+    }
+
+    #[test]
+    fn round_trips_tcb_info_v3_tdx() {
+        let tcb_info = round_trip::<TcbInfo>(TCB_INFO_V3_TDX).body;
+        assert_eq!(tcb_info.id.as_deref(), Some("TDX"));
+        assert_eq!(
+            tcb_info.tdx_module.unwrap().attributes_mask,
+            "FFFFFFFFFFFFFFFF"
+        );
+        let identities = tcb_info.tdx_module_identities.unwrap();
+        assert_eq!(identities.len(), 2);
+        assert_eq!(identities[1].id, "TDX_01");
+        assert_eq!(identities[1].tcb_levels[1].tcb.isvsvn, 2);
+        assert_eq!(
+            tcb_info.tcb_levels[0].tcb.tdx_components().unwrap()[2]
+                .component_type
+                .as_deref(),
+            Some("TDX Late Microcode Update")
+        );
+    }
+
+    #[test]
+    fn round_trips_qe_identity_v2() {
+        for json in [QE_IDENTITY_V2, TD_QE_IDENTITY_V2] {
+            let identity = round_trip::<EnclaveIdentity>(json);
+            assert_eq!(identity.body.version, 2);
+            assert_eq!(identity.signature.len(), 64);
+        }
+        let identity = round_trip::<EnclaveIdentity>(QE_IDENTITY_V2).body;
+        assert_eq!(identity.id, "QE");
+        assert_eq!(identity.tcb_levels.len(), 6);
+        assert_eq!(
+            identity.tcb_levels[1].advisory_ids,
+            Some(vec![String::from("INTEL-SA-00615")])
+        );
+    }
+
+    #[test]
+    fn rejects_reordered_fields() {
+        let reordered =
+            TCB_INFO_V3_SGX.replacen(r#""id":"SGX","version":3"#, r#""version":3,"id":"SGX""#, 1);
+        assert_ne!(reordered, TCB_INFO_V3_SGX);
+        assert!(matches!(
+            Signed::<TcbInfo>::from_json(reordered.as_bytes()),
+            Err(SignedCollateralError::NotCanonical {
+                collateral: "TCBInfo"
+            })
+        ));
+    }
+}
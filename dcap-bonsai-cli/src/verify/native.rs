@@ -0,0 +1,146 @@
+use anyhow::Result;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+use dcap_rs::types::{
+    collaterals::IntelCollateral,
+    quotes::{version_3::QuoteV3, version_4::QuoteV4},
+    VerifiedOutput,
+};
+use dcap_rs::utils::quotes::{version_3::verify_quote_dcapv3, version_4::verify_quote_dcapv4};
+
+use crate::quote::{cert_data_type_name, Quote, PCK_CERT_CHAIN_CERT_DATA_TYPE};
+
+/// Verifies `quote` against `collaterals` at `timestamp` with dcap-rs on the host, exactly as
+/// the guest program does: the quote and QE report signatures, the attestation key binding,
+/// the PCK chain, the TCB level and the QE identity.
+///
+/// dcap-rs panics when a check fails, the panic message is returned as the error.
+pub fn verify_quote(
+    quote: &[u8],
+    version: u16,
+    collaterals: &IntelCollateral,
+    timestamp: u64,
+) -> Result<VerifiedOutput> {
+    check_supported_quote(&Quote::from_bytes(quote)?)?;
+
+    // Silence the default hook, the panic is reported as an error instead
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        if version == 3 {
+            verify_quote_dcapv3(&QuoteV3::from_bytes(quote), collaterals, timestamp)
+        } else {
+            verify_quote_dcapv4(&QuoteV4::from_bytes(quote), collaterals, timestamp)
+        }
+    }));
+    panic::set_hook(hook);
+
+    result.map_err(|panic| {
+        anyhow::Error::msg(format!(
+            "Quote verification failed: {}",
+            panic_message(panic.as_ref())
+        ))
+    })
+}
+
+/// Fails for the quotes dcap-rs, and hence the guest program, cannot verify: quotes of other
+/// versions than v3 and v4, and quotes that carry a PCK identifier instead of their PCK
+/// certificate chain, as dcap-rs only reads the chain embedded in the quote
+pub fn check_supported_quote(quote: &Quote) -> Result<()> {
+    let version = quote.header.version;
+    if !(3..=4).contains(&version) {
+        return Err(anyhow::Error::msg(format!(
+            "dcap-rs does not verify v{} quotes",
+            version
+        )));
+    }
+    let (cert_data_type, _) = quote.signature.cert_data.pck_cert_data();
+    if cert_data_type != PCK_CERT_CHAIN_CERT_DATA_TYPE {
+        return Err(anyhow::Error::msg(format!(
+            "dcap-rs only verifies quotes that embed their PCK certificate chain, this quote carries: {}",
+            cert_data_type_name(cert_data_type)
+        )));
+    }
+    Ok(())
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown error"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quote::{
+        ENCLAVE_REPORT_SIZE, PPID_RSA3072_ENCRYPTED_CERT_DATA_TYPE, QUOTE_HEADER_SIZE,
+        TD10_REPORT_SIZE,
+    };
+
+    const QUOTE_HEX: &str = include_str!("../../../data/quote.hex");
+
+    /// Swaps the PCK certificate chain embedded in the sample v4 TDX quote for RSA-3072
+    /// encrypted PPID certification data
+    fn identifier_quote() -> Vec<u8> {
+        let quote = hex::decode(QUOTE_HEX.trim()).unwrap();
+        let qe_auth_data_len = Quote::from_bytes(&quote)
+            .unwrap()
+            .signature
+            .cert_data
+            .qe_report_cert_data()
+            .unwrap()
+            .qe_auth_data
+            .len();
+
+        // The signature data follows its length, and starts with the quote signature and the
+        // attestation key. The QE report certification data then follows its type and size.
+        let signature_data_offset = QUOTE_HEADER_SIZE + TD10_REPORT_SIZE + 4;
+        let qe_report_cert_data_offset = signature_data_offset + 64 + 64 + 2 + 4;
+        let pck_cert_data_offset =
+            qe_report_cert_data_offset + ENCLAVE_REPORT_SIZE + 64 + 2 + qe_auth_data_len;
+
+        // Encrypted PPID, CPUSVN, PCESVN and PCE ID
+        let mut quote = quote[..pck_cert_data_offset].to_vec();
+        quote.extend_from_slice(&PPID_RSA3072_ENCRYPTED_CERT_DATA_TYPE.to_le_bytes());
+        quote.extend_from_slice(&(384u32 + 16 + 2 + 2).to_le_bytes());
+        quote.extend_from_slice(&[0xaa; 384]);
+        quote.extend_from_slice(&[0x01; 16]);
+        quote.extend_from_slice(&13u16.to_le_bytes());
+        quote.extend_from_slice(&0u16.to_le_bytes());
+
+        let qe_report_cert_data_size = (quote.len() - qe_report_cert_data_offset) as u32;
+        quote[qe_report_cert_data_offset - 4..qe_report_cert_data_offset]
+            .copy_from_slice(&qe_report_cert_data_size.to_le_bytes());
+        let signature_data_len = (quote.len() - signature_data_offset) as u32;
+        quote[signature_data_offset - 4..signature_data_offset]
+            .copy_from_slice(&signature_data_len.to_le_bytes());
+        quote
+    }
+
+    #[test]
+    fn supports_v3_and_v4_quotes_embedding_their_pck_chain() {
+        let mut quote = Quote::from_bytes(&hex::decode(QUOTE_HEX.trim()).unwrap()).unwrap();
+        check_supported_quote(&quote).unwrap();
+
+        quote.header.version = 5;
+        let err = check_supported_quote(&quote).unwrap_err();
+        assert_eq!(err.to_string(), "dcap-rs does not verify v5 quotes");
+    }
+
+    #[test]
+    fn refuses_to_verify_quotes_carrying_a_pck_identifier() {
+        // dcap-rs asserts "QE Cert Type must be 5", whatever PCK chain the collaterals carry
+        let err = verify_quote(&identifier_quote(), 4, &IntelCollateral::new(), 0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "dcap-rs only verifies quotes that embed their PCK certificate chain, \
+             this quote carries: PPID encrypted with RSA-3072-OAEP, CPUSVN and PCESVN"
+        );
+    }
+}
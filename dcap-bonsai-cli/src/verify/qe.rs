@@ -0,0 +1,280 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt;
+
+use super::tcb::TcbLevelMatch;
+use crate::quote::{field, EnclaveReport};
+use crate::signed::{EnclaveIdentity, EnclaveTcbLevel, TcbStatus};
+
+/// How the QE report of a quote measures up to a QEIdentity or TDQE identity.
+#[derive(Debug, Clone, Serialize)]
+pub struct QeIdentityEvaluation {
+    /// QE, QVE or TD_QE
+    pub id: String,
+    pub version: u32,
+    pub tcb_evaluation_data_number: Option<u32>,
+    /// The identity fields the QE report does not match, the guest rejects the quote if any
+    pub mismatches: Vec<IdentityMismatch>,
+    pub isv_svn: u16,
+    /// The first TCB level the QE's ISVSVN meets, none if it is below all of them
+    pub level: Option<TcbLevelMatch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IdentityMismatch {
+    pub field: &'static str,
+    pub expected: String,
+    pub found: String,
+}
+
+/// Checks the QE report of a quote against an enclave identity:
+/// MRSIGNER, ISVPRODID, MISCSELECT and attributes under their masks, and the TCB level
+/// of its ISVSVN.
+pub fn evaluate_qe_identity(
+    identity: &EnclaveIdentity,
+    qe_report: &EnclaveReport,
+) -> Result<QeIdentityEvaluation> {
+    let mut mismatches = Vec::new();
+
+    let mrsigner = decode_hex::<32>("mrsigner", &identity.mrsigner)?;
+    if qe_report.mr_signer != mrsigner {
+        mismatches.push(IdentityMismatch {
+            field: "MRSIGNER",
+            expected: hex::encode(mrsigner),
+            found: hex::encode(qe_report.mr_signer),
+        });
+    }
+
+    if qe_report.isv_prod_id != identity.isvprodid {
+        mismatches.push(IdentityMismatch {
+            field: "ISVPRODID",
+            expected: identity.isvprodid.to_string(),
+            found: qe_report.isv_prod_id.to_string(),
+        });
+    }
+
+    // MISCSELECT is a little-endian integer in the report, a big-endian one in the identity
+    let miscselect = u32::from_be_bytes(decode_hex("miscselect", &identity.miscselect)?);
+    let miscselect_mask =
+        u32::from_be_bytes(decode_hex("miscselectMask", &identity.miscselect_mask)?);
+    if qe_report.misc_select & miscselect_mask != miscselect {
+        mismatches.push(IdentityMismatch {
+            field: "MISCSELECT",
+            expected: format!("{:08x} (mask {:08x})", miscselect, miscselect_mask),
+            found: format!("{:08x}", qe_report.misc_select),
+        });
+    }
+
+    let attributes = decode_hex::<16>("attributes", &identity.attributes)?;
+    let attributes_mask = decode_hex::<16>("attributesMask", &identity.attributes_mask)?;
+    let masked_attributes: Vec<u8> = qe_report
+        .attributes
+        .iter()
+        .zip(attributes_mask)
+        .map(|(attribute, mask)| attribute & mask)
+        .collect();
+    if masked_attributes != attributes {
+        mismatches.push(IdentityMismatch {
+            field: "ATTRIBUTES",
+            expected: format!(
+                "{} (mask {})",
+                hex::encode(attributes),
+                hex::encode(attributes_mask)
+            ),
+            found: hex::encode(qe_report.attributes),
+        });
+    }
+
+    let level = match_isv_svn(&identity.tcb_levels, qe_report.isv_svn);
+
+    Ok(QeIdentityEvaluation {
+        id: identity.id.clone(),
+        version: identity.version,
+        tcb_evaluation_data_number: Some(identity.tcb_evaluation_data_number),
+        mismatches,
+        isv_svn: qe_report.isv_svn,
+        level,
+    })
+}
+
+impl QeIdentityEvaluation {
+    /// The status of the matched TCB level
+    pub fn tcb_status(&self) -> TcbStatus {
+        self.level
+            .as_ref()
+            .map_or(TcbStatus::Unrecognized, |level| level.tcb_status)
+    }
+}
+
+/// Returns the first of `levels` that `isv_svn` meets
+pub(super) fn match_isv_svn(levels: &[EnclaveTcbLevel], isv_svn: u16) -> Option<TcbLevelMatch> {
+    levels
+        .iter()
+        .enumerate()
+        .find(|(_, level)| isv_svn >= level.tcb.isvsvn)
+        .map(|(index, level)| TcbLevelMatch {
+            index,
+            tcb_status: level.tcb_status,
+            tcb_date: level.tcb_date.clone(),
+            advisory_ids: level.advisory_ids.clone().unwrap_or_default(),
+        })
+}
+
+pub(super) fn decode_hex<const N: usize>(name: &str, value: &str) -> Result<[u8; N]> {
+    hex::decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            anyhow::Error::msg(format!(
+                "Invalid {}: {}, expected {} hex-encoded bytes",
+                name, value, N
+            ))
+        })
+}
+
+impl fmt::Display for QeIdentityEvaluation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "QE Identity Evaluation")?;
+        field(f, "ID", &self.id)?;
+        field(f, "Identity Version", self.version)?;
+        if let Some(number) = self.tcb_evaluation_data_number {
+            field(f, "TCB Eval Data Number", number)?;
+        }
+        for mismatch in &self.mismatches {
+            field(
+                f,
+                mismatch.field,
+                format!(
+                    "mismatch, expected {} found {}",
+                    mismatch.expected, mismatch.found
+                ),
+            )?;
+        }
+        field(f, "ISVSVN", self.isv_svn)?;
+        match &self.level {
+            Some(level) => {
+                field(f, "TCB Level", level.index)?;
+                field(f, "TCB Status", level.tcb_status)?;
+                field(f, "TCB Date", &level.tcb_date)?;
+                if !level.advisory_ids.is_empty() {
+                    field(f, "Advisory IDs", level.advisory_ids.join(", "))?;
+                }
+            }
+            None => field(
+                f,
+                "TCB Status",
+                "Unrecognized (no TCB level matches the QE)",
+            )?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quote::Quote;
+    use crate::signed::Signed;
+
+    const QUOTE_HEX: &str = include_str!("../../../data/quote.hex");
+    const QE_IDENTITY_V2: &str = include_str!("../../../data/collaterals/qe_identity_v2.json");
+    const TD_QE_IDENTITY_V2: &str =
+        include_str!("../../../data/collaterals/td_qe_identity_v2.json");
+
+    fn identity(json: &str) -> EnclaveIdentity {
+        Signed::<EnclaveIdentity>::from_json(json.as_bytes())
+            .unwrap()
+            .body
+    }
+
+    /// The TD_QE report of the sample TDX quote
+    fn qe_report() -> EnclaveReport {
+        let quote = Quote::from_bytes(&hex::decode(QUOTE_HEX.trim()).unwrap()).unwrap();
+        quote
+            .signature
+            .cert_data
+            .qe_report_cert_data()
+            .unwrap()
+            .qe_report
+            .clone()
+    }
+
+    fn mismatched_fields(evaluation: &QeIdentityEvaluation) -> Vec<&'static str> {
+        evaluation
+            .mismatches
+            .iter()
+            .map(|mismatch| mismatch.field)
+            .collect()
+    }
+
+    #[test]
+    fn matches_td_qe_identity() {
+        let evaluation = evaluate_qe_identity(&identity(TD_QE_IDENTITY_V2), &qe_report()).unwrap();
+        assert!(evaluation.mismatches.is_empty());
+        assert_eq!(evaluation.id, "TD_QE");
+        assert_eq!(evaluation.isv_svn, 5);
+        assert_eq!(evaluation.tcb_status(), TcbStatus::UpToDate);
+    }
+
+    #[test]
+    fn reports_mrsigner_and_isvprodid_mismatches() {
+        // The SGX QE identity does not apply to the TD_QE
+        let evaluation = evaluate_qe_identity(&identity(QE_IDENTITY_V2), &qe_report()).unwrap();
+        assert_eq!(mismatched_fields(&evaluation), ["MRSIGNER", "ISVPRODID"]);
+        assert_eq!(
+            evaluation.mismatches[0].expected,
+            "8c4f5775d796503e96137f77c68a829a0056ac8ded70140b081b094490c57bff"
+        );
+        assert_eq!(evaluation.mismatches[1].expected, "1");
+        assert_eq!(evaluation.mismatches[1].found, "2");
+    }
+
+    #[test]
+    fn compares_miscselect_and_attributes_under_their_masks() {
+        let mut identity = identity(TD_QE_IDENTITY_V2);
+        let mut qe_report = qe_report();
+
+        // Bits outside the masks do not matter
+        identity.miscselect_mask = String::from("FFFFFFFE");
+        qe_report.misc_select = 1;
+        qe_report.attributes[0] ^= 0x04;
+        let evaluation = evaluate_qe_identity(&identity, &qe_report).unwrap();
+        assert!(evaluation.mismatches.is_empty());
+
+        // Bits inside the masks do
+        qe_report.misc_select = 2;
+        qe_report.attributes[0] ^= 0x01;
+        let evaluation = evaluate_qe_identity(&identity, &qe_report).unwrap();
+        assert_eq!(mismatched_fields(&evaluation), ["MISCSELECT", "ATTRIBUTES"]);
+        assert_eq!(
+            evaluation.mismatches[0].expected,
+            "00000000 (mask fffffffe)"
+        );
+        assert_eq!(evaluation.mismatches[0].found, "00000002");
+    }
+
+    #[test]
+    fn selects_the_first_level_the_isvsvn_meets() {
+        let identity = identity(QE_IDENTITY_V2);
+        let mut qe_report = qe_report();
+
+        for (isv_svn, index, tcb_status) in [
+            (9, 0, TcbStatus::UpToDate),
+            (8, 0, TcbStatus::UpToDate),
+            (7, 1, TcbStatus::OutOfDate),
+            (3, 4, TcbStatus::OutOfDate),
+            (1, 5, TcbStatus::OutOfDate),
+        ] {
+            qe_report.isv_svn = isv_svn;
+            let evaluation = evaluate_qe_identity(&identity, &qe_report).unwrap();
+            let level = evaluation.level.unwrap();
+            assert_eq!((level.index, level.tcb_status), (index, tcb_status));
+            assert_eq!(level.tcb_date, identity.tcb_levels[index].tcb_date);
+        }
+
+        qe_report.isv_svn = 0;
+        let evaluation = evaluate_qe_identity(&identity, &qe_report).unwrap();
+        assert!(evaluation.level.is_none());
+        assert_eq!(evaluation.tcb_status(), TcbStatus::Unrecognized);
+    }
+}
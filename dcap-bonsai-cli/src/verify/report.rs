@@ -0,0 +1,175 @@
+use serde::Serialize;
+use std::fmt;
+
+use super::qe::QeIdentityEvaluation;
+use super::tcb::{converge_tcb_status, TcbEvaluation};
+use super::tdx::TdxModuleEvaluation;
+use crate::quote::field;
+use crate::signed::TcbStatus;
+
+/// The TCB statuses of a quote's platform, QE and, for TDX quotes, TDX module,
+/// and the status they add up to.
+#[derive(Debug, Clone, Serialize)]
+pub struct TcbReport {
+    pub platform: TcbEvaluation,
+    pub qe_identity: QeIdentityEvaluation,
+    pub tdx_module: Option<TdxModuleEvaluation>,
+    /// The status the guest will put in its output, none if it will reject the quote.
+    /// A platform that matches no TCB level is not rejected, dcap-rs outputs it as
+    /// TcbUnrecognized.
+    pub status: Option<TcbStatus>,
+}
+
+impl TcbReport {
+    pub fn new(
+        platform: TcbEvaluation,
+        qe_identity: QeIdentityEvaluation,
+        tdx_module: Option<TdxModuleEvaluation>,
+    ) -> Self {
+        let mut report = TcbReport {
+            platform,
+            qe_identity,
+            tdx_module,
+            status: None,
+        };
+        if report.rejection().is_none() {
+            let mut status = report.platform.tcb_status();
+            if let Some(module_status) = report.tdx_module_status() {
+                status = match module_status {
+                    TcbStatus::Revoked => TcbStatus::Revoked,
+                    _ => converge_tcb_status(status, module_status),
+                };
+            }
+            report.status = Some(converge_tcb_status(status, report.qe_identity.tcb_status()));
+        }
+        report
+    }
+
+    /// Why dcap-rs will reject the quote, if it will
+    pub fn rejection(&self) -> Option<&'static str> {
+        if !self.qe_identity.mismatches.is_empty() {
+            Some("the QE does not match its identity")
+        } else if self.qe_identity.tcb_status() == TcbStatus::Revoked {
+            Some("the QE TCB level is revoked")
+        } else if self
+            .tdx_module
+            .as_ref()
+            .is_some_and(|module| !module.mismatches.is_empty())
+        {
+            Some("the TDX module does not match its identity")
+        } else {
+            None
+        }
+    }
+
+    fn tdx_module_status(&self) -> Option<TcbStatus> {
+        self.tdx_module
+            .as_ref()
+            .and_then(TdxModuleEvaluation::tcb_status)
+    }
+}
+
+impl fmt::Display for TcbReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.platform)?;
+        write!(f, "{}", self.qe_identity)?;
+        if let Some(tdx_module) = &self.tdx_module {
+            write!(f, "{}", tdx_module)?;
+        }
+        writeln!(f, "Combined TCB Status")?;
+        field(f, "Platform", self.platform.tcb_status())?;
+        field(f, "QE", self.qe_identity.tcb_status())?;
+        if let Some(module_status) = self.tdx_module_status() {
+            field(f, "TDX Module", module_status)?;
+        }
+        match self.status {
+            Some(status) => field(f, "Combined", status),
+            None => field(
+                f,
+                "Combined",
+                format!("rejected, {}", self.rejection().unwrap_or_default()),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{get_pck_certification_data, get_pck_extensions, PckCertificationData};
+    use crate::pem_chain_to_der;
+    use crate::quote::Quote;
+    use crate::signed::{EnclaveIdentity, Signed, TcbInfo};
+    use crate::verify::qe::{evaluate_qe_identity, IdentityMismatch};
+    use crate::verify::tcb::evaluate_tcb;
+
+    const QUOTE_HEX: &str = include_str!("../../../data/quote.hex");
+    const TCB_INFO_V3_TDX: &str = include_str!("../../../data/collaterals/tcb_info_v3_tdx.json");
+    const TD_QE_IDENTITY_V2: &str =
+        include_str!("../../../data/collaterals/td_qe_identity_v2.json");
+
+    /// The platform and TD_QE evaluations of the sample TDX quote, its PCESVN replaced if given
+    fn evaluations(pce_svn: Option<u16>) -> (TcbEvaluation, QeIdentityEvaluation) {
+        let tcb_info = Signed::<TcbInfo>::from_json(TCB_INFO_V3_TDX.as_bytes())
+            .unwrap()
+            .body;
+        let identity = Signed::<EnclaveIdentity>::from_json(TD_QE_IDENTITY_V2.as_bytes())
+            .unwrap()
+            .body;
+        let quote = Quote::from_bytes(&hex::decode(QUOTE_HEX.trim()).unwrap()).unwrap();
+        let pck_cert_chain = match get_pck_certification_data(&quote).unwrap() {
+            PckCertificationData::CertChain(chain) => chain,
+            PckCertificationData::Identifier(_) => panic!("The sample quote embeds its PCK chain"),
+        };
+        let mut pck = get_pck_extensions(&pem_chain_to_der(&pck_cert_chain).unwrap()[0]).unwrap();
+        if let Some(pce_svn) = pce_svn {
+            pck.tcb.pce_svn = pce_svn;
+        }
+        let qe_report = &quote
+            .signature
+            .cert_data
+            .qe_report_cert_data()
+            .unwrap()
+            .qe_report;
+        (
+            evaluate_tcb(&tcb_info, &pck, quote.body.tee_tcb_svn()).unwrap(),
+            evaluate_qe_identity(&identity, qe_report).unwrap(),
+        )
+    }
+
+    #[test]
+    fn outputs_status_of_matched_levels() {
+        let (platform, qe_identity) = evaluations(None);
+
+        let report = TcbReport::new(platform, qe_identity, None);
+        assert!(report.rejection().is_none());
+        assert_eq!(report.status, Some(TcbStatus::OutOfDate));
+    }
+
+    #[test]
+    fn outputs_unrecognized_platform() {
+        let (platform, qe_identity) = evaluations(Some(10));
+        assert!(platform.level.is_none());
+
+        let report = TcbReport::new(platform, qe_identity, None);
+        assert!(report.rejection().is_none());
+        assert_eq!(report.status, Some(TcbStatus::Unrecognized));
+    }
+
+    #[test]
+    fn rejects_mismatched_qe() {
+        let (platform, mut qe_identity) = evaluations(None);
+        qe_identity.mismatches.push(IdentityMismatch {
+            field: "ISVPRODID",
+            expected: String::from("2"),
+            found: String::from("1"),
+        });
+
+        let report = TcbReport::new(platform, qe_identity, None);
+        assert_eq!(
+            report.rejection(),
+            Some("the QE does not match its identity")
+        );
+        assert!(report.status.is_none());
+    }
+}
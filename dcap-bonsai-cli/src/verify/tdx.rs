@@ -0,0 +1,299 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt;
+
+use super::qe::{decode_hex, match_isv_svn, IdentityMismatch};
+use super::tcb::TcbLevelMatch;
+use crate::quote::{field, TdReport10};
+use crate::signed::{TcbInfo, TcbStatus, TdxModule};
+
+/// How the TDX module of a TD report measures up to the TDX module identities of a TCBInfo v3.
+#[derive(Debug, Clone, Serialize)]
+pub struct TdxModuleEvaluation {
+    /// TDX module major version, the second byte of TEE_TCB_SVN
+    pub major_version: u8,
+    /// TDX module SVN, the first byte of TEE_TCB_SVN
+    pub isv_svn: u8,
+    /// The TDX module identity applying to the module, none for TDX 1.0 modules,
+    /// which are only checked against `tdxModule`
+    pub identity_id: Option<String>,
+    /// The identity fields the module does not match, the guest rejects the quote if any
+    pub mismatches: Vec<IdentityMismatch>,
+    /// The first TCB level of the identity the module SVN meets
+    pub level: Option<TcbLevelMatch>,
+}
+
+/// Checks the TDX module of a TD report against the TDX module identities of a TCBInfo v3.
+///
+/// TDX 1.0 modules (major version 0) are checked against `tdxModule`, later ones against
+/// the `tdxModuleIdentities` entry `TDX_<major version>`, whose TCB levels are matched
+/// on the module SVN.
+pub fn evaluate_tdx_module(
+    tcb_info: &TcbInfo,
+    td_report: &TdReport10,
+) -> Result<TdxModuleEvaluation> {
+    let tdx_module = tcb_info.tdx_module.as_ref().ok_or_else(|| {
+        anyhow::Error::msg("The TCBInfo has no TDX module identity, expected a TDX TCBInfo v3")
+    })?;
+
+    let isv_svn = td_report.tee_tcb_svn[0];
+    let major_version = td_report.tee_tcb_svn[1];
+    let mut evaluation = TdxModuleEvaluation {
+        major_version,
+        isv_svn,
+        identity_id: None,
+        mismatches: Vec::new(),
+        level: None,
+    };
+
+    if major_version == 0 {
+        evaluation.mismatches = module_mismatches(tdx_module, td_report)?;
+        return Ok(evaluation);
+    }
+
+    let id = format!("TDX_{:02X}", major_version);
+    let identities = tcb_info
+        .tdx_module_identities
+        .as_deref()
+        .unwrap_or_default();
+    match identities
+        .iter()
+        .find(|identity| identity.id.eq_ignore_ascii_case(&id))
+    {
+        Some(identity) => {
+            evaluation.mismatches = module_mismatches(&identity.module, td_report)?;
+            evaluation.level = match_isv_svn(&identity.tcb_levels, isv_svn as u16);
+        }
+        None => evaluation.mismatches.push(IdentityMismatch {
+            field: "TDX Module Identity",
+            expected: identities
+                .iter()
+                .map(|identity| identity.id.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            found: id.clone(),
+        }),
+    }
+    evaluation.identity_id = Some(id);
+
+    Ok(evaluation)
+}
+
+impl TdxModuleEvaluation {
+    /// The status of the matched TCB level, none for TDX 1.0 modules
+    pub fn tcb_status(&self) -> Option<TcbStatus> {
+        self.identity_id.as_ref().map(|_| {
+            self.level
+                .as_ref()
+                .map_or(TcbStatus::Unrecognized, |level| level.tcb_status)
+        })
+    }
+}
+
+/// Checks MRSIGNERSEAM and the SEAM attributes under their mask
+fn module_mismatches(module: &TdxModule, td_report: &TdReport10) -> Result<Vec<IdentityMismatch>> {
+    let mut mismatches = Vec::new();
+
+    let mrsigner = decode_hex::<48>("TDX module mrsigner", &module.mrsigner)?;
+    if td_report.mr_signer_seam != mrsigner {
+        mismatches.push(IdentityMismatch {
+            field: "MRSIGNERSEAM",
+            expected: hex::encode(mrsigner),
+            found: hex::encode(td_report.mr_signer_seam),
+        });
+    }
+
+    let attributes = decode_hex::<8>("TDX module attributes", &module.attributes)?;
+    let attributes_mask = decode_hex::<8>("TDX module attributesMask", &module.attributes_mask)?;
+    let masked_attributes: Vec<u8> = td_report
+        .seam_attributes
+        .iter()
+        .zip(attributes_mask)
+        .map(|(attribute, mask)| attribute & mask)
+        .collect();
+    if masked_attributes != attributes {
+        mismatches.push(IdentityMismatch {
+            field: "SEAMATTRIBUTES",
+            expected: format!(
+                "{} (mask {})",
+                hex::encode(attributes),
+                hex::encode(attributes_mask)
+            ),
+            found: hex::encode(td_report.seam_attributes),
+        });
+    }
+
+    Ok(mismatches)
+}
+
+impl fmt::Display for TdxModuleEvaluation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "TDX Module Evaluation")?;
+        field(f, "Major Version", self.major_version)?;
+        field(f, "SVN", self.isv_svn)?;
+        field(
+            f,
+            "Identity",
+            self.identity_id.as_deref().unwrap_or("tdxModule (TDX 1.0)"),
+        )?;
+        for mismatch in &self.mismatches {
+            field(
+                f,
+                mismatch.field,
+                format!(
+                    "mismatch, expected {} found {}",
+                    mismatch.expected, mismatch.found
+                ),
+            )?;
+        }
+        if self.identity_id.is_some() {
+            match &self.level {
+                Some(level) => {
+                    field(f, "TCB Level", level.index)?;
+                    field(f, "TCB Status", level.tcb_status)?;
+                    field(f, "TCB Date", &level.tcb_date)?;
+                    if !level.advisory_ids.is_empty() {
+                        field(f, "Advisory IDs", level.advisory_ids.join(", "))?;
+                    }
+                }
+                None => field(
+                    f,
+                    "TCB Status",
+                    "Unrecognized (no TCB level matches the TDX module)",
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quote::Quote;
+    use crate::signed::{EnclaveIdentity, Signed};
+    use crate::verify::qe::evaluate_qe_identity;
+    use crate::verify::report::TcbReport;
+    use crate::verify::tcb::TcbEvaluation;
+
+    const QUOTE_HEX: &str = include_str!("../../../data/quote.hex");
+    const TCB_INFO_V3_TDX: &str = include_str!("../../../data/collaterals/tcb_info_v3_tdx.json");
+    const TD_QE_IDENTITY_V2: &str =
+        include_str!("../../../data/collaterals/td_qe_identity_v2.json");
+
+    fn tcb_info() -> TcbInfo {
+        Signed::<TcbInfo>::from_json(TCB_INFO_V3_TDX.as_bytes())
+            .unwrap()
+            .body
+    }
+
+    /// The sample quote, whose TDX module has SVN 4 and major version 1
+    fn quote() -> Quote {
+        Quote::from_bytes(&hex::decode(QUOTE_HEX.trim()).unwrap()).unwrap()
+    }
+
+    fn td_report(quote: &Quote) -> TdReport10 {
+        quote.body.td_report().unwrap().clone()
+    }
+
+    fn platform(tcb_status: TcbStatus) -> TcbEvaluation {
+        TcbEvaluation {
+            fmspc: String::from("90C06F000000"),
+            tcb_info_version: 3,
+            tcb_evaluation_data_number: Some(17),
+            level: Some(TcbLevelMatch {
+                index: 0,
+                tcb_status,
+                tcb_date: String::from("2024-03-13T00:00:00Z"),
+                advisory_ids: Vec::new(),
+            }),
+            next_level: None,
+            up_to_date_level: None,
+        }
+    }
+
+    #[test]
+    fn matches_tdx_module_identity() {
+        let evaluation = evaluate_tdx_module(&tcb_info(), &td_report(&quote())).unwrap();
+        assert_eq!((evaluation.major_version, evaluation.isv_svn), (1, 4));
+        assert_eq!(evaluation.identity_id.as_deref(), Some("TDX_01"));
+        assert!(evaluation.mismatches.is_empty());
+        assert_eq!(evaluation.level.as_ref().unwrap().index, 1);
+        assert_eq!(evaluation.tcb_status(), Some(TcbStatus::OutOfDate));
+    }
+
+    #[test]
+    fn checks_tdx_1_0_modules_against_tdx_module() {
+        let mut td_report = td_report(&quote());
+        td_report.tee_tcb_svn[1] = 0;
+        let evaluation = evaluate_tdx_module(&tcb_info(), &td_report).unwrap();
+        assert!(evaluation.identity_id.is_none());
+        assert!(evaluation.mismatches.is_empty());
+        assert_eq!(evaluation.tcb_status(), None);
+
+        td_report.mr_signer_seam[0] = 1;
+        let evaluation = evaluate_tdx_module(&tcb_info(), &td_report).unwrap();
+        assert_eq!(evaluation.mismatches[0].field, "MRSIGNERSEAM");
+    }
+
+    #[test]
+    fn reports_unknown_tdx_module_id() {
+        let mut td_report = td_report(&quote());
+        td_report.tee_tcb_svn[1] = 2;
+        let evaluation = evaluate_tdx_module(&tcb_info(), &td_report).unwrap();
+        assert_eq!(evaluation.identity_id.as_deref(), Some("TDX_02"));
+        assert_eq!(evaluation.mismatches.len(), 1);
+        let mismatch = &evaluation.mismatches[0];
+        assert_eq!(mismatch.field, "TDX Module Identity");
+        assert_eq!(mismatch.expected, "TDX_03, TDX_01");
+        assert_eq!(mismatch.found, "TDX_02");
+        assert_eq!(evaluation.tcb_status(), Some(TcbStatus::Unrecognized));
+    }
+
+    #[test]
+    fn converges_module_and_platform_statuses() {
+        let quote = quote();
+        let identity = Signed::<EnclaveIdentity>::from_json(TD_QE_IDENTITY_V2.as_bytes())
+            .unwrap()
+            .body;
+        let qe_report = &quote
+            .signature
+            .cert_data
+            .qe_report_cert_data()
+            .unwrap()
+            .qe_report;
+        let qe_identity = evaluate_qe_identity(&identity, qe_report).unwrap();
+        assert_eq!(qe_identity.tcb_status(), TcbStatus::UpToDate);
+
+        // An out of date module makes an up to date platform out of date
+        let mut td_report = td_report(&quote);
+        let module = evaluate_tdx_module(&tcb_info(), &td_report).unwrap();
+        let report = TcbReport::new(
+            platform(TcbStatus::UpToDate),
+            qe_identity.clone(),
+            Some(module),
+        );
+        assert_eq!(report.status, Some(TcbStatus::OutOfDate));
+
+        // An up to date module leaves the platform status as is
+        td_report.tee_tcb_svn[0] = 5;
+        let module = evaluate_tdx_module(&tcb_info(), &td_report).unwrap();
+        assert_eq!(module.tcb_status(), Some(TcbStatus::UpToDate));
+        let report = TcbReport::new(
+            platform(TcbStatus::ConfigurationNeeded),
+            qe_identity.clone(),
+            Some(module),
+        );
+        assert_eq!(report.status, Some(TcbStatus::ConfigurationNeeded));
+
+        // An unknown module gets the quote rejected
+        td_report.tee_tcb_svn[1] = 2;
+        let module = evaluate_tdx_module(&tcb_info(), &td_report).unwrap();
+        let report = TcbReport::new(platform(TcbStatus::UpToDate), qe_identity, Some(module));
+        assert_eq!(report.status, None);
+        assert_eq!(
+            report.rejection(),
+            Some("the TDX module does not match its identity")
+        );
+    }
+}
@@ -0,0 +1,511 @@
+use alloy::primitives::keccak256;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use x509_parser::pem::Pem;
+use x509_parser::prelude::*;
+
+use crate::chain::block::PinnedBlock;
+use crate::collaterals::Collaterals;
+use crate::provider::{ca_name, CollateralRequest};
+use crate::quote::field;
+use crate::signed::{EnclaveIdentity, EnclaveTcbLevel, Signed, TcbComponent, TcbInfo, TcbLevel};
+use crate::{der_to_pem, format_timestamp, remove_prefix_if_found, to_der};
+
+/// The exact collateral set used for a quote, with where it was read from.
+///
+/// TCBInfo and QEIdentity are kept in Intel's signed format as passed to the guest,
+/// certificates as PEM and CRLs as hex-encoded DER.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollateralBundle {
+    /// The collateral source, as named in the logs
+    pub source: String,
+    /// The block the on-chain collaterals were read at, none for off-chain sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<BundleBlock>,
+    pub fmspc: String,
+    /// The PCK CA the PCK CRL belongs to, platform or processor
+    pub pck_ca: String,
+    pub tcb_info: String,
+    pub qe_identity: String,
+    pub root_ca: String,
+    pub tcb_signing_ca: String,
+    pub root_ca_crl: String,
+    pub pck_crl: String,
+    /// PEM PCK certificate chain, only set when the quote does not embed it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pck_certchain: Option<String>,
+    pub hashes: BundleHashes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleBlock {
+    pub number: u64,
+    pub hash: String,
+}
+
+/// Keccak-256 hashes of the collaterals, as passed to the guest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleHashes {
+    pub tcb_info: String,
+    pub qe_identity: String,
+    pub root_ca: String,
+    pub tcb_signing_ca: String,
+    pub root_ca_crl: String,
+    pub pck_crl: String,
+}
+
+impl BundleHashes {
+    pub fn new(collaterals: &Collaterals) -> Self {
+        BundleHashes {
+            tcb_info: hash(&collaterals.tcb_info),
+            qe_identity: hash(&collaterals.qe_identity),
+            root_ca: hash(&collaterals.root_ca),
+            tcb_signing_ca: hash(&collaterals.tcb_signing_ca),
+            root_ca_crl: hash(&collaterals.root_ca_crl),
+            pck_crl: hash(&collaterals.pck_crl),
+        }
+    }
+}
+
+fn hash(data: &[u8]) -> String {
+    hex::encode(keccak256(data))
+}
+
+impl CollateralBundle {
+    pub fn new(
+        source: &str,
+        block: Option<&PinnedBlock>,
+        request: &CollateralRequest,
+        collaterals: &Collaterals,
+    ) -> Result<Self> {
+        Ok(CollateralBundle {
+            source: source.to_string(),
+            block: block.map(|block| BundleBlock {
+                number: block.number,
+                hash: block.hash.to_string(),
+            }),
+            fmspc: request.fmspc.clone(),
+            pck_ca: ca_name(request.pck_ca).to_string(),
+            tcb_info: String::from_utf8(collaterals.tcb_info.clone())
+                .context("The TCBInfo is not UTF-8")?,
+            qe_identity: String::from_utf8(collaterals.qe_identity.clone())
+                .context("The QEIdentity is not UTF-8")?,
+            root_ca: der_to_pem(&collaterals.root_ca, "CERTIFICATE"),
+            tcb_signing_ca: der_to_pem(&collaterals.tcb_signing_ca, "CERTIFICATE"),
+            root_ca_crl: hex::encode(&collaterals.root_ca_crl),
+            pck_crl: hex::encode(&collaterals.pck_crl),
+            pck_certchain: if collaterals.pck_certchain.is_empty() {
+                None
+            } else {
+                Some(
+                    String::from_utf8(collaterals.pck_certchain.clone())
+                        .context("The PCK certificate chain is not PEM")?,
+                )
+            },
+            hashes: BundleHashes::new(collaterals),
+        })
+    }
+
+    pub fn from_json(json: &[u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Decodes the collaterals, failing if they do not match the recorded hashes
+    pub fn collaterals(&self) -> Result<Collaterals> {
+        let mut collaterals = Collaterals::new(
+            self.tcb_info.as_bytes().to_vec(),
+            self.qe_identity.as_bytes().to_vec(),
+            to_der(self.root_ca.as_bytes())?,
+            to_der(self.tcb_signing_ca.as_bytes())?,
+            hex::decode(remove_prefix_if_found(&self.root_ca_crl))?,
+            hex::decode(remove_prefix_if_found(&self.pck_crl))?,
+        );
+        if let Some(pck_certchain) = &self.pck_certchain {
+            collaterals.pck_certchain = pck_certchain.as_bytes().to_vec();
+        }
+
+        let hashes = BundleHashes::new(&collaterals);
+        let mismatches: Vec<&str> = [
+            ("TCBInfo", &hashes.tcb_info, &self.hashes.tcb_info),
+            ("QEIdentity", &hashes.qe_identity, &self.hashes.qe_identity),
+            ("Root CA", &hashes.root_ca, &self.hashes.root_ca),
+            (
+                "TCB Signing CA",
+                &hashes.tcb_signing_ca,
+                &self.hashes.tcb_signing_ca,
+            ),
+            ("Root CA CRL", &hashes.root_ca_crl, &self.hashes.root_ca_crl),
+            ("PCK CRL", &hashes.pck_crl, &self.hashes.pck_crl),
+        ]
+        .into_iter()
+        .filter(|(_, found, expected)| {
+            !found.eq_ignore_ascii_case(remove_prefix_if_found(expected))
+        })
+        .map(|(name, _, _)| name)
+        .collect();
+        if !mismatches.is_empty() {
+            return Err(anyhow::Error::msg(format!(
+                "The bundle hashes do not match its {}",
+                mismatches.join(", ")
+            )));
+        }
+
+        Ok(collaterals)
+    }
+
+    /// Decodes the bundle into what `collaterals show` prints
+    pub fn summary(&self) -> Result<BundleSummary> {
+        let collaterals = self.collaterals()?;
+
+        let mut certificates = vec![
+            certificate_summary("Intel SGX Root CA", &collaterals.root_ca)?,
+            certificate_summary("Intel TCB Signing CA", &collaterals.tcb_signing_ca)?,
+        ];
+        if let Some(pck_certchain) = &self.pck_certchain {
+            let names = ["PCK Certificate", "PCK CA", "PCK Chain Root CA"];
+            for (name, pem) in names
+                .into_iter()
+                .zip(Pem::iter_from_buffer(pck_certchain.as_bytes()))
+            {
+                certificates.push(certificate_summary(name, &pem?.contents)?);
+            }
+        }
+
+        Ok(BundleSummary {
+            source: self.source.clone(),
+            block: self.block.clone(),
+            fmspc: self.fmspc.clone(),
+            pck_ca: self.pck_ca.clone(),
+            tcb_info: Signed::from_json(&collaterals.tcb_info)?,
+            qe_identity: Signed::from_json(&collaterals.qe_identity)?,
+            certificates,
+            crls: vec![
+                crl_summary("Root CA CRL", &collaterals.root_ca_crl)?,
+                crl_summary("PCK CRL", &collaterals.pck_crl)?,
+            ],
+            hashes: self.hashes.clone(),
+        })
+    }
+}
+
+/// A decoded collateral bundle
+#[derive(Debug, Clone)]
+pub struct BundleSummary {
+    pub source: String,
+    pub block: Option<BundleBlock>,
+    pub fmspc: String,
+    pub pck_ca: String,
+    pub tcb_info: Signed<TcbInfo>,
+    pub qe_identity: Signed<EnclaveIdentity>,
+    pub certificates: Vec<CertificateSummary>,
+    pub crls: Vec<CrlSummary>,
+    pub hashes: BundleHashes,
+}
+
+#[derive(Debug, Clone)]
+pub struct CertificateSummary {
+    pub name: &'static str,
+    pub subject: String,
+    pub issuer: String,
+    pub serial: String,
+    pub not_before: i64,
+    pub not_after: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CrlSummary {
+    pub name: &'static str,
+    pub issuer: String,
+    pub this_update: i64,
+    pub next_update: Option<i64>,
+    pub revoked_serials: Vec<String>,
+}
+
+fn certificate_summary(name: &'static str, der: &[u8]) -> Result<CertificateSummary> {
+    let (_, cert) = parse_x509_certificate(der)
+        .map_err(|e| anyhow::Error::msg(format!("Invalid {}: {}", name, e)))?;
+    Ok(CertificateSummary {
+        name,
+        subject: cert.subject().to_string(),
+        issuer: cert.issuer().to_string(),
+        serial: cert.raw_serial_as_string(),
+        not_before: cert.validity().not_before.timestamp(),
+        not_after: cert.validity().not_after.timestamp(),
+    })
+}
+
+fn crl_summary(name: &'static str, der: &[u8]) -> Result<CrlSummary> {
+    let (_, crl) =
+        parse_x509_crl(der).map_err(|e| anyhow::Error::msg(format!("Invalid {}: {}", name, e)))?;
+    Ok(CrlSummary {
+        name,
+        issuer: crl.issuer().to_string(),
+        this_update: crl.last_update().timestamp(),
+        next_update: crl.next_update().map(|next_update| next_update.timestamp()),
+        revoked_serials: crl
+            .iter_revoked_certificates()
+            .map(|revoked| revoked.raw_serial_as_string())
+            .collect(),
+    })
+}
+
+fn tcb_level(f: &mut fmt::Formatter, index: usize, level: &TcbLevel) -> fmt::Result {
+    writeln!(f, "TCB Level {}", index)?;
+    field(f, "TCB Status", level.tcb_status)?;
+    field(f, "TCB Date", &level.tcb_date)?;
+    field(f, "SGX TCB Components", svns(&level.tcb.sgx_components()))?;
+    field(f, "PCESVN", level.tcb.pce_svn())?;
+    if let Some(components) = level.tcb.tdx_components() {
+        field(f, "TDX TCB Components", svns(components))?;
+    }
+    advisory_ids(f, &level.advisory_ids)
+}
+
+fn svns(components: &[TcbComponent]) -> String {
+    components
+        .iter()
+        .map(|component| component.svn.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn enclave_tcb_level(f: &mut fmt::Formatter, index: usize, level: &EnclaveTcbLevel) -> fmt::Result {
+    writeln!(f, "TCB Level {}", index)?;
+    field(f, "ISVSVN", level.tcb.isvsvn)?;
+    field(f, "TCB Status", level.tcb_status)?;
+    field(f, "TCB Date", &level.tcb_date)?;
+    advisory_ids(f, &level.advisory_ids)
+}
+
+fn advisory_ids(f: &mut fmt::Formatter, advisory_ids: &Option<Vec<String>>) -> fmt::Result {
+    match advisory_ids {
+        Some(advisory_ids) if !advisory_ids.is_empty() => {
+            field(f, "Advisory IDs", advisory_ids.join(", "))
+        }
+        _ => Ok(()),
+    }
+}
+
+impl fmt::Display for BundleSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Collateral Bundle")?;
+        field(f, "Source", &self.source)?;
+        if let Some(block) = &self.block {
+            field(f, "Block", format!("{} ({})", block.number, block.hash))?;
+        }
+        field(f, "FMSPC", &self.fmspc)?;
+        field(f, "PCK CA", &self.pck_ca)?;
+
+        let tcb_info = &self.tcb_info.body;
+        writeln!(f, "TCBInfo")?;
+        if let Some(id) = &tcb_info.id {
+            field(f, "ID", id)?;
+        }
+        field(f, "Version", tcb_info.version)?;
+        field(f, "Issue Date", &tcb_info.issue_date)?;
+        field(f, "Next Update", &tcb_info.next_update)?;
+        field(f, "FMSPC", &tcb_info.fmspc)?;
+        field(f, "PCE-ID", &tcb_info.pce_id)?;
+        field(f, "TCB Type", tcb_info.tcb_type)?;
+        if let Some(number) = tcb_info.tcb_evaluation_data_number {
+            field(f, "TCB Evaluation Data", number)?;
+        }
+        for (index, level) in tcb_info.tcb_levels.iter().enumerate() {
+            tcb_level(f, index, level)?;
+        }
+        if let Some(tdx_module) = &tcb_info.tdx_module {
+            writeln!(f, "TDX Module")?;
+            field(f, "MRSIGNER", &tdx_module.mrsigner)?;
+            field(
+                f,
+                "Attributes",
+                format!(
+                    "{} (mask {})",
+                    tdx_module.attributes, tdx_module.attributes_mask
+                ),
+            )?;
+        }
+        for identity in tcb_info.tdx_module_identities.iter().flatten() {
+            writeln!(f, "TDX Module Identity {}", identity.id)?;
+            field(f, "MRSIGNER", &identity.module.mrsigner)?;
+            field(
+                f,
+                "Attributes",
+                format!(
+                    "{} (mask {})",
+                    identity.module.attributes, identity.module.attributes_mask
+                ),
+            )?;
+            for (index, level) in identity.tcb_levels.iter().enumerate() {
+                enclave_tcb_level(f, index, level)?;
+            }
+        }
+
+        let qe_identity = &self.qe_identity.body;
+        writeln!(f, "QE Identity")?;
+        field(f, "ID", &qe_identity.id)?;
+        field(f, "Version", qe_identity.version)?;
+        field(f, "Issue Date", &qe_identity.issue_date)?;
+        field(f, "Next Update", &qe_identity.next_update)?;
+        field(
+            f,
+            "TCB Evaluation Data",
+            qe_identity.tcb_evaluation_data_number,
+        )?;
+        field(f, "MRSIGNER", &qe_identity.mrsigner)?;
+        field(f, "ISVPRODID", qe_identity.isvprodid)?;
+        field(
+            f,
+            "MISCSELECT",
+            format!(
+                "{} (mask {})",
+                qe_identity.miscselect, qe_identity.miscselect_mask
+            ),
+        )?;
+        field(
+            f,
+            "Attributes",
+            format!(
+                "{} (mask {})",
+                qe_identity.attributes, qe_identity.attributes_mask
+            ),
+        )?;
+        for (index, level) in qe_identity.tcb_levels.iter().enumerate() {
+            enclave_tcb_level(f, index, level)?;
+        }
+
+        for certificate in &self.certificates {
+            writeln!(f, "{}", certificate.name)?;
+            field(f, "Subject", &certificate.subject)?;
+            field(f, "Issuer", &certificate.issuer)?;
+            field(f, "Serial", &certificate.serial)?;
+            field(f, "Not Before", format_timestamp(certificate.not_before))?;
+            field(f, "Not After", format_timestamp(certificate.not_after))?;
+        }
+
+        for crl in &self.crls {
+            writeln!(f, "{}", crl.name)?;
+            field(f, "Issuer", &crl.issuer)?;
+            field(f, "This Update", format_timestamp(crl.this_update))?;
+            if let Some(next_update) = crl.next_update {
+                field(f, "Next Update", format_timestamp(next_update))?;
+            }
+            field(f, "Revoked", crl.revoked_serials.len())?;
+            for serial in &crl.revoked_serials {
+                field(f, "Revoked Serial", serial)?;
+            }
+        }
+
+        writeln!(f, "Hashes (Keccak-256)")?;
+        field(f, "TCBInfo", &self.hashes.tcb_info)?;
+        field(f, "QEIdentity", &self.hashes.qe_identity)?;
+        field(f, "Root CA", &self.hashes.root_ca)?;
+        field(f, "TCB Signing CA", &self.hashes.tcb_signing_ca)?;
+        field(f, "Root CA CRL", &self.hashes.root_ca_crl)?;
+        field(f, "PCK CRL", &self.hashes.pck_crl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::B256;
+
+    use super::*;
+    use crate::chain::pccs::{enclave_id::EnclaveIdType, pcs::IPCSDao::CA};
+    use crate::parser::{get_pck_certification_data, PckCertificationData};
+    use crate::pem_chain_to_der;
+    use crate::quote::Quote;
+
+    const QUOTE_HEX: &str = include_str!("../../data/quote.hex");
+    const TCB_INFO_V3_TDX: &str = include_str!("../../data/collaterals/tcb_info_v3_tdx.json");
+    const TD_QE_IDENTITY_V2: &str = include_str!("../../data/collaterals/td_qe_identity_v2.json");
+    const TEST_ROOT_CA: &str = include_str!("../../data/collaterals/test_root_ca.pem");
+    const TEST_SIGNING_CA: &str = include_str!("../../data/collaterals/test_tcb_signing_ca.pem");
+    const TEST_ROOT_CA_CRL: &str = include_str!("../../data/collaterals/test_root_ca_crl.pem");
+
+    fn der(pem: &str) -> Vec<u8> {
+        pem_chain_to_der(pem.as_bytes()).unwrap().remove(0)
+    }
+
+    /// The fixtures with the test PKI, and the PCK chain of the sample quote as if it had
+    /// been read from the PCK DAO
+    fn collaterals() -> Collaterals {
+        let quote = Quote::from_bytes(&hex::decode(QUOTE_HEX.trim()).unwrap()).unwrap();
+        let PckCertificationData::CertChain(chain) = get_pck_certification_data(&quote).unwrap()
+        else {
+            panic!("The sample quote embeds its PCK chain");
+        };
+        let mut collaterals = Collaterals::new(
+            TCB_INFO_V3_TDX.as_bytes().to_vec(),
+            TD_QE_IDENTITY_V2.as_bytes().to_vec(),
+            der(TEST_ROOT_CA),
+            der(TEST_SIGNING_CA),
+            der(TEST_ROOT_CA_CRL),
+            der(TEST_ROOT_CA_CRL),
+        );
+        collaterals.pck_certchain = chain;
+        collaterals
+    }
+
+    fn bundle() -> CollateralBundle {
+        let request = CollateralRequest {
+            fmspc: "90C06F000000".to_string(),
+            pck_ca: CA::PLATFORM,
+            tcb_type: 1,
+            tcb_version: 3,
+            qe_id_type: EnclaveIdType::TDQE,
+            qe_id_version: 4,
+        };
+        let block = PinnedBlock {
+            number: 1234567,
+            hash: B256::repeat_byte(0xab),
+        };
+        CollateralBundle::new("onchain", Some(&block), &request, &collaterals()).unwrap()
+    }
+
+    #[test]
+    fn round_trips_collaterals() {
+        let json = bundle().to_json().unwrap();
+        let bundle = CollateralBundle::from_json(json.as_bytes()).unwrap();
+        assert_eq!(bundle.source, "onchain");
+        assert_eq!(bundle.block.as_ref().unwrap().number, 1234567);
+        assert_eq!(bundle.pck_ca, "platform");
+
+        let expected = collaterals();
+        let decoded = bundle.collaterals().unwrap();
+        assert_eq!(decoded.tcb_info, expected.tcb_info);
+        assert_eq!(decoded.qe_identity, expected.qe_identity);
+        assert_eq!(decoded.root_ca, expected.root_ca);
+        assert_eq!(decoded.tcb_signing_ca, expected.tcb_signing_ca);
+        assert_eq!(decoded.root_ca_crl, expected.root_ca_crl);
+        assert_eq!(decoded.pck_crl, expected.pck_crl);
+        assert_eq!(decoded.pck_certchain, expected.pck_certchain);
+
+        let summary = bundle.summary().unwrap();
+        assert_eq!(summary.certificates.len(), 5);
+        assert_eq!(summary.crls.len(), 2);
+    }
+
+    #[test]
+    fn fails_on_tampered_collaterals() {
+        let mut bundle = bundle();
+        bundle.tcb_info = bundle.tcb_info.replacen(
+            "\"fmspc\":\"90C06F000000\"",
+            "\"fmspc\":\"90C06F000001\"",
+            1,
+        );
+        let crl = der(TEST_ROOT_CA_CRL);
+        bundle.pck_crl = hex::encode(&crl[..crl.len() - 1]);
+
+        let err = bundle.collaterals().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The bundle hashes do not match its TCBInfo, PCK CRL"
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::Result;
+
+use crate::config::ChainConfig;
+use crate::remove_prefix_if_found;
+
+use alloy::{
+    eips::{BlockId, BlockNumberOrTag},
+    primitives::B256,
+    providers::{Provider, ProviderBuilder},
+};
+
+/// A block resolved to its number and hash.
+#[derive(Debug, Clone, Copy)]
+pub struct PinnedBlock {
+    pub number: u64,
+    pub hash: B256,
+}
+
+impl PinnedBlock {
+    /// Identifies the block by hash, so that reads fail rather than silently move
+    /// to another block after a reorg
+    pub fn id(&self) -> BlockId {
+        BlockId::from(self.hash)
+    }
+}
+
+/// Parses a block number (decimal or `0x`-prefixed hex), a 32-byte block hash,
+/// or one of the tags `latest`, `safe`, `finalized`, `earliest` and `pending`.
+pub fn parse_block_id(value: &str) -> Result<BlockId, String> {
+    let tag = match value.to_lowercase().as_str() {
+        "latest" => Some(BlockNumberOrTag::Latest),
+        "safe" => Some(BlockNumberOrTag::Safe),
+        "finalized" => Some(BlockNumberOrTag::Finalized),
+        "earliest" => Some(BlockNumberOrTag::Earliest),
+        "pending" => Some(BlockNumberOrTag::Pending),
+        _ => None,
+    };
+    if let Some(tag) = tag {
+        return Ok(BlockId::from(tag));
+    }
+
+    let number = if value.starts_with("0x") {
+        let digits = remove_prefix_if_found(value);
+        if digits.len() == 64 {
+            let hash = value
+                .parse::<B256>()
+                .map_err(|e| format!("Invalid block hash {}: {}", value, e))?;
+            return Ok(BlockId::from(hash));
+        }
+        u64::from_str_radix(digits, 16)
+    } else {
+        value.parse::<u64>()
+    };
+
+    number
+        .map(BlockId::from)
+        .map_err(|_| format!("Expected a block number, hash or tag, got \"{}\"", value))
+}
+
+/// Looks up `block` on the RPC endpoint and returns its number and hash.
+pub async fn resolve_block(config: &ChainConfig, block: BlockId) -> Result<PinnedBlock> {
+    let rpc_url = config.rpc_url.parse()?;
+    let provider = ProviderBuilder::new().on_http(rpc_url);
+
+    let response: serde_json::Value = match block {
+        BlockId::Hash(hash) => {
+            provider
+                .raw_request("eth_getBlockByHash".into(), (hash.block_hash, false))
+                .await?
+        }
+        BlockId::Number(number) => {
+            provider
+                .raw_request("eth_getBlockByNumber".into(), (number, false))
+                .await?
+        }
+    };
+    if response.is_null() {
+        return Err(anyhow::Error::msg(format!(
+            "Block {:?} was not found on {}",
+            block, config.rpc_url
+        )));
+    }
+
+    let field = |name: &str| {
+        response[name].as_str().ok_or_else(|| {
+            anyhow::Error::msg(format!("The block returned by the RPC has no {}", name))
+        })
+    };
+    let number = u64::from_str_radix(remove_prefix_if_found(field("number")?), 16)?;
+    let hash = field("hash")?.parse::<B256>()?;
+
+    Ok(PinnedBlock { number, hash })
+}
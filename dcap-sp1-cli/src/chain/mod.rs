@@ -1,4 +1,5 @@
 pub mod attestation;
+pub mod block;
 pub mod pccs;
 
 use alloy::{
@@ -10,6 +11,8 @@ use alloy::{
 };
 use anyhow::Result;
 
+use crate::config::ChainConfig;
+
 pub struct TxSender {
     rpc_url: String,
     wallet: EthereumWallet,
@@ -83,3 +86,19 @@ pub fn get_evm_address_from_key(key: &str) -> String {
     let address = secret_key_to_address(&signing_key);
     address.to_checksum(None)
 }
+
+/// Fails if the RPC endpoint serves a different chain than the one the profile expects.
+pub async fn check_chain_id(config: &ChainConfig) -> Result<()> {
+    if let Some(expected) = config.chain_id {
+        let rpc_url = config.rpc_url.parse()?;
+        let provider = ProviderBuilder::new().on_http(rpc_url);
+        let chain_id = provider.get_chain_id().await?;
+        if chain_id != expected {
+            return Err(anyhow::Error::msg(format!(
+                "RPC endpoint {} serves chain ID {}, expected {}",
+                config.rpc_url, chain_id, expected
+            )));
+        }
+    }
+    Ok(())
+}
@@ -1,12 +1,12 @@
 use anyhow::Result;
 
-use crate::constants::{DEFAULT_RPC_URL, ENCLAVE_ID_DAO_ADDRESS};
-use crate::remove_prefix_if_found;
+use super::PccsClient;
+use crate::signed::{EnclaveIdentity, Signed};
 
 use alloy::{
-    primitives::{Address, U256},
-    providers::ProviderBuilder,
+    primitives::{Bytes, U256},
     sol,
+    sol_types::SolCall,
 };
 
 sol! {
@@ -20,58 +20,132 @@ sol! {
 
         #[derive(Debug)]
         function getEnclaveIdentity(uint256 id, uint256 version) returns (EnclaveIdentityJsonObj memory enclaveIdObj);
+
+        #[derive(Debug)]
+        function upsertEnclaveIdentity(uint256 id, uint256 version, EnclaveIdentityJsonObj calldata enclaveIdentityObj) returns (bytes32 attestationId);
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum EnclaveIdType {
     QE,
     QVE,
     TDQE,
 }
 
-pub async fn get_enclave_identity(id: EnclaveIdType, version: u32) -> Result<Vec<u8>> {
-    let rpc_url = DEFAULT_RPC_URL.parse().expect("Failed to parse RPC URL");
-    let provider = ProviderBuilder::new().on_http(rpc_url);
-
-    let enclave_id_dao_address_slice =
-        hex::decode(ENCLAVE_ID_DAO_ADDRESS).expect("Invalid address hex");
-
-    let enclave_id_dao_contract = IEnclaveIdentityDao::new(
-        Address::from_slice(&enclave_id_dao_address_slice),
-        &provider,
-    );
+pub fn enclave_id_type_uint256(id: EnclaveIdType) -> U256 {
+    match id {
+        EnclaveIdType::QE => U256::from(0),
+        EnclaveIdType::QVE => U256::from(1),
+        EnclaveIdType::TDQE => U256::from(2),
+    }
+}
 
-    let enclave_id_type_uint256;
+/// The enclave an identity is for, from its `id` (QE, QVE or TD_QE)
+pub fn enclave_id_type_from_id(id: &str) -> Result<EnclaveIdType> {
     match id {
-        EnclaveIdType::QE => enclave_id_type_uint256 = U256::from(0),
-        EnclaveIdType::QVE => enclave_id_type_uint256 = U256::from(1),
-        EnclaveIdType::TDQE => enclave_id_type_uint256 = U256::from(2),
+        "QE" => Ok(EnclaveIdType::QE),
+        "QVE" => Ok(EnclaveIdType::QVE),
+        "TD_QE" => Ok(EnclaveIdType::TDQE),
+        _ => Err(anyhow::Error::msg(format!(
+            "Unknown enclave identity ID {}",
+            id
+        ))),
     }
+}
+
+pub async fn get_enclave_identity(
+    client: &PccsClient,
+    id: EnclaveIdType,
+    version: u32,
+) -> Result<Vec<u8>> {
+    let enclave_id_dao_contract = IEnclaveIdentityDao::new(client.enclave_id_dao, &client.provider);
 
-    let call_builder =
-        enclave_id_dao_contract.getEnclaveIdentity(enclave_id_type_uint256, U256::from(version));
+    let call_builder = enclave_id_dao_contract
+        .getEnclaveIdentity(enclave_id_type_uint256(id), U256::from(version));
 
-    let call_return = call_builder.call().await?;
+    let call_return = call_builder.block(client.block).call().await?;
+    enclave_identity_to_json(call_return.enclaveIdObj, id, version)
+}
 
-    let identity_str = call_return.enclaveIdObj.identityStr;
-    let signature_bytes = call_return.enclaveIdObj.signature;
+/// Assembles the identity JSON in Intel's signed format from what the DAO returned,
+/// rejecting a body that does not parse as an enclave identity or would not verify in the guest
+pub fn enclave_identity_to_json(
+    enclave_id_obj: IEnclaveIdentityDao::EnclaveIdentityJsonObj,
+    id: EnclaveIdType,
+    version: u32,
+) -> Result<Vec<u8>> {
+    let identity_str = enclave_id_obj.identityStr;
+    let signature_bytes = enclave_id_obj.signature;
 
     if identity_str.len() == 0 || signature_bytes.len() == 0 {
         return Err(anyhow::Error::msg(format!(
-            "QEIdentity for ID: {:?}; Version: {} is missing and must be upserted to on-chain pccs",
+            "QEIdentity for ID: {:?}; Version: {} is missing and must be upserted to on-chain pccs, see `pccs upsert`",
             id, version
         )));
     }
 
-    let signature = signature_bytes.to_string();
+    let identity =
+        Signed::<EnclaveIdentity>::new(&identity_str, &signature_bytes).map_err(|e| {
+            anyhow::Error::msg(format!(
+                "QEIdentity for ID: {:?}; Version: {} is malformed on-chain: {}",
+                id, version, e
+            ))
+        })?;
+    Ok(identity.to_json())
+}
 
-    let ret_str = format!(
-        "{{\"enclaveIdentity\": {}, \"signature\": \"{}\"}}",
-        identity_str,
-        remove_prefix_if_found(signature.as_str())
-    );
+/// Encodes the EnclaveIdentityDao call upserting `identity` for the given PCS API version,
+/// with its body as signed
+pub fn upsert_enclave_identity_calldata(
+    identity: &Signed<EnclaveIdentity>,
+    version: u32,
+) -> Result<Vec<u8>> {
+    let id = enclave_id_type_from_id(&identity.body.id)?;
+    Ok(IEnclaveIdentityDao::upsertEnclaveIdentityCall {
+        id: enclave_id_type_uint256(id),
+        version: U256::from(version),
+        enclaveIdentityObj: IEnclaveIdentityDao::EnclaveIdentityJsonObj {
+            identityStr: identity.raw_body.get().to_string(),
+            signature: Bytes::from(identity.signature.clone()),
+        },
+    }
+    .abi_encode())
+}
 
-    let ret = ret_str.into_bytes();
-    Ok(ret)
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::keccak256;
+
+    use super::*;
+
+    const TD_QE_IDENTITY_V2: &str =
+        include_str!("../../../../data/collaterals/td_qe_identity_v2.json");
+
+    #[test]
+    fn encodes_upsert_enclave_identity_calldata() {
+        let identity = Signed::<EnclaveIdentity>::from_json(TD_QE_IDENTITY_V2.as_bytes()).unwrap();
+        let calldata = upsert_enclave_identity_calldata(&identity, 4).unwrap();
+
+        // upsertEnclaveIdentity(uint256 id, uint256 version, EnclaveIdentityJsonObj), the struct
+        // being (string identityStr, bytes signature)
+        assert_eq!(
+            calldata[..4],
+            keccak256("upsertEnclaveIdentity(uint256,uint256,(string,bytes))")[..4]
+        );
+        let call =
+            IEnclaveIdentityDao::upsertEnclaveIdentityCall::abi_decode(&calldata, true).unwrap();
+        assert_eq!(call.id, U256::from(2));
+        assert_eq!(call.version, U256::from(4));
+        assert_eq!(call.enclaveIdentityObj.identityStr, identity.raw_body.get());
+        assert_eq!(
+            call.enclaveIdentityObj.signature.to_vec(),
+            identity.signature
+        );
+
+        // What the DAO stores reads back as the collateral that was upserted
+        let json =
+            enclave_identity_to_json(call.enclaveIdentityObj, EnclaveIdType::TDQE, 4).unwrap();
+        assert_eq!(json, TD_QE_IDENTITY_V2.as_bytes());
+    }
 }
@@ -1,12 +1,12 @@
 use anyhow::Result;
 
-use crate::constants::{DEFAULT_RPC_URL, FMSPC_TCB_DAO_ADDRESS};
-use crate::remove_prefix_if_found;
+use super::PccsClient;
+use crate::signed::{Signed, TcbInfo};
 
 use alloy::{
-    primitives::{Address, U256},
-    providers::ProviderBuilder,
+    primitives::{Bytes, U256},
     sol,
+    sol_types::SolCall,
 };
 
 sol! {
@@ -20,17 +20,19 @@ sol! {
 
         #[derive(Debug)]
         function getTcbInfo(uint256 tcbType, string calldata fmspc, uint256 version) returns (TcbInfoJsonObj memory tcbObj);
+
+        #[derive(Debug)]
+        function upsertFmspcTcb(TcbInfoJsonObj calldata tcbInfoObj) returns (bytes32 attestationId);
     }
 }
 
-pub async fn get_tcb_info(tcb_type: u8, fmspc: &str, version: u32) -> Result<Vec<u8>> {
-    let rpc_url = DEFAULT_RPC_URL.parse().expect("Failed to parse RPC URL");
-    let provider = ProviderBuilder::new().on_http(rpc_url);
-
-    let fmspc_tcb_dao_address_slice =
-        hex::decode(FMSPC_TCB_DAO_ADDRESS).expect("Invalid address hex");
-    let fmspc_tcb_dao_contract =
-        IFmspcTcbDao::new(Address::from_slice(&fmspc_tcb_dao_address_slice), &provider);
+pub async fn get_tcb_info(
+    client: &PccsClient,
+    tcb_type: u8,
+    fmspc: &str,
+    version: u32,
+) -> Result<Vec<u8>> {
+    let fmspc_tcb_dao_contract = IFmspcTcbDao::new(client.fmspc_tcb_dao, &client.provider);
 
     let call_builder = fmspc_tcb_dao_contract.getTcbInfo(
         U256::from(tcb_type),
@@ -38,25 +40,71 @@ pub async fn get_tcb_info(tcb_type: u8, fmspc: &str, version: u32) -> Result<Vec
         U256::from(version),
     );
 
-    let call_return = call_builder.call().await?;
-    let tcb_info_str = call_return.tcbObj.tcbInfoStr;
-    let signature_bytes = call_return.tcbObj.signature;
+    let call_return = call_builder.block(client.block).call().await?;
+    tcb_info_to_json(call_return.tcbObj, fmspc, version)
+}
+
+/// Assembles the TCBInfo JSON in Intel's signed format from what the DAO returned,
+/// rejecting a body that does not parse as a TCBInfo or would not verify in the guest
+pub fn tcb_info_to_json(
+    tcb_obj: IFmspcTcbDao::TcbInfoJsonObj,
+    fmspc: &str,
+    version: u32,
+) -> Result<Vec<u8>> {
+    let tcb_info_str = tcb_obj.tcbInfoStr;
+    let signature_bytes = tcb_obj.signature;
 
     if tcb_info_str.len() == 0 || signature_bytes.len() == 0 {
         return Err(anyhow::Error::msg(format!(
-            "TCBInfo for FMSPC: {}; Version: {} is missing and must be upserted to on-chain pccs",
+            "TCBInfo for FMSPC: {}; Version: {} is missing and must be upserted to on-chain pccs, see `pccs upsert`",
             fmspc, version
         )));
     }
 
-    let signature = signature_bytes.to_string();
+    let tcb_info = Signed::<TcbInfo>::new(&tcb_info_str, &signature_bytes).map_err(|e| {
+        anyhow::Error::msg(format!(
+            "TCBInfo for FMSPC: {}; Version: {} is malformed on-chain: {}",
+            fmspc, version, e
+        ))
+    })?;
+    Ok(tcb_info.to_json())
+}
 
-    let ret_str = format!(
-        "{{\"tcbInfo\": {}, \"signature\": \"{}\"}}",
-        tcb_info_str,
-        remove_prefix_if_found(signature.as_str())
-    );
+/// Encodes the FmspcTcbDao call upserting `tcb_info`, with its body as signed
+pub fn upsert_tcb_info_calldata(tcb_info: &Signed<TcbInfo>) -> Vec<u8> {
+    IFmspcTcbDao::upsertFmspcTcbCall {
+        tcbInfoObj: IFmspcTcbDao::TcbInfoJsonObj {
+            tcbInfoStr: tcb_info.raw_body.get().to_string(),
+            signature: Bytes::from(tcb_info.signature.clone()),
+        },
+    }
+    .abi_encode()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::keccak256;
+
+    use super::*;
+
+    const TCB_INFO_V3_TDX: &str = include_str!("../../../../data/collaterals/tcb_info_v3_tdx.json");
 
-    let ret = ret_str.into_bytes();
-    Ok(ret)
+    #[test]
+    fn encodes_upsert_fmspc_tcb_calldata() {
+        let tcb_info = Signed::<TcbInfo>::from_json(TCB_INFO_V3_TDX.as_bytes()).unwrap();
+        let calldata = upsert_tcb_info_calldata(&tcb_info);
+
+        // upsertFmspcTcb(TcbInfoJsonObj), the struct being (string tcbInfoStr, bytes signature)
+        assert_eq!(
+            calldata[..4],
+            keccak256("upsertFmspcTcb((string,bytes))")[..4]
+        );
+        let call = IFmspcTcbDao::upsertFmspcTcbCall::abi_decode(&calldata, true).unwrap();
+        assert_eq!(call.tcbInfoObj.tcbInfoStr, tcb_info.raw_body.get());
+        assert_eq!(call.tcbInfoObj.signature.to_vec(), tcb_info.signature);
+
+        // What the DAO stores reads back as the collateral that was upserted
+        let json = tcb_info_to_json(call.tcbInfoObj, "90C06F000000", 3).unwrap();
+        assert_eq!(json, TCB_INFO_V3_TDX.as_bytes());
+    }
 }
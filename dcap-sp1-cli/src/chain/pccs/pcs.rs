@@ -1,8 +1,9 @@
 use anyhow::Result;
 
-use crate::constants::{DEFAULT_RPC_URL, PCS_DAO_ADDRESS};
+use super::PccsClient;
 
-use alloy::{primitives::Address, providers::ProviderBuilder, sol};
+use alloy::{primitives::Bytes, sol, sol_types::SolCall};
+use x509_parser::prelude::*;
 
 sol! {
     #[sol(rpc)]
@@ -17,22 +18,119 @@ sol! {
 
         #[derive(Debug)]
         function getCertificateById(CA ca) external view returns (bytes memory cert, bytes memory crl);
+
+        #[derive(Debug)]
+        function upsertPcsCertificates(CA ca, bytes calldata cert) external returns (bytes32 attestationId);
+
+        #[derive(Debug)]
+        function upsertRootCACrl(bytes calldata rootcacrl) external returns (bytes32 attestationId);
+
+        #[derive(Debug)]
+        function upsertPckCrl(CA ca, bytes calldata crl) external returns (bytes32 attestationId);
     }
 }
 
-pub async fn get_certificate_by_id(ca_id: IPCSDao::CA) -> Result<(Vec<u8>, Vec<u8>)> {
-    let rpc_url = DEFAULT_RPC_URL.parse().expect("Failed to parse RPC URL");
-    let provider = ProviderBuilder::new().on_http(rpc_url);
-
-    let pcs_dao_address_slice = hex::decode(PCS_DAO_ADDRESS).expect("invalid address hex");
-    let pcs_dao_contract = IPCSDao::new(Address::from_slice(&pcs_dao_address_slice), &provider);
+pub async fn get_certificate_by_id(
+    client: &PccsClient,
+    ca_id: IPCSDao::CA,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    let pcs_dao_contract = IPCSDao::new(client.pcs_dao, &client.provider);
 
     let call_builder = pcs_dao_contract.getCertificateById(ca_id);
 
-    let call_return = call_builder.call().await?;
+    let call_return = call_builder.block(client.block).call().await?;
 
     let cert = call_return.cert.to_vec();
     let crl = call_return.crl.to_vec();
 
     Ok((cert, crl))
 }
+
+/// The PCK CA that issued a DER-encoded PCK CRL, from the CRL issuer
+pub fn pck_crl_ca(crl: &[u8]) -> Result<IPCSDao::CA> {
+    let (_, crl) = parse_x509_crl(crl).map_err(|e| anyhow::Error::msg(e.to_string()))?;
+    let issuer = crl
+        .issuer()
+        .iter_common_name()
+        .next()
+        .and_then(|cn| cn.as_str().ok())
+        .ok_or_else(|| anyhow::Error::msg("The PCK CRL has no issuer common name"))?;
+    match issuer {
+        "Intel SGX PCK Platform CA" => Ok(IPCSDao::CA::PLATFORM),
+        "Intel SGX PCK Processor CA" => Ok(IPCSDao::CA::PROCESSOR),
+        _ => Err(anyhow::Error::msg(format!(
+            "Unknown PCK CRL issuer \"{}\"",
+            issuer
+        ))),
+    }
+}
+
+/// Encodes the PcsDao call upserting the DER certificate of `ca`
+pub fn upsert_certificate_calldata(ca: IPCSDao::CA, cert: &[u8]) -> Vec<u8> {
+    IPCSDao::upsertPcsCertificatesCall {
+        ca,
+        cert: Bytes::from(cert.to_vec()),
+    }
+    .abi_encode()
+}
+
+/// Encodes the PcsDao call upserting the DER Root CA CRL
+pub fn upsert_root_ca_crl_calldata(crl: &[u8]) -> Vec<u8> {
+    IPCSDao::upsertRootCACrlCall {
+        rootcacrl: Bytes::from(crl.to_vec()),
+    }
+    .abi_encode()
+}
+
+/// Encodes the PcsDao call upserting the DER CRL of the PCK CA `ca`
+pub fn upsert_pck_crl_calldata(ca: IPCSDao::CA, crl: &[u8]) -> Vec<u8> {
+    IPCSDao::upsertPckCrlCall {
+        ca,
+        crl: Bytes::from(crl.to_vec()),
+    }
+    .abi_encode()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::keccak256;
+
+    use super::*;
+
+    fn selector(signature: &str) -> [u8; 4] {
+        keccak256(signature)[..4].try_into().unwrap()
+    }
+
+    #[test]
+    fn encodes_upsert_pcs_certificates_calldata() {
+        let calldata = upsert_certificate_calldata(IPCSDao::CA::SIGNING, b"certificate");
+
+        // The CA enum is encoded as uint8
+        assert_eq!(
+            calldata[..4],
+            selector("upsertPcsCertificates(uint8,bytes)")
+        );
+        let call = IPCSDao::upsertPcsCertificatesCall::abi_decode(&calldata, true).unwrap();
+        assert!(matches!(call.ca, IPCSDao::CA::SIGNING));
+        assert_eq!(call.cert.to_vec(), b"certificate");
+    }
+
+    #[test]
+    fn encodes_upsert_root_ca_crl_calldata() {
+        let calldata = upsert_root_ca_crl_calldata(b"crl");
+
+        assert_eq!(calldata[..4], selector("upsertRootCACrl(bytes)"));
+        let call = IPCSDao::upsertRootCACrlCall::abi_decode(&calldata, true).unwrap();
+        assert_eq!(call.rootcacrl.to_vec(), b"crl");
+    }
+
+    #[test]
+    fn encodes_upsert_pck_crl_calldata() {
+        let calldata = upsert_pck_crl_calldata(IPCSDao::CA::PLATFORM, b"crl");
+
+        assert_eq!(calldata[..4], selector("upsertPckCrl(uint8,bytes)"));
+        let call = IPCSDao::upsertPckCrlCall::abi_decode(&calldata, true).unwrap();
+        assert!(matches!(call.ca, IPCSDao::CA::PLATFORM));
+        assert_eq!(call.crl.to_vec(), b"crl");
+    }
+}
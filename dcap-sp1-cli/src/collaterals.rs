@@ -0,0 +1,85 @@
+use anyhow::Result;
+
+use crate::pem_chain_to_der;
+
+#[derive(Debug)]
+pub struct Collaterals {
+    pub tcb_info: Vec<u8>,
+    pub qe_identity: Vec<u8>,
+    pub root_ca: Vec<u8>,
+    pub tcb_signing_ca: Vec<u8>,
+    pub root_ca_crl: Vec<u8>,
+    pub pck_crl: Vec<u8>,
+    /// PEM PCK certificate chain as read from the PCK DAO, only set when the quote does not embed it
+    pub pck_certchain: Vec<u8>,
+}
+
+impl Collaterals {
+    pub fn new(
+        tcb_info: Vec<u8>,
+        qe_identity: Vec<u8>,
+        root_ca: Vec<u8>,
+        tcb_signing_ca: Vec<u8>,
+        root_ca_crl: Vec<u8>,
+        pck_crl: Vec<u8>,
+    ) -> Self {
+        Collaterals {
+            tcb_info,
+            qe_identity,
+            root_ca,
+            tcb_signing_ca,
+            root_ca_crl,
+            pck_crl,
+            pck_certchain: Vec::new(),
+        }
+    }
+
+    /// Returns the PCK certificate chain as concatenated DER certificates, leaf first,
+    /// or nothing when the quote embeds the chain
+    pub fn pck_certchain_der(&self) -> Result<Vec<u8>> {
+        Ok(pem_chain_to_der(&self.pck_certchain)?.concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use x509_parser::prelude::*;
+
+    use super::*;
+    use crate::parser::{get_pck_certification_data, PckCertificationData};
+    use crate::quote::Quote;
+
+    const QUOTE_HEX: &str = include_str!("../../data/quote.hex");
+
+    #[test]
+    fn converts_the_pck_dao_chain_to_der() {
+        let quote = hex::decode(QUOTE_HEX.trim()).unwrap();
+        let pem_chain = match get_pck_certification_data(&Quote::from_bytes(&quote).unwrap()) {
+            Ok(PckCertificationData::CertChain(chain)) => chain,
+            _ => panic!("The sample quote embeds its PCK chain"),
+        };
+        let mut collaterals = Collaterals::new(
+            b"tcb_info".to_vec(),
+            b"qe_identity".to_vec(),
+            b"root_ca".to_vec(),
+            b"tcb_signing_ca".to_vec(),
+            b"root_ca_crl".to_vec(),
+            b"pck_crl".to_vec(),
+        );
+        assert!(collaterals.pck_certchain_der().unwrap().is_empty());
+
+        // The PCK DAO returns the chain in PEM, the guest parses concatenated DER certificates
+        collaterals.pck_certchain = pem_chain;
+        let der_chain = collaterals.pck_certchain_der().unwrap();
+        let mut rest = der_chain.as_slice();
+        let mut subjects = Vec::new();
+        while !rest.is_empty() {
+            let (remaining, cert) = X509Certificate::from_der(rest).unwrap();
+            subjects.push(cert.subject().to_string());
+            rest = remaining;
+        }
+        assert_eq!(subjects.len(), 3);
+        assert!(subjects[0].contains("Intel SGX PCK Certificate"));
+        assert!(subjects[2].contains("Intel SGX Root CA"));
+    }
+}
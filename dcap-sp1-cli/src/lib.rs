@@ -1,7 +1,67 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use x509_parser::pem::Pem;
+
+pub mod bundle;
 pub mod chain;
+pub mod collaterals;
+pub mod config;
 pub mod constants;
 pub mod parser;
+pub mod provider;
+pub mod quote;
+pub mod signed;
+pub mod verify;
 
 pub fn remove_prefix_if_found(h: &str) -> &str {
     h.trim_start_matches("0x")
 }
+
+/// Normalizes a certificate or CRL given as raw DER, PEM, or a hex string of DER into DER.
+pub fn to_der(data: &[u8]) -> Result<Vec<u8>> {
+    let text = std::str::from_utf8(data).map(|s| s.trim());
+    match text {
+        Ok(s) if s.starts_with("-----BEGIN") => {
+            let pem = Pem::iter_from_buffer(s.as_bytes())
+                .next()
+                .ok_or_else(|| anyhow::Error::msg("Empty PEM input"))??;
+            Ok(pem.contents)
+        }
+        Ok(s)
+            if !s.is_empty()
+                && remove_prefix_if_found(s)
+                    .bytes()
+                    .all(|b| b.is_ascii_hexdigit()) =>
+        {
+            Ok(hex::decode(remove_prefix_if_found(s))?)
+        }
+        _ => Ok(data.to_vec()),
+    }
+}
+
+/// Splits a PEM certificate chain into its DER-encoded certificates, leaf first.
+pub fn pem_chain_to_der(data: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let chain = Pem::iter_from_buffer(data)
+        .map(|pem| pem.map(|pem| pem.contents))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(chain)
+}
+
+/// Encodes DER as a PEM block with the given label, e.g. `CERTIFICATE`.
+pub fn der_to_pem(der: &[u8], label: &str) -> String {
+    let body = STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in body.as_bytes().chunks(64) {
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+/// Formats seconds since epoch as an RFC 3339 UTC timestamp.
+pub fn format_timestamp(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_else(|| timestamp.to_string())
+}
@@ -1,22 +1,59 @@
 use std::fs::read_to_string;
 use std::path::PathBuf;
 
+use dcap_sp1_cli::bundle::CollateralBundle;
 use dcap_sp1_cli::chain::attestation::{
     decode_attestation_ret_data, generate_attestation_calldata,
 };
+use dcap_sp1_cli::chain::block::{parse_block_id, resolve_block, PinnedBlock};
 use dcap_sp1_cli::chain::pccs::{
-    enclave_id::{get_enclave_identity, EnclaveIdType},
-    fmspc_tcb::get_tcb_info,
-    pcs::{get_certificate_by_id, IPCSDao::CA},
+    decode_attestation_id,
+    enclave_id::upsert_enclave_identity_calldata,
+    fmspc_tcb::upsert_tcb_info_calldata,
+    pck::{get_pck_cert, get_pck_cert_chain},
+    pcs::{
+        pck_crl_ca, upsert_certificate_calldata, upsert_pck_crl_calldata,
+        upsert_root_ca_crl_calldata, IPCSDao::CA,
+    },
+    PccsClient,
 };
-use dcap_sp1_cli::chain::TxSender;
+use dcap_sp1_cli::chain::{check_chain_id, TxSender};
+use dcap_sp1_cli::collaterals::Collaterals;
+use dcap_sp1_cli::config::{resolve_chain_config, ChainConfig, NetworkProfile};
 use dcap_sp1_cli::constants::*;
-use dcap_sp1_cli::parser::get_pck_fmspc_and_issuer;
-use dcap_sp1_cli::remove_prefix_if_found;
+use dcap_sp1_cli::parser::{
+    get_pck_certification_data, get_pck_extensions, get_pck_fmspc_and_issuer, get_pck_issuer,
+    PckCertificationData,
+};
+use dcap_sp1_cli::provider::{
+    ca_name,
+    cache::{now, CachedProvider, CollateralCache},
+    dir::DirProvider,
+    fallback::FallbackProvider,
+    fetch_collaterals,
+    http::PcsProvider,
+    onchain::OnChainProvider,
+    overrides::{CollateralFiles, OverrideProvider},
+    CollateralProvider, CollateralRequest,
+};
+use dcap_sp1_cli::quote::Quote;
+use dcap_sp1_cli::signed::{EnclaveIdentity, Signed, TcbInfo};
+use dcap_sp1_cli::verify::{
+    freshness::check_freshness,
+    native::{check_supported_quote, verify_quote},
+    pck::verify_pck_chain,
+    qe::evaluate_qe_identity,
+    report::TcbReport,
+    root::check_root_ca,
+    signature::verify_collateral_signatures,
+    tcb::evaluate_tcb,
+    tdx::evaluate_tdx_module,
+};
+use dcap_sp1_cli::{format_timestamp, pem_chain_to_der, remove_prefix_if_found, to_der};
 
-use anyhow::Result;
+use alloy::eips::{BlockId, BlockNumberOrTag};
+use anyhow::{Context, Result};
 use clap::{Args, Parser, Subcommand, ValueEnum};
-use dcap_rs::constants::{SGX_TEE_TYPE, TDX_TEE_TYPE};
 use dcap_rs::types::{collaterals::IntelCollateral, VerifiedOutput};
 use sp1_sdk::{utils, HashableKey, ProverClient, SP1Stdin};
 
@@ -36,8 +73,29 @@ enum Commands {
     /// Fetches proof from SP1 and sends them on-chain to verify DCAP quote
     Prove(DcapArgs),
 
+    /// Verifies a DCAP quote natively with dcap-rs, without generating a proof
+    Verify(VerifyArgs),
+
+    /// Decodes a quote and prints its header, report body and signature data
+    Inspect(InspectArgs),
+
     /// De-serializes and prints information about the Output
     Deserialize(OutputArgs),
+
+    /// Lists or clears the on-disk collateral cache
+    Cache(CacheArgs),
+
+    /// Decodes PCK certificates
+    Pck(PckArgs),
+
+    /// Evaluates the TCB level of a quote's platform and QE against its TCBInfo and QE identity
+    Tcb(TcbArgs),
+
+    /// Fetches, exports and inspects the collaterals used for a quote
+    Collaterals(CollateralsArgs),
+
+    /// Publishes collaterals missing from the on-chain PCCS
+    Pccs(PccsArgs),
 }
 
 /// Enum representing the available proof systems
@@ -64,111 +122,512 @@ struct DcapArgs {
         default_value = "groth16"
     )]
     proof_system: Option<ProofSystem>,
+
+    /// Proves even if a collateral has expired, warning instead of failing
+    #[arg(long = "allow-stale")]
+    allow_stale: bool,
+
+    #[command(flatten)]
+    chain: ChainArgs,
+
+    #[command(flatten)]
+    collaterals: CollateralArgs,
 }
 
 #[derive(Args)]
-struct OutputArgs {
-    #[arg(short = 'o', long = "output")]
-    output: String,
+struct VerifyArgs {
+    /// The input quote provided as a hex string, this overwrites the --quote-path argument
+    #[arg(short = 'q', long = "quote-hex")]
+    quote_hex: Option<String>,
+
+    /// Optional: The path to a quote.hex file. Default: /data/quote.hex or overwritten by the --quote-hex argument if provided.
+    #[arg(short = 'p', long = "quote-path")]
+    quote_path: Option<PathBuf>,
+
+    /// Optional: Verification time in seconds since epoch. Default: now
+    #[arg(long = "timestamp")]
+    timestamp: Option<u64>,
+
+    #[command(flatten)]
+    chain: ChainArgs,
+
+    #[command(flatten)]
+    collaterals: CollateralArgs,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    utils::setup_logger();
+#[derive(Args)]
+struct TcbArgs {
+    /// The input quote provided as a hex string, this overwrites the --quote-path argument
+    #[arg(short = 'q', long = "quote-hex")]
+    quote_hex: Option<String>,
 
-    let cli = Cli::parse();
+    /// Optional: The path to a quote.hex file. Default: /data/quote.hex or overwritten by the --quote-hex argument if provided.
+    #[arg(short = 'p', long = "quote-path")]
+    quote_path: Option<PathBuf>,
 
-    match &cli.command {
-        Commands::Prove(args) => {
-            // Step 0: Read quote
-            println!("Begin reading quote and fetching the necessary collaterals...");
-            let quote = get_quote(&args.quote_path, &args.quote_hex).expect("Failed to read quote");
+    /// Prints the TCB evaluation as JSON
+    #[arg(long = "json")]
+    json: bool,
 
-            // Step 1: Determine quote version and TEE type
-            let quote_version = u16::from_le_bytes([quote[0], quote[1]]);
-            let tee_type = u32::from_le_bytes([quote[4], quote[5], quote[6], quote[7]]);
+    #[command(flatten)]
+    chain: ChainArgs,
 
-            println!("Quote version: {}", quote_version);
-            println!("TEE Type: {}", tee_type);
+    #[command(flatten)]
+    collaterals: CollateralArgs,
+}
 
-            if quote_version < 3 || quote_version > 4 {
-                panic!("Unsupported quote version");
-            }
+#[derive(Args)]
+struct InspectArgs {
+    /// The input quote provided as a hex string, this overwrites the --quote-path argument
+    #[arg(short = 'q', long = "quote-hex")]
+    quote_hex: Option<String>,
 
-            if tee_type != SGX_TEE_TYPE && tee_type != TDX_TEE_TYPE {
-                panic!("Unsupported tee type");
-            }
+    /// Optional: The path to a quote.hex file. Default: /data/quote.hex or overwritten by the --quote-hex argument if provided.
+    #[arg(short = 'p', long = "quote-path")]
+    quote_path: Option<PathBuf>,
 
-            // Step 2: Load collaterals
-            println!("Quote read successfully. Begin fetching collaterals from the on-chain PCCS");
+    /// Prints the decoded quote as JSON
+    #[arg(long = "json")]
+    json: bool,
+}
 
-            let (root_ca, root_ca_crl) = get_certificate_by_id(CA::ROOT).await?;
-            if root_ca.is_empty() || root_ca_crl.is_empty() {
-                panic!("Intel SGX Root CA is missing");
-            } else {
-                println!("Fetched Intel SGX RootCA and CRL");
-            }
+/// Enum representing the available collateral sources
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+enum CollateralSource {
+    /// The on-chain PCCS DAOs of the selected network
+    Onchain,
+    /// The Intel PCS, or a PCCS instance, at --pcs-url
+    Pcs,
+    /// Collateral files in --collaterals-dir
+    Dir,
+}
 
-            let (fmspc, pck_type, pck_issuer) =
-                get_pck_fmspc_and_issuer(&quote, quote_version, tee_type);
+#[derive(Args, Clone)]
+struct CollateralArgs {
+    /// Collateral sources to query, in order of preference. Default: dir if --collaterals-dir is provided, onchain otherwise
+    #[arg(long = "collateral-source", value_enum, value_delimiter = ',')]
+    sources: Vec<CollateralSource>,
 
-            let tcb_type: u8;
-            if tee_type == TDX_TEE_TYPE {
-                tcb_type = 1;
-            } else {
-                tcb_type = 0;
-            }
-            let tcb_version: u32;
-            if quote_version < 4 {
-                tcb_version = 2
-            } else {
-                tcb_version = 3
-            }
-            let tcb_info = get_tcb_info(tcb_type, fmspc.as_str(), tcb_version).await?;
+    /// Base URL of the Intel PCS or of a PCCS instance, used by the `pcs` source
+    #[arg(long = "pcs-url", env = "PCS_URL", default_value = INTEL_PCS_URL)]
+    pcs_url: String,
 
-            println!("Fetched TCBInfo JSON for FMSPC: {}", fmspc);
+    /// Directory holding collateral files. On its own, collaterals are read from this directory only and no RPC call is made
+    #[arg(long = "collaterals-dir")]
+    collaterals_dir: Option<PathBuf>,
 
-            let qe_id_type: EnclaveIdType;
-            if tee_type == TDX_TEE_TYPE {
-                qe_id_type = EnclaveIdType::TDQE
-            } else {
-                qe_id_type = EnclaveIdType::QE
-            }
-            let qe_identity = get_enclave_identity(qe_id_type, quote_version as u32).await?;
-            println!("Fetched QEIdentity JSON");
+    /// Optional: TCBInfo JSON file in Intel's signed format, replaces the fetched TCBInfo
+    #[arg(long = "tcb-info-file")]
+    tcb_info_file: Option<PathBuf>,
 
-            let (signing_ca, _) = get_certificate_by_id(CA::SIGNING).await?;
-            if signing_ca.is_empty() {
-                panic!("Intel TCB Signing CA is missing");
-            } else {
-                println!("Fetched Intel TCB Signing CA");
+    /// Optional: QEIdentity JSON file in Intel's signed format, replaces the fetched QEIdentity
+    #[arg(long = "qe-identity-file")]
+    qe_identity_file: Option<PathBuf>,
+
+    /// Optional: Intel SGX Root CA certificate (DER or PEM), replaces the fetched Root CA
+    #[arg(long = "root-ca-file")]
+    root_ca_file: Option<PathBuf>,
+
+    /// Optional: Intel SGX Root CA CRL (DER or PEM), replaces the fetched Root CA CRL
+    #[arg(long = "root-ca-crl-file")]
+    root_ca_crl_file: Option<PathBuf>,
+
+    /// Optional: Intel TCB Signing CA certificate (DER or PEM), replaces the fetched Signing CA
+    #[arg(long = "signing-ca-file")]
+    signing_ca_file: Option<PathBuf>,
+
+    /// Optional: PCK CRL (DER or PEM), replaces the fetched PCK CRL
+    #[arg(long = "pck-crl-file")]
+    pck_crl_file: Option<PathBuf>,
+
+    /// Optional: Root certificate (DER or PEM) to trust instead of the pinned Intel SGX Root CA, for test PKIs
+    #[arg(long = "trusted-root")]
+    trusted_root: Option<PathBuf>,
+
+    /// Reads on-chain collaterals with individual concurrent calls instead of one Multicall3 call
+    #[arg(long = "no-multicall")]
+    no_multicall: bool,
+
+    /// Optional: Reads on-chain collaterals at a block number, hash or tag (e.g. finalized), bypassing the cache
+    #[arg(long = "block", value_parser = parse_block_id)]
+    block: Option<BlockId>,
+
+    /// Disables the on-disk cache of on-chain collaterals
+    #[arg(long = "no-cache")]
+    no_cache: bool,
+
+    /// Optional: Collateral cache directory. Default: $XDG_CACHE_HOME/dcap-sp1-cli/collaterals
+    #[arg(long = "cache-dir", env = "COLLATERAL_CACHE_DIR")]
+    cache_dir: Option<PathBuf>,
+}
+
+impl CollateralArgs {
+    fn sources(&self) -> Vec<CollateralSource> {
+        if !self.sources.is_empty() {
+            self.sources.clone()
+        } else if self.collaterals_dir.is_some() {
+            vec![CollateralSource::Dir]
+        } else {
+            vec![CollateralSource::Onchain]
+        }
+    }
+
+    /// Whether the collaterals come from local files only
+    fn is_offline(&self) -> bool {
+        self.sources()
+            .iter()
+            .all(|source| *source == CollateralSource::Dir)
+    }
+
+    /// Resolves `--block` to a concrete block when collaterals are read on-chain
+    async fn pinned_block(&self, chain_config: &ChainConfig) -> Result<Option<PinnedBlock>> {
+        match self.block {
+            Some(block) if self.sources().contains(&CollateralSource::Onchain) => {
+                Ok(Some(resolve_block(chain_config, block).await?))
             }
+            _ => Ok(None),
+        }
+    }
 
-            let (_, pck_crl) = get_certificate_by_id(pck_type).await?;
-            if pck_crl.is_empty() {
-                panic!("CRL for {} is missing", pck_issuer);
-            } else {
-                println!("Fetched Intel PCK CRL for {}", pck_issuer);
+    /// Reads the DER certificate given with --trusted-root
+    fn trusted_root(&self) -> Result<Option<Vec<u8>>> {
+        match &self.trusted_root {
+            Some(path) => Ok(Some(to_der(&std::fs::read(path)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn files(&self) -> CollateralFiles {
+        CollateralFiles {
+            tcb_info: self.tcb_info_file.clone(),
+            qe_identity: self.qe_identity_file.clone(),
+            root_ca: self.root_ca_file.clone(),
+            root_ca_crl: self.root_ca_crl_file.clone(),
+            signing_ca: self.signing_ca_file.clone(),
+            pck_crl: self.pck_crl_file.clone(),
+        }
+    }
+
+    fn build_provider(
+        &self,
+        chain_config: &ChainConfig,
+        pinned_block: Option<&PinnedBlock>,
+    ) -> Result<Box<dyn CollateralProvider>> {
+        let mut providers: Vec<Box<dyn CollateralProvider>> = Vec::new();
+        for source in self.sources().iter() {
+            match source {
+                CollateralSource::Onchain => {
+                    let block = pinned_block
+                        .map(PinnedBlock::id)
+                        .unwrap_or(BlockNumberOrTag::Latest.into());
+                    let onchain = Box::new(OnChainProvider::new(
+                        chain_config,
+                        !self.no_multicall,
+                        block,
+                    )?);
+                    // The cache only holds the latest collaterals, of a known chain
+                    match chain_config.chain_id {
+                        Some(chain_id) if !self.no_cache && pinned_block.is_none() => {
+                            let cache = CollateralCache::new(&cache_dir(&self.cache_dir)?);
+                            providers.push(Box::new(CachedProvider::new(
+                                onchain,
+                                cache,
+                                chain_id,
+                                chain_config,
+                            )))
+                        }
+                        _ => providers.push(onchain),
+                    }
+                }
+                CollateralSource::Pcs => providers.push(Box::new(PcsProvider::new(&self.pcs_url))),
+                CollateralSource::Dir => {
+                    let dir = self.collaterals_dir.as_ref().ok_or_else(|| {
+                        anyhow::Error::msg("The dir collateral source requires --collaterals-dir")
+                    })?;
+                    providers.push(Box::new(DirProvider::new(dir)))
+                }
             }
+        }
+
+        let provider: Box<dyn CollateralProvider> = if providers.len() == 1 {
+            providers.remove(0)
+        } else {
+            Box::new(FallbackProvider::new(providers))
+        };
+
+        let files = self.files();
+        if files.is_empty() {
+            Ok(provider)
+        } else {
+            Ok(Box::new(OverrideProvider::new(provider, files)))
+        }
+    }
+}
+
+#[derive(Args)]
+struct ChainArgs {
+    /// Named network profile, built-in or defined in the networks config file
+    #[arg(short = 'n', long = "network", env = "NETWORK", default_value = DEFAULT_NETWORK)]
+    network: String,
+
+    /// Optional: Path to a TOML or YAML networks config file. Default: ./networks.toml if present
+    #[arg(long = "config", env = "NETWORKS_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Overrides the RPC endpoint of the selected network
+    #[arg(long = "rpc-url", env = "RPC_URL")]
+    rpc_url: Option<String>,
+
+    /// Overrides the address of the DCAP attestation contract
+    #[arg(long = "dcap-contract", env = "DCAP_CONTRACT")]
+    dcap_contract: Option<String>,
+
+    /// Overrides the block explorer URL used to print transaction links
+    #[arg(long = "explorer-url", env = "EXPLORER_URL")]
+    explorer_url: Option<String>,
+
+    /// Overrides the address of the PCS DAO
+    #[arg(long = "pcs-dao", env = "PCS_DAO")]
+    pcs_dao: Option<String>,
+
+    /// Overrides the address of the FMSPC TCB DAO
+    #[arg(long = "fmspc-tcb-dao", env = "FMSPC_TCB_DAO")]
+    fmspc_tcb_dao: Option<String>,
+
+    /// Overrides the address of the Enclave Identity DAO
+    #[arg(long = "enclave-id-dao", env = "ENCLAVE_ID_DAO")]
+    enclave_id_dao: Option<String>,
+
+    /// Overrides the address of the PCK DAO
+    #[arg(long = "pck-dao", env = "PCK_DAO")]
+    pck_dao: Option<String>,
+
+    /// Overrides the address of the Multicall3 contract used to batch PCCS reads
+    #[arg(long = "multicall3", env = "MULTICALL3")]
+    multicall3: Option<String>,
+}
+
+impl ChainArgs {
+    fn to_config(&self) -> Result<ChainConfig> {
+        let overrides = NetworkProfile {
+            rpc_url: self.rpc_url.clone(),
+            chain_id: None,
+            dcap_contract: self.dcap_contract.clone(),
+            explorer_url: self.explorer_url.clone(),
+            pcs_dao: self.pcs_dao.clone(),
+            fmspc_tcb_dao: self.fmspc_tcb_dao.clone(),
+            enclave_id_dao: self.enclave_id_dao.clone(),
+            pck_dao: self.pck_dao.clone(),
+            multicall3: self.multicall3.clone(),
+        };
+        resolve_chain_config(&self.network, self.config.as_deref(), &overrides)
+    }
+}
+
+#[derive(Args)]
+struct CacheArgs {
+    #[command(subcommand)]
+    command: CacheCommands,
+
+    /// Optional: Collateral cache directory. Default: $XDG_CACHE_HOME/dcap-sp1-cli/collaterals
+    #[arg(long = "cache-dir", env = "COLLATERAL_CACHE_DIR", global = true)]
+    cache_dir: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum CacheCommands {
+    /// Lists the cached collaterals and when they expire
+    List,
+
+    /// Removes cached collaterals
+    Clear {
+        /// Only removes the collaterals past their nextUpdate
+        #[arg(long = "expired")]
+        expired: bool,
+    },
+}
+
+#[derive(Args)]
+struct PckArgs {
+    #[command(subcommand)]
+    command: PckCommands,
+}
+
+#[derive(Subcommand)]
+enum PckCommands {
+    /// Prints the SGX extensions of a PCK certificate: PPID, TCB, PCE-ID, FMSPC, SGX type and configuration
+    Show(PckShowArgs),
+}
+
+#[derive(Args)]
+struct PckShowArgs {
+    /// PCK certificate (DER or PEM, the first certificate of a PEM chain is used), instead of the quote's
+    #[arg(long = "cert")]
+    cert: Option<PathBuf>,
+
+    /// The input quote provided as a hex string, this overwrites the --quote-path argument
+    #[arg(short = 'q', long = "quote-hex")]
+    quote_hex: Option<String>,
+
+    /// Optional: The path to a quote.hex file. Default: /data/quote.hex or overwritten by the --quote-hex argument if provided.
+    #[arg(short = 'p', long = "quote-path")]
+    quote_path: Option<PathBuf>,
+
+    /// Prints the SGX extensions as JSON
+    #[arg(long = "json")]
+    json: bool,
+
+    /// Used to look up the PCK certificate in the PCK DAO for quotes that carry no PCK certificate chain
+    #[command(flatten)]
+    chain: ChainArgs,
+}
+
+#[derive(Args)]
+struct CollateralsArgs {
+    #[command(subcommand)]
+    command: CollateralsCommands,
+}
+
+#[derive(Subcommand)]
+enum CollateralsCommands {
+    /// Writes the collaterals used for a quote to a JSON bundle, with their source, block and hashes
+    Fetch(CollateralsFetchArgs),
+
+    /// Prints the TCB levels, QE identity levels, certificates and CRLs of a bundle, or of the live collaterals of a quote
+    Show(CollateralsShowArgs),
+}
+
+#[derive(Args)]
+struct CollateralsFetchArgs {
+    /// The input quote provided as a hex string, this overwrites the --quote-path argument
+    #[arg(short = 'q', long = "quote-hex")]
+    quote_hex: Option<String>,
+
+    /// Optional: The path to a quote.hex file. Default: /data/quote.hex or overwritten by the --quote-hex argument if provided.
+    #[arg(short = 'p', long = "quote-path")]
+    quote_path: Option<PathBuf>,
+
+    /// Path of the JSON bundle to write
+    #[arg(short = 'o', long = "out")]
+    out: PathBuf,
+
+    #[command(flatten)]
+    chain: ChainArgs,
+
+    #[command(flatten)]
+    collaterals: CollateralArgs,
+}
+
+#[derive(Args)]
+struct CollateralsShowArgs {
+    /// JSON bundle written by `collaterals fetch`, instead of fetching the quote's collaterals
+    #[arg(short = 'b', long = "bundle")]
+    bundle: Option<PathBuf>,
+
+    /// The input quote provided as a hex string, this overwrites the --quote-path argument
+    #[arg(short = 'q', long = "quote-hex")]
+    quote_hex: Option<String>,
+
+    /// Optional: The path to a quote.hex file. Default: /data/quote.hex or overwritten by the --quote-hex argument if provided.
+    #[arg(short = 'p', long = "quote-path")]
+    quote_path: Option<PathBuf>,
+
+    #[command(flatten)]
+    chain: ChainArgs,
+
+    #[command(flatten)]
+    collaterals: CollateralArgs,
+}
+
+#[derive(Args)]
+struct PccsArgs {
+    #[command(subcommand)]
+    command: PccsCommands,
+}
+
+#[derive(Subcommand)]
+enum PccsCommands {
+    /// Upserts Intel PCS collaterals to the PCS, FMSPC TCB and Enclave Identity DAOs and prints their attestation IDs
+    Upsert(PccsUpsertArgs),
+}
+
+#[derive(Args)]
+struct PccsUpsertArgs {
+    /// TCBInfo JSON as returned by the PCS tcb endpoint
+    #[arg(long = "tcb-info")]
+    tcb_info: Option<PathBuf>,
+
+    /// QEIdentity JSON as returned by the PCS qe/identity endpoint
+    #[arg(long = "qe-identity")]
+    qe_identity: Option<PathBuf>,
+
+    /// PCS API version the QEIdentity was fetched from, which the DAO stores it under
+    #[arg(long = "qe-identity-version", default_value_t = 4)]
+    qe_identity_version: u32,
+
+    /// Issuer chain of the TCBInfo or QEIdentity (the TCB-Info-Issuer-Chain or SGX-Enclave-Identity-Issuer-Chain header, URL-encoded or not), whose TCB Signing CA is upserted first
+    #[arg(long = "issuer-chain")]
+    issuer_chain: Option<PathBuf>,
+
+    /// PCK CRL (DER, PEM or hex) as returned by the PCS pckcrl endpoint, its CA is read from its issuer
+    #[arg(long = "pck-crl")]
+    pck_crl: Option<PathBuf>,
 
-            let mut intel_collaterals = IntelCollateral::new();
-            println!("set_tcbinfo_bytes: {:?}", tcb_info);
-            intel_collaterals.set_tcbinfo_bytes(&tcb_info);
-            println!("set_qeidentity_bytes: {:?}", qe_identity);
-            intel_collaterals.set_qeidentity_bytes(&qe_identity);
-            println!("set_intel_root_ca_der: {:?}", root_ca);
-            intel_collaterals.set_intel_root_ca_der(&root_ca);
-            println!("set_sgx_tcb_signing_der: {:?}", signing_ca);
-            intel_collaterals.set_sgx_tcb_signing_der(&signing_ca);
-            println!("set_sgx_intel_root_ca_crl_der: {:?}", root_ca_crl);
-            intel_collaterals.set_sgx_intel_root_ca_crl_der(&root_ca_crl);
-            println!("set_sgx_platform_crl_der: {:?}", pck_crl);
-            intel_collaterals.set_sgx_platform_crl_der(&pck_crl);
+    /// Root CA CRL (DER, PEM or hex)
+    #[arg(long = "root-ca-crl")]
+    root_ca_crl: Option<PathBuf>,
 
+    /// Optional: The upserts are only simulated if left blank.
+    #[arg(short = 'k', long = "wallet-key")]
+    wallet_private_key: Option<String>,
+
+    #[command(flatten)]
+    chain: ChainArgs,
+}
+
+#[derive(Args)]
+struct OutputArgs {
+    #[arg(short = 'o', long = "output")]
+    output: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    utils::setup_logger();
+
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+
+    match &cli.command {
+        Commands::Prove(args) => {
+            // Steps 0 to 2: Read the quote and load its collaterals, checked at the time
+            // the guest will verify the quote at
+            let current_time = now() as u64;
+            let QuoteInput {
+                quote,
+                chain_config,
+                pinned_block,
+                collaterals,
+                tcb_report,
+                ..
+            } = load_quote_input(
+                &args.quote_path,
+                &args.quote_hex,
+                &args.chain,
+                &args.collaterals,
+                current_time,
+                true,
+            )
+            .await?;
+            print!("{}", tcb_report);
+            let intel_collaterals = intel_collateral(&collaterals)?;
             let intel_collaterals_bytes = intel_collaterals.to_bytes();
 
-            // Step 3: Generate the input to upload to SP1 Proving Server
-            let input = generate_input(&quote, &intel_collaterals_bytes);
+            // Step 3: Check the collaterals at the time the guest will verify the quote at
+            check_freshness(&collaterals, current_time, args.allow_stale)?;
+
+            // Step 4: Generate the input to upload to SP1 Proving Server
+            let input = generate_input(&quote, &intel_collaterals_bytes, current_time);
 
             println!("All collaterals found! Begin uploading input to SP1 Proving Server...");
 
@@ -217,24 +676,63 @@ async fn main() -> Result<()> {
 
             let parsed_output = VerifiedOutput::from_bytes(&output);
             println!("{:?}", parsed_output);
+            if let Some(block) = &pinned_block {
+                println!("Collaterals Block: {} ({})", block.number, block.hash);
+            }
 
-            // Send the calldata to Ethereum.
-            println!("Submitting proofs to on-chain DCAP contract to be verified...");
-            let calldata = generate_attestation_calldata(&ret_slice, &proof.bytes());
-            println!("Calldata: {}", hex::encode(&calldata));
-
-            let tx_sender = TxSender::new(DEFAULT_RPC_URL, DEFAULT_DCAP_CONTRACT)
-                .expect("Failed to create txSender");
+            if args.collaterals.is_offline() {
+                println!("Collaterals were read from local files, skipping on-chain verification");
+            } else {
+                verify_on_chain(&chain_config, ret_slice, &proof.bytes(), &output).await?;
+            }
+        }
+        Commands::Verify(args) => {
+            let timestamp = args.timestamp.unwrap_or(now() as u64);
+            let QuoteInput {
+                quote,
+                version,
+                collaterals,
+                ..
+            } = load_quote_input(
+                &args.quote_path,
+                &args.quote_hex,
+                &args.chain,
+                &args.collaterals,
+                timestamp,
+                false,
+            )
+            .await?;
+            let intel_collaterals = intel_collateral(&collaterals)?;
 
-            // staticcall to the DCAP verifier contract to verify proof
-            let call_output = (tx_sender.call(calldata.clone()).await?).to_vec();
-            let (chain_verified, chain_raw_verified_output) =
-                decode_attestation_ret_data(call_output);
+            let verified_output = verify_quote(&quote, version, &intel_collaterals, timestamp)?;
 
-            if chain_verified && output == chain_raw_verified_output {
-                println!("On-chain verification succeed.");
+            println!("Timestamp: {}", format_timestamp(timestamp as i64));
+            println!("Verified Output: {:?}", verified_output);
+            println!("Output: {}", hex::encode(verified_output.to_vec()));
+        }
+        Commands::Tcb(args) => {
+            let QuoteInput { tcb_report, .. } = load_quote_input(
+                &args.quote_path,
+                &args.quote_hex,
+                &args.chain,
+                &args.collaterals,
+                now() as u64,
+                false,
+            )
+            .await?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&tcb_report)?);
             } else {
-                println!("On-chain verification fail!");
+                print!("{}", tcb_report);
+            }
+        }
+        Commands::Inspect(args) => {
+            let quote = get_quote(&args.quote_path, &args.quote_hex)?;
+            let parsed_quote = Quote::from_bytes(&quote)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&parsed_quote)?);
+            } else {
+                print!("{}", parsed_quote);
             }
         }
         Commands::Deserialize(args) => {
@@ -243,6 +741,84 @@ async fn main() -> Result<()> {
             let deserialized_output = VerifiedOutput::from_bytes(&output_vec);
             println!("Deserialized output: {:?}", deserialized_output);
         }
+        Commands::Cache(args) => {
+            let cache = CollateralCache::new(&cache_dir(&args.cache_dir)?);
+            match &args.command {
+                CacheCommands::List => {
+                    let now = now();
+                    let entries = cache.list()?;
+                    println!(
+                        "{} cached collaterals in {}",
+                        entries.len(),
+                        cache.dir().display()
+                    );
+                    for entry in entries {
+                        println!(
+                            "{}  nextUpdate: {}{}",
+                            entry.key,
+                            format_timestamp(entry.next_update),
+                            if entry.is_expired(now) {
+                                " (expired)"
+                            } else {
+                                ""
+                            }
+                        );
+                    }
+                }
+                CacheCommands::Clear { expired } => {
+                    let removed = cache.clear(*expired)?;
+                    println!(
+                        "Removed {} cached collaterals from {}",
+                        removed,
+                        cache.dir().display()
+                    );
+                }
+            }
+        }
+        Commands::Pck(args) => match &args.command {
+            PckCommands::Show(args) => {
+                let pck_cert = get_pck_cert_for_show(args).await?;
+                let extensions = get_pck_extensions(&pck_cert)?;
+                if args.json {
+                    println!("{}", serde_json::to_string_pretty(&extensions)?);
+                } else {
+                    let (_, pck_issuer) = get_pck_issuer(&pck_cert)?;
+                    println!("PCK issuer: {}", pck_issuer);
+                    print!("{}", extensions);
+                }
+            }
+        },
+        Commands::Collaterals(args) => match &args.command {
+            CollateralsCommands::Fetch(args) => {
+                let bundle = fetch_bundle(
+                    &args.quote_path,
+                    &args.quote_hex,
+                    &args.chain,
+                    &args.collaterals,
+                )
+                .await?;
+                std::fs::write(&args.out, bundle.to_json()?)?;
+                println!("Collaterals written to {}", args.out.display());
+            }
+            CollateralsCommands::Show(args) => {
+                let bundle = match &args.bundle {
+                    Some(path) => CollateralBundle::from_json(&std::fs::read(path)?)?,
+                    None => {
+                        fetch_bundle(
+                            &args.quote_path,
+                            &args.quote_hex,
+                            &args.chain,
+                            &args.collaterals,
+                        )
+                        .await?
+                    }
+                };
+                print!("{}", bundle.summary()?);
+            }
+        },
+        Commands::Pccs(args) => match &args.command {
+            PccsCommands::Upsert(args) => upsert_collaterals(args).await?,
+        },
     }
 
     println!("Job completed!");
@@ -250,6 +826,324 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// A quote and the collaterals needed to verify it
+struct QuoteInput {
+    quote: Vec<u8>,
+    version: u16,
+    chain_config: ChainConfig,
+    pinned_block: Option<PinnedBlock>,
+    source: String,
+    request: CollateralRequest,
+    collaterals: Collaterals,
+    tcb_report: TcbReport,
+}
+
+/// Reads the quote, fetches its collaterals, checks their signatures and its PCK certificate chain,
+/// and evaluates its TCB level, QE identity and TDX module identity. The PCK certificate chain
+/// is checked at `timestamp` (seconds since epoch), the time the quote is verified at.
+/// With `for_guest`, quotes the guest program cannot verify are refused before anything is fetched.
+async fn load_quote_input(
+    quote_path: &Option<PathBuf>,
+    quote_hex: &Option<String>,
+    chain_args: &ChainArgs,
+    collateral_args: &CollateralArgs,
+    timestamp: u64,
+    for_guest: bool,
+) -> Result<QuoteInput> {
+    // Step 0: Read quote
+    println!("Begin reading quote and fetching the necessary collaterals...");
+    let quote = get_quote(quote_path, quote_hex).context("Failed to read quote")?;
+
+    // Step 1: Determine quote version and TEE type
+    let parsed_quote = Quote::from_bytes(&quote)?;
+    let quote_version = parsed_quote.header.version;
+    let tee_type = parsed_quote.header.tee_type;
+
+    println!("Quote version: {}", quote_version);
+    println!("TEE Type: {}", tee_type);
+
+    // The guest only verifies v3 and v4 quotes that embed their PCK certificate chain,
+    // don't pay for a proof bound to fail
+    if for_guest {
+        check_supported_quote(&parsed_quote)?;
+    }
+
+    // Step 2: Load collaterals
+    let chain_config = chain_args.to_config()?;
+    if !collateral_args.is_offline() {
+        check_chain_id(&chain_config).await?;
+    }
+    let pinned_block = collateral_args.pinned_block(&chain_config).await?;
+    if let Some(block) = &pinned_block {
+        println!(
+            "Reading on-chain collaterals at block {} ({})",
+            block.number, block.hash
+        );
+    }
+    let provider = collateral_args.build_provider(&chain_config, pinned_block.as_ref())?;
+    let source = provider.name();
+    println!(
+        "Quote read successfully. Begin fetching collaterals from {}",
+        source
+    );
+
+    let (pck_cert_chain, pck_from_dao) = match get_pck_certification_data(&parsed_quote)? {
+        PckCertificationData::CertChain(chain) => (chain, false),
+        PckCertificationData::Identifier(id) => {
+            if collateral_args.is_offline() {
+                return Err(anyhow::Error::msg(
+                    "The quote carries no PCK certificate chain, which must be read from the on-chain PCK DAO",
+                ));
+            }
+            let block = pinned_block
+                .map(|block| block.id())
+                .unwrap_or(BlockNumberOrTag::Latest.into());
+            let client = PccsClient::new(&chain_config, block)?;
+            println!(
+                "Reading the PCK certificate for QE ID {} from the PCK DAO",
+                hex::encode(id.qe_id)
+            );
+            (get_pck_cert_chain(&client, &id).await?, true)
+        }
+    };
+    let (fmspc, pck_type, pck_issuer) = get_pck_fmspc_and_issuer(&pck_cert_chain)?;
+    println!("FMSPC: {}, PCK issuer: {}", fmspc, pck_issuer);
+
+    let request = CollateralRequest::new(quote_version, tee_type, &fmspc, pck_type);
+    let mut collaterals = fetch_collaterals(provider.as_ref(), &request).await?;
+    if pck_from_dao {
+        collaterals.pck_certchain = pck_cert_chain.clone();
+    }
+
+    // Hand the guest the TCBInfo and QEIdentity bodies exactly as signed, whatever the source
+    let tcb_info = Signed::<TcbInfo>::from_json(&collaterals.tcb_info)?;
+    let qe_identity = Signed::<EnclaveIdentity>::from_json(&collaterals.qe_identity)?;
+    collaterals.tcb_info = tcb_info.to_json();
+    collaterals.qe_identity = qe_identity.to_json();
+
+    // Catch a swapped root of trust before anything is verified against it
+    check_root_ca(
+        &collaterals.root_ca,
+        collateral_args.trusted_root()?.as_deref(),
+    )?;
+
+    // Catch tampered or mis-stored collaterals before paying for a proof
+    verify_collateral_signatures(&collaterals)?;
+    println!("TCBInfo and QEIdentity signatures verified");
+
+    // Catch a revoked or expired PCK certificate before paying for a proof
+    verify_pck_chain(&pck_cert_chain, &collaterals, timestamp as i64)?;
+    println!("PCK certificate chain verified");
+
+    let pck_extensions = get_pck_extensions(&pem_chain_to_der(&pck_cert_chain)?[0])?;
+    let tcb_evaluation = evaluate_tcb(
+        &tcb_info.body,
+        &pck_extensions,
+        parsed_quote.body.tee_tcb_svn(),
+    )?;
+    let qe_report_cert_data = parsed_quote
+        .signature
+        .cert_data
+        .qe_report_cert_data()
+        .ok_or_else(|| anyhow::Error::msg("The quote carries no QE report"))?;
+    let qe_identity_evaluation =
+        evaluate_qe_identity(&qe_identity.body, &qe_report_cert_data.qe_report)?;
+    let tdx_module_evaluation = match parsed_quote.body.td_report() {
+        Some(td_report) => Some(evaluate_tdx_module(&tcb_info.body, td_report)?),
+        None => None,
+    };
+    let tcb_report = TcbReport::new(
+        tcb_evaluation,
+        qe_identity_evaluation,
+        tdx_module_evaluation,
+    );
+
+    Ok(QuoteInput {
+        quote,
+        version: quote_version,
+        chain_config,
+        pinned_block,
+        source,
+        request,
+        collaterals,
+        tcb_report,
+    })
+}
+
+/// Loads the quote's collaterals into a bundle, reading on-chain collaterals at a pinned
+/// block, the latest one unless --block is given, so that the bundle records it
+async fn fetch_bundle(
+    quote_path: &Option<PathBuf>,
+    quote_hex: &Option<String>,
+    chain_args: &ChainArgs,
+    collateral_args: &CollateralArgs,
+) -> Result<CollateralBundle> {
+    let mut collateral_args = collateral_args.clone();
+    collateral_args
+        .block
+        .get_or_insert(BlockNumberOrTag::Latest.into());
+
+    let QuoteInput {
+        source,
+        pinned_block,
+        request,
+        collaterals,
+        ..
+    } = load_quote_input(
+        quote_path,
+        quote_hex,
+        chain_args,
+        &collateral_args,
+        now() as u64,
+        false,
+    )
+    .await?;
+    CollateralBundle::new(&source, pinned_block.as_ref(), &request, &collaterals)
+}
+
+fn intel_collateral(collaterals: &Collaterals) -> Result<IntelCollateral> {
+    let mut intel_collaterals = IntelCollateral::new();
+    intel_collaterals.set_tcbinfo_bytes(&collaterals.tcb_info);
+    intel_collaterals.set_qeidentity_bytes(&collaterals.qe_identity);
+    intel_collaterals.set_intel_root_ca_der(&collaterals.root_ca);
+    intel_collaterals.set_sgx_tcb_signing_der(&collaterals.tcb_signing_ca);
+    intel_collaterals.set_sgx_intel_root_ca_crl_der(&collaterals.root_ca_crl);
+    intel_collaterals.set_sgx_platform_crl_der(&collaterals.pck_crl);
+    if !collaterals.pck_certchain.is_empty() {
+        // The PCK DAO returns the chain in PEM, the guest expects concatenated DER certificates
+        intel_collaterals.sgx_pck_certchain_der = Some(collaterals.pck_certchain_der()?);
+    }
+
+    Ok(intel_collaterals)
+}
+
+/// Upserts the given collaterals to the PCCS DAOs, certificates and CRLs first since
+/// the DAOs verify the TCBInfo and QEIdentity against them. Without a wallet key nothing
+/// is sent, so every upsert is simulated against the DAOs as they are on-chain, without
+/// the certificates and CRLs simulated before it
+async fn upsert_collaterals(args: &PccsUpsertArgs) -> Result<()> {
+    let chain_config = args.chain.to_config()?;
+    check_chain_id(&chain_config).await?;
+
+    let mut upserts: Vec<(String, &str, Vec<u8>)> = Vec::new();
+    if let Some(path) = &args.issuer_chain {
+        upserts.push((
+            String::from("Intel TCB Signing CA"),
+            chain_config.pcs_dao.as_str(),
+            upsert_certificate_calldata(CA::SIGNING, &read_signing_ca(path)?),
+        ));
+    }
+    if let Some(path) = &args.root_ca_crl {
+        let crl = to_der(&std::fs::read(path)?)?;
+        upserts.push((
+            String::from("Root CA CRL"),
+            chain_config.pcs_dao.as_str(),
+            upsert_root_ca_crl_calldata(&crl),
+        ));
+    }
+    if let Some(path) = &args.pck_crl {
+        let crl = to_der(&std::fs::read(path)?)?;
+        let ca = pck_crl_ca(&crl)?;
+        upserts.push((
+            format!("PCK CRL ({})", ca_name(ca)),
+            chain_config.pcs_dao.as_str(),
+            upsert_pck_crl_calldata(ca, &crl),
+        ));
+    }
+    if let Some(path) = &args.tcb_info {
+        let tcb_info = Signed::<TcbInfo>::from_json(&std::fs::read(path)?)?;
+        upserts.push((
+            format!("TCBInfo for FMSPC: {}", tcb_info.body.fmspc),
+            chain_config.fmspc_tcb_dao.as_str(),
+            upsert_tcb_info_calldata(&tcb_info),
+        ));
+    }
+    if let Some(path) = &args.qe_identity {
+        let identity = Signed::<EnclaveIdentity>::from_json(&std::fs::read(path)?)?;
+        upserts.push((
+            format!(
+                "{} identity; Version: {}",
+                identity.body.id, args.qe_identity_version
+            ),
+            chain_config.enclave_id_dao.as_str(),
+            upsert_enclave_identity_calldata(&identity, args.qe_identity_version)?,
+        ));
+    }
+    if upserts.is_empty() {
+        return Err(anyhow::Error::msg(
+            "Nothing to upsert, pass --tcb-info, --qe-identity, --issuer-chain, --pck-crl or --root-ca-crl",
+        ));
+    }
+
+    let mut simulated: Vec<String> = Vec::new();
+    for (collateral, dao, calldata) in upserts {
+        let mut tx_sender = TxSender::new(&chain_config.rpc_url, dao)?;
+
+        // staticcall first, to read the attestation ID and catch a rejected collateral before paying gas
+        let attestation_id = tx_sender
+            .call(calldata.clone())
+            .await
+            .and_then(|output| decode_attestation_id(&output))
+            .with_context(|| {
+                if simulated.is_empty() {
+                    format!("The DAO rejects the upsert of {}", collateral)
+                } else {
+                    format!(
+                        "The DAO rejects the upsert of {}, note that it was simulated without the {} of this run, which were not sent",
+                        collateral,
+                        simulated.join(", ")
+                    )
+                }
+            })?;
+        match &args.wallet_private_key {
+            Some(wallet_key) => {
+                tx_sender.set_wallet(wallet_key)?;
+                let tx_receipt = tx_sender.send(calldata).await?;
+                let hash = tx_receipt.transaction_hash;
+                if !tx_receipt.status() {
+                    return Err(anyhow::Error::msg(format!(
+                        "The upsert of {} reverted in transaction 0x{}",
+                        collateral,
+                        hex::encode(hash.as_slice())
+                    )));
+                }
+                println!(
+                    "Upserted {}, attestation ID: {}",
+                    collateral, attestation_id
+                );
+                println!(
+                    "See transaction at: {}/0x{}",
+                    chain_config.explorer_url,
+                    hex::encode(hash.as_slice())
+                );
+            }
+            None => {
+                println!(
+                    "{} would be upserted, attestation ID: {}",
+                    collateral, attestation_id
+                );
+                simulated.push(collateral);
+            }
+        }
+    }
+    if args.wallet_private_key.is_none() {
+        println!("No wallet key provided, the upserts were only simulated");
+    }
+
+    Ok(())
+}
+
+/// Reads the TCB Signing CA, the first certificate of a PEM issuer chain as the PCS
+/// returns it in its issuer chain headers, URL-encoded or not
+fn read_signing_ca(path: &PathBuf) -> Result<Vec<u8>> {
+    let encoded = read_to_string(path)?;
+    let decoded = percent_encoding::percent_decode_str(encoded.trim()).decode_utf8()?;
+    pem_chain_to_der(decoded.as_bytes())?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::Error::msg("The issuer chain holds no certificate"))
+}
+
 fn get_quote(path: &Option<PathBuf>, hex: &Option<String>) -> Result<Vec<u8>> {
     let error_msg: &str = "Failed to read quote from the provided path";
     match hex {
@@ -259,14 +1153,14 @@ fn get_quote(path: &Option<PathBuf>, hex: &Option<String>) -> Result<Vec<u8>> {
         }
         _ => match path {
             Some(p) => {
-                let quote_string = read_to_string(p).expect(error_msg);
+                let quote_string = read_to_string(p).context(error_msg)?;
                 let processed = remove_prefix_if_found(&quote_string);
                 let quote_hex = hex::decode(processed)?;
                 Ok(quote_hex)
             }
             _ => {
                 let default_path = PathBuf::from(DEFAULT_QUOTE_PATH);
-                let quote_string = read_to_string(default_path).expect(error_msg);
+                let quote_string = read_to_string(default_path).context(error_msg)?;
                 let processed = remove_prefix_if_found(&quote_string);
                 let quote_hex = hex::decode(processed)?;
                 Ok(quote_hex)
@@ -275,12 +1169,65 @@ fn get_quote(path: &Option<PathBuf>, hex: &Option<String>) -> Result<Vec<u8>> {
     }
 }
 
-fn generate_input(quote: &[u8], collaterals: &[u8]) -> Vec<u8> {
-    // get current time in seconds since epoch
-    let current_time = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+/// Returns the DER PCK certificate given with --cert, or else the one of the quote,
+/// read from the PCK DAO if the quote carries no PCK certificate chain
+async fn get_pck_cert_for_show(args: &PckShowArgs) -> Result<Vec<u8>> {
+    if let Some(path) = &args.cert {
+        return to_der(&std::fs::read(path)?);
+    }
+
+    let quote = get_quote(&args.quote_path, &args.quote_hex)?;
+    let parsed_quote = Quote::from_bytes(&quote)?;
+    match get_pck_certification_data(&parsed_quote)? {
+        PckCertificationData::CertChain(chain) => pem_chain_to_der(&chain)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::Error::msg("The quote's PCK certificate chain is empty")),
+        PckCertificationData::Identifier(id) => {
+            let chain_config = args.chain.to_config()?;
+            let client = PccsClient::new(&chain_config, BlockNumberOrTag::Latest.into())?;
+            get_pck_cert(&client, &id).await
+        }
+    }
+}
+
+fn cache_dir(dir: &Option<PathBuf>) -> Result<PathBuf> {
+    match dir {
+        Some(dir) => Ok(dir.clone()),
+        _ => CollateralCache::default_dir(),
+    }
+}
+
+async fn verify_on_chain(
+    chain_config: &ChainConfig,
+    output: &[u8],
+    proof: &[u8],
+    raw_verified_output: &[u8],
+) -> Result<()> {
+    // Send the calldata to Ethereum.
+    println!("Submitting proofs to on-chain DCAP contract to be verified...");
+    let calldata = generate_attestation_calldata(output, proof);
+    println!("Calldata: {}", hex::encode(&calldata));
+
+    let tx_sender = TxSender::new(&chain_config.rpc_url, &chain_config.dcap_contract)
+        .expect("Failed to create txSender");
+
+    // staticcall to the DCAP verifier contract to verify proof
+    let call_output = (tx_sender.call(calldata.clone()).await?).to_vec();
+    let (chain_verified, chain_raw_verified_output) = decode_attestation_ret_data(call_output);
+
+    if chain_verified && raw_verified_output == chain_raw_verified_output {
+        println!("On-chain verification succeed.");
+    } else {
+        println!("On-chain verification fail!");
+    }
+
+    Ok(())
+}
+
+/// Serializes the guest input, `current_time` (seconds since epoch) being the time
+/// the guest verifies the quote at
+fn generate_input(quote: &[u8], collaterals: &[u8], current_time: u64) -> Vec<u8> {
     let current_time_bytes = current_time.to_le_bytes();
 
     let quote_len = quote.len() as u32;
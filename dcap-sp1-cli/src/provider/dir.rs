@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::fs::read;
+use std::path::{Path, PathBuf};
+
+use super::{ca_name, CollateralProvider};
+use crate::chain::pccs::{enclave_id::EnclaveIdType, pcs::IPCSDao::CA};
+use crate::to_der;
+
+const CERT_EXTENSIONS: [&str; 5] = ["der", "pem", "crl", "cer", "hex"];
+pub const TCB_INFO_KEY: &str = "tcbInfo";
+pub const ENCLAVE_IDENTITY_KEY: &str = "enclaveIdentity";
+
+/// Reads collaterals from files in a local directory.
+///
+/// Certificates and CRLs may be stored as DER, PEM or hex, under any of the extensions
+/// in `CERT_EXTENSIONS`:
+///
+/// - `root_ca`, `root_ca_crl`, `signing_ca`
+/// - `pck_platform_crl` or `pck_processor_crl`, falling back to `pck_crl`
+///
+/// TCBInfo and QEIdentity are stored as JSON in Intel's signed format:
+///
+/// - `tcb_info_<fmspc>.json` (`tdx_tcb_info_<fmspc>.json` for TDX), falling back to `tcb_info.json`
+/// - `qe_identity.json`, `tdqe_identity.json` or `qve_identity.json`
+pub struct DirProvider {
+    dir: PathBuf,
+}
+
+impl DirProvider {
+    pub fn new(dir: &Path) -> Self {
+        DirProvider {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    fn find(&self, names: &[String]) -> Result<PathBuf> {
+        names
+            .iter()
+            .map(|name| self.dir.join(name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                anyhow::Error::msg(format!(
+                    "None of {} found in {}",
+                    names.join(", "),
+                    self.dir.display()
+                ))
+            })
+    }
+
+    fn read_cert(&self, stems: &[&str]) -> Result<Vec<u8>> {
+        let names: Vec<String> = stems
+            .iter()
+            .flat_map(|stem| {
+                CERT_EXTENSIONS
+                    .iter()
+                    .map(move |ext| format!("{}.{}", stem, ext))
+            })
+            .collect();
+        read_cert_file(&self.find(&names)?)
+    }
+}
+
+/// Reads a certificate or CRL file stored as DER, PEM or hex and returns it DER-encoded.
+pub fn read_cert_file(path: &Path) -> Result<Vec<u8>> {
+    let raw = read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let der = to_der(&raw).with_context(|| format!("Failed to decode {}", path.display()))?;
+    if der.is_empty() {
+        return Err(anyhow::Error::msg(format!("{} is empty", path.display())));
+    }
+    Ok(der)
+}
+
+/// Reads a TCBInfo or QEIdentity file and checks that it is in Intel's signed format,
+/// i.e. a JSON object with the collateral under `body_key` and a hex `signature`.
+pub fn read_signed_json_file(path: &Path, body_key: &str) -> Result<Vec<u8>> {
+    let raw = read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let json: serde_json::Value = serde_json::from_slice(&raw)
+        .with_context(|| format!("{} is not valid JSON", path.display()))?;
+
+    if !json.get(body_key).is_some_and(|body| body.is_object()) {
+        return Err(anyhow::Error::msg(format!(
+            "{} is missing the \"{}\" object",
+            path.display(),
+            body_key
+        )));
+    }
+    let signature_is_hex = json
+        .get("signature")
+        .and_then(|signature| signature.as_str())
+        .is_some_and(|signature| hex::decode(signature).is_ok());
+    if !signature_is_hex {
+        return Err(anyhow::Error::msg(format!(
+            "{} is missing a hex \"signature\"",
+            path.display()
+        )));
+    }
+
+    Ok(raw.trim_ascii().to_vec())
+}
+
+#[async_trait]
+impl CollateralProvider for DirProvider {
+    fn name(&self) -> String {
+        format!("directory ({})", self.dir.display())
+    }
+
+    async fn root_ca(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+        let root_ca = self.read_cert(&["root_ca"])?;
+        let root_ca_crl = self.read_cert(&["root_ca_crl"])?;
+        Ok((root_ca, root_ca_crl))
+    }
+
+    async fn signing_ca(&self) -> Result<Vec<u8>> {
+        self.read_cert(&["signing_ca"])
+    }
+
+    async fn pck_crl(&self, ca: CA) -> Result<Vec<u8>> {
+        let stem = format!("pck_{}_crl", ca_name(ca));
+        self.read_cert(&[stem.as_str(), "pck_crl"])
+    }
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, _version: u32) -> Result<Vec<u8>> {
+        let prefix = if tcb_type == 1 {
+            "tdx_tcb_info"
+        } else {
+            "tcb_info"
+        };
+        let path = self.find(&[
+            format!("{}_{}.json", prefix, fmspc),
+            format!("{}.json", prefix),
+        ])?;
+        read_signed_json_file(&path, TCB_INFO_KEY)
+    }
+
+    async fn qe_identity(&self, id: EnclaveIdType, _version: u32) -> Result<Vec<u8>> {
+        let name = match id {
+            EnclaveIdType::QE => "qe_identity.json",
+            EnclaveIdType::QVE => "qve_identity.json",
+            EnclaveIdType::TDQE => "tdqe_identity.json",
+        };
+        read_signed_json_file(&self.find(&[name.to_string()])?, ENCLAVE_IDENTITY_KEY)
+    }
+}
@@ -0,0 +1,359 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{header::HeaderMap, Client};
+
+use super::{ca_name, CollateralProvider};
+use crate::chain::pccs::{enclave_id::EnclaveIdType, pcs::IPCSDao::CA};
+use crate::constants::INTEL_ROOT_CA_CRL_URL;
+use crate::{pem_chain_to_der, to_der};
+
+const PCK_CRL_ISSUER_CHAIN_HEADER: &str = "SGX-PCK-CRL-Issuer-Chain";
+const ENCLAVE_IDENTITY_ISSUER_CHAIN_HEADER: &str = "SGX-Enclave-Identity-Issuer-Chain";
+
+/// Reads collaterals from the Intel PCS or a PCCS instance over the v3/v4 HTTP API.
+///
+/// The API version follows the collateral version that is requested: TCBInfo v2 is served
+/// by the v3 API and TCBInfo v3 by the v4 API, QEIdentity versions map to the API directly.
+pub struct PcsProvider {
+    base_url: String,
+    client: Client,
+}
+
+impl PcsProvider {
+    /// Creates a provider for the given base URL, e.g. `https://api.trustedservices.intel.com`
+    /// or the address of a local PCCS.
+    pub fn new(base_url: &str) -> Self {
+        PcsProvider {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: Client::new(),
+        }
+    }
+
+    async fn get(&self, url: &str) -> Result<(Vec<u8>, HeaderMap)> {
+        tracing::debug!("GET {}", url);
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", url))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::Error::msg(format!(
+                "{} responded with {}",
+                url, status
+            )));
+        }
+
+        let headers = response.headers().clone();
+        let body = response.bytes().await?.to_vec();
+        Ok((body, headers))
+    }
+
+    fn issuer_chain(headers: &HeaderMap, name: &str) -> Result<Vec<Vec<u8>>> {
+        let encoded = headers
+            .get(name)
+            .ok_or_else(|| anyhow::Error::msg(format!("Missing {} header", name)))?
+            .to_str()?;
+        let decoded = percent_encoding::percent_decode_str(encoded).decode_utf8()?;
+        let chain = pem_chain_to_der(decoded.as_bytes())?;
+        if chain.is_empty() {
+            return Err(anyhow::Error::msg(format!("Empty {} header", name)));
+        }
+        Ok(chain)
+    }
+
+    fn api_version_for_tcb_info(version: u32) -> u32 {
+        if version < 3 {
+            3
+        } else {
+            4
+        }
+    }
+}
+
+#[async_trait]
+impl CollateralProvider for PcsProvider {
+    fn name(&self) -> String {
+        format!("PCS ({})", self.base_url)
+    }
+
+    async fn root_ca(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+        // The root certificate is the last entry of any issuer chain
+        let url = format!(
+            "{}/sgx/certification/v4/pckcrl?ca=platform&encoding=der",
+            self.base_url
+        );
+        let (_, headers) = self.get(&url).await?;
+        let mut chain = Self::issuer_chain(&headers, PCK_CRL_ISSUER_CHAIN_HEADER)?;
+        let root_ca = chain.pop().unwrap();
+
+        // A PCCS serves the Root CA CRL itself, the Intel PCS leaves it to the distribution point
+        let url = format!("{}/sgx/certification/v4/rootcacrl", self.base_url);
+        let root_ca_crl = match self.get(&url).await {
+            Ok((body, _)) => to_der(&body)?,
+            Err(_) => {
+                let (body, _) = self.get(INTEL_ROOT_CA_CRL_URL).await?;
+                to_der(&body)?
+            }
+        };
+
+        Ok((root_ca, root_ca_crl))
+    }
+
+    async fn signing_ca(&self) -> Result<Vec<u8>> {
+        let url = format!("{}/sgx/certification/v4/qe/identity", self.base_url);
+        let (_, headers) = self.get(&url).await?;
+        let mut chain = Self::issuer_chain(&headers, ENCLAVE_IDENTITY_ISSUER_CHAIN_HEADER)?;
+        Ok(chain.remove(0))
+    }
+
+    async fn pck_crl(&self, ca: CA) -> Result<Vec<u8>> {
+        let url = format!(
+            "{}/sgx/certification/v4/pckcrl?ca={}&encoding=der",
+            self.base_url,
+            ca_name(ca)
+        );
+        let (body, _) = self.get(&url).await?;
+        to_der(&body)
+    }
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<Vec<u8>> {
+        let tee = if tcb_type == 1 { "tdx" } else { "sgx" };
+        let url = format!(
+            "{}/{}/certification/v{}/tcb?fmspc={}",
+            self.base_url,
+            tee,
+            Self::api_version_for_tcb_info(version),
+            fmspc
+        );
+        let (body, _) = self.get(&url).await?;
+        Ok(body)
+    }
+
+    async fn qe_identity(&self, id: EnclaveIdType, version: u32) -> Result<Vec<u8>> {
+        let (tee, enclave) = match id {
+            EnclaveIdType::QE => ("sgx", "qe"),
+            EnclaveIdType::QVE => ("sgx", "qve"),
+            EnclaveIdType::TDQE => ("tdx", "qe"),
+        };
+        let url = format!(
+            "{}/{}/certification/v{}/{}/identity",
+            self.base_url, tee, version, enclave
+        );
+        let (body, _) = self.get(&url).await?;
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+    use reqwest::{header::HeaderValue, StatusCode};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::parser::{get_pck_certification_data, PckCertificationData};
+    use crate::quote::Quote;
+
+    const QUOTE_HEX: &str = include_str!("../../../data/quote.hex");
+    // Not valid UTF-8, so that it can only be taken as DER
+    const CRL_DER: [u8; 6] = [0x30, 0x04, 0x02, 0x02, 0xff, 0xfe];
+
+    struct Route {
+        target: &'static str,
+        status: u16,
+        headers: Vec<(&'static str, String)>,
+        body: Vec<u8>,
+    }
+
+    impl Route {
+        fn new(target: &'static str, body: &[u8]) -> Self {
+            Route {
+                target,
+                status: 200,
+                headers: Vec::new(),
+                body: body.to_vec(),
+            }
+        }
+
+        fn status(mut self, status: u16) -> Self {
+            self.status = status;
+            self
+        }
+
+        fn header(mut self, name: &'static str, value: String) -> Self {
+            self.headers.push((name, value));
+            self
+        }
+    }
+
+    /// Serves the routes on a local port until the test ends, anything else is a 404,
+    /// and returns the base URL to reach them at
+    async fn serve(routes: Vec<Route>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    let read = stream.read(&mut buf).await.unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..read]);
+                }
+                let request = String::from_utf8_lossy(&request);
+                let target = request.split_whitespace().nth(1).unwrap_or_default();
+
+                let not_found = Route::new("", b"").status(404);
+                let route = routes
+                    .iter()
+                    .find(|route| route.target == target)
+                    .unwrap_or(&not_found);
+                let status = StatusCode::from_u16(route.status).unwrap();
+                let mut response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    status,
+                    route.body.len()
+                );
+                for (name, value) in &route.headers {
+                    response.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                response.push_str("\r\n");
+                let mut response = response.into_bytes();
+                response.extend_from_slice(&route.body);
+                stream.write_all(&response).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+        base_url
+    }
+
+    /// The PEM PCK certificate chain of the sample quote, which stands in for an issuer chain
+    fn pem_chain() -> String {
+        let quote = hex::decode(QUOTE_HEX.trim()).unwrap();
+        match get_pck_certification_data(&Quote::from_bytes(&quote).unwrap()) {
+            Ok(PckCertificationData::CertChain(chain)) => String::from_utf8(chain).unwrap(),
+            _ => panic!("The sample quote embeds its PCK chain"),
+        }
+    }
+
+    /// Encodes a PEM chain the way the PCS puts it in an issuer chain header
+    fn header_value(pem_chain: &str) -> String {
+        utf8_percent_encode(pem_chain, NON_ALPHANUMERIC).to_string()
+    }
+
+    #[test]
+    fn parses_issuer_chain_headers() {
+        let pem_chain = pem_chain();
+        let der_chain = pem_chain_to_der(pem_chain.as_bytes()).unwrap();
+
+        let mut headers = HeaderMap::new();
+        for name in [
+            "TCB-Info-Issuer-Chain",
+            ENCLAVE_IDENTITY_ISSUER_CHAIN_HEADER,
+        ] {
+            headers.insert(
+                name,
+                HeaderValue::from_str(&header_value(&pem_chain)).unwrap(),
+            );
+            assert_eq!(
+                PcsProvider::issuer_chain(&headers, name).unwrap(),
+                der_chain
+            );
+        }
+
+        let err = PcsProvider::issuer_chain(&headers, PCK_CRL_ISSUER_CHAIN_HEADER).unwrap_err();
+        assert_eq!(err.to_string(), "Missing SGX-PCK-CRL-Issuer-Chain header");
+
+        headers.insert("TCB-Info-Issuer-Chain", HeaderValue::from_static(""));
+        let err = PcsProvider::issuer_chain(&headers, "TCB-Info-Issuer-Chain").unwrap_err();
+        assert_eq!(err.to_string(), "Empty TCB-Info-Issuer-Chain header");
+    }
+
+    #[tokio::test]
+    async fn reads_the_signing_ca_from_the_enclave_identity_issuer_chain() {
+        let pem_chain = pem_chain();
+        let base_url = serve(vec![Route::new("/sgx/certification/v4/qe/identity", b"{}")
+            .header(
+                ENCLAVE_IDENTITY_ISSUER_CHAIN_HEADER,
+                header_value(&pem_chain),
+            )])
+        .await;
+
+        let signing_ca = PcsProvider::new(&base_url).signing_ca().await.unwrap();
+        assert_eq!(
+            signing_ca,
+            pem_chain_to_der(pem_chain.as_bytes()).unwrap()[0]
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_the_root_ca_crl_as_hex_or_der() {
+        let pem_chain = pem_chain();
+        let root_ca = pem_chain_to_der(pem_chain.as_bytes())
+            .unwrap()
+            .pop()
+            .unwrap();
+
+        // A PCCS answers with hex, a DER answer is taken as is
+        for body in [hex::encode(CRL_DER).into_bytes(), CRL_DER.to_vec()] {
+            let base_url = serve(vec![
+                Route::new(
+                    "/sgx/certification/v4/pckcrl?ca=platform&encoding=der",
+                    &CRL_DER,
+                )
+                .header(PCK_CRL_ISSUER_CHAIN_HEADER, header_value(&pem_chain)),
+                Route::new("/sgx/certification/v4/rootcacrl", &body),
+            ])
+            .await;
+
+            let (fetched_root_ca, root_ca_crl) =
+                PcsProvider::new(&base_url).root_ca().await.unwrap();
+            assert_eq!(fetched_root_ca, root_ca);
+            assert_eq!(root_ca_crl, CRL_DER);
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_on_unsuccessful_responses() {
+        let base_url = serve(vec![
+            Route::new(
+                "/sgx/certification/v4/pckcrl?ca=processor&encoding=der",
+                &CRL_DER,
+            ),
+            Route::new("/sgx/certification/v4/tcb?fmspc=00906ED50000", b"").status(500),
+        ])
+        .await;
+        let provider = PcsProvider::new(&base_url);
+
+        assert_eq!(provider.pck_crl(CA::PROCESSOR).await.unwrap(), CRL_DER);
+
+        let err = provider.tcb_info(0, "00906ED50000", 3).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{}/sgx/certification/v4/tcb?fmspc=00906ED50000 responded with 500 Internal Server Error",
+                base_url
+            )
+        );
+
+        // Not served, e.g. a PCCS without QvE identity
+        let err = provider
+            .qe_identity(EnclaveIdType::QVE, 2)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{}/sgx/certification/v2/qve/identity responded with 404 Not Found",
+                base_url
+            )
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use alloy::eips::BlockId;
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::{ca_name, fetch_each, CollateralProvider, CollateralRequest};
+use crate::chain::pccs::{
+    enclave_id::{get_enclave_identity, EnclaveIdType},
+    fmspc_tcb::get_tcb_info,
+    multicall::{self, is_multicall3_deployed},
+    pcs::{get_certificate_by_id, IPCSDao::CA},
+    PccsClient,
+};
+use crate::collaterals::Collaterals;
+use crate::config::ChainConfig;
+
+/// Reads collaterals from the on-chain PCCS DAOs over a single shared connection.
+///
+/// With `multicall` set, all reads for a quote are batched into one Multicall3 call so they
+/// come from the same block, unless the chain has no Multicall3 deployment.
+/// All reads are issued at `block`.
+pub struct OnChainProvider {
+    rpc_url: String,
+    client: PccsClient,
+    multicall: bool,
+}
+
+impl OnChainProvider {
+    pub fn new(config: &ChainConfig, multicall: bool, block: BlockId) -> Result<Self> {
+        Ok(OnChainProvider {
+            rpc_url: config.rpc_url.clone(),
+            client: PccsClient::new(config, block)?,
+            multicall,
+        })
+    }
+}
+
+#[async_trait]
+impl CollateralProvider for OnChainProvider {
+    fn name(&self) -> String {
+        format!("on-chain PCCS ({})", self.rpc_url)
+    }
+
+    async fn root_ca(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+        let (root_ca, root_ca_crl) = get_certificate_by_id(&self.client, CA::ROOT).await?;
+        check_root_ca(root_ca, root_ca_crl)
+    }
+
+    async fn signing_ca(&self) -> Result<Vec<u8>> {
+        let (signing_ca, _) = get_certificate_by_id(&self.client, CA::SIGNING).await?;
+        check_signing_ca(signing_ca)
+    }
+
+    async fn pck_crl(&self, ca: CA) -> Result<Vec<u8>> {
+        let (_, crl) = get_certificate_by_id(&self.client, ca).await?;
+        check_pck_crl(ca, crl)
+    }
+
+    async fn tcb_info(&self, tcb_type: u8, fmspc: &str, version: u32) -> Result<Vec<u8>> {
+        get_tcb_info(&self.client, tcb_type, fmspc, version).await
+    }
+
+    async fn qe_identity(&self, id: EnclaveIdType, version: u32) -> Result<Vec<u8>> {
+        get_enclave_identity(&self.client, id, version).await
+    }
+
+    async fn collaterals(&self, request: &CollateralRequest) -> Result<Collaterals> {
+        if !self.multicall {
+            return fetch_each(self, request).await;
+        }
+        if !is_multicall3_deployed(&self.client).await? {
+            tracing::warn!(
+                "No Multicall3 deployment at {}, reading collaterals individually",
+                self.client.multicall3
+            );
+            return fetch_each(self, request).await;
+        }
+
+        let reads = multicall::get_collaterals(
+            &self.client,
+            request.pck_ca,
+            request.tcb_type,
+            &request.fmspc,
+            request.tcb_version,
+            request.qe_id_type,
+            request.qe_id_version,
+        )
+        .await?;
+        tracing::info!("Read all collaterals with a single Multicall3 call");
+
+        let (root_ca, root_ca_crl) = check_root_ca(reads.root_ca.0, reads.root_ca.1)?;
+        Ok(Collaterals::new(
+            reads.tcb_info,
+            reads.qe_identity,
+            root_ca,
+            check_signing_ca(reads.signing_ca)?,
+            root_ca_crl,
+            check_pck_crl(request.pck_ca, reads.pck_crl)?,
+        ))
+    }
+}
+
+fn check_root_ca(root_ca: Vec<u8>, root_ca_crl: Vec<u8>) -> Result<(Vec<u8>, Vec<u8>)> {
+    if root_ca.is_empty() || root_ca_crl.is_empty() {
+        return Err(anyhow::Error::msg("Intel SGX Root CA is missing"));
+    }
+    Ok((root_ca, root_ca_crl))
+}
+
+fn check_signing_ca(signing_ca: Vec<u8>) -> Result<Vec<u8>> {
+    if signing_ca.is_empty() {
+        return Err(anyhow::Error::msg("Intel TCB Signing CA is missing"));
+    }
+    Ok(signing_ca)
+}
+
+fn check_pck_crl(ca: CA, crl: Vec<u8>) -> Result<Vec<u8>> {
+    if crl.is_empty() {
+        return Err(anyhow::Error::msg(format!(
+            "CRL for the {} PCK CA is missing",
+            ca_name(ca)
+        )));
+    }
+    Ok(crl)
+}
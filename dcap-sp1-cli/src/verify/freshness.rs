@@ -0,0 +1,214 @@
+use anyhow::Result;
+use std::fmt;
+
+use crate::collaterals::Collaterals;
+use crate::format_timestamp;
+use crate::provider::cache::{crl_next_update, crl_this_update, json_issue_date, json_next_update};
+use crate::provider::dir::{ENCLAVE_IDENTITY_KEY, TCB_INFO_KEY};
+
+/// The period a collateral is valid for, from its issue date to its `nextUpdate`.
+#[derive(Debug, Clone)]
+pub struct CollateralValidity {
+    pub collateral: &'static str,
+    pub issued: i64,
+    pub next_update: i64,
+}
+
+impl CollateralValidity {
+    /// Whether the collateral is not yet issued or already past its `nextUpdate` at `timestamp`
+    pub fn is_stale(&self, timestamp: i64) -> bool {
+        timestamp < self.issued || timestamp > self.next_update
+    }
+}
+
+/// Reads the validity periods of the TCBInfo and QEIdentity (`issueDate` to `nextUpdate`)
+/// and of the Root CA and PCK CRLs (`thisUpdate` to `nextUpdate`).
+pub fn collateral_validity(collaterals: &Collaterals) -> Result<Vec<CollateralValidity>> {
+    Ok(vec![
+        CollateralValidity {
+            collateral: "TCBInfo",
+            issued: json_issue_date(&collaterals.tcb_info, TCB_INFO_KEY)?,
+            next_update: json_next_update(&collaterals.tcb_info, TCB_INFO_KEY)?,
+        },
+        CollateralValidity {
+            collateral: "QEIdentity",
+            issued: json_issue_date(&collaterals.qe_identity, ENCLAVE_IDENTITY_KEY)?,
+            next_update: json_next_update(&collaterals.qe_identity, ENCLAVE_IDENTITY_KEY)?,
+        },
+        CollateralValidity {
+            collateral: "Root CA CRL",
+            issued: crl_this_update(&collaterals.root_ca_crl)?,
+            next_update: crl_next_update(&collaterals.root_ca_crl)?,
+        },
+        CollateralValidity {
+            collateral: "PCK CRL",
+            issued: crl_this_update(&collaterals.pck_crl)?,
+            next_update: crl_next_update(&collaterals.pck_crl)?,
+        },
+    ])
+}
+
+/// Returns the collaterals the guest would find stale at `timestamp`
+pub fn stale_collaterals(
+    collaterals: &Collaterals,
+    timestamp: i64,
+) -> Result<Vec<CollateralValidity>> {
+    Ok(collateral_validity(collaterals)?
+        .into_iter()
+        .filter(|validity| validity.is_stale(timestamp))
+        .collect())
+}
+
+/// Fails, or only warns with `allow_stale`, if a collateral is stale at `current_time`
+pub fn check_freshness(
+    collaterals: &Collaterals,
+    current_time: u64,
+    allow_stale: bool,
+) -> Result<()> {
+    let stale = stale_collaterals(collaterals, current_time as i64)?;
+    if stale.is_empty() {
+        return Ok(());
+    }
+
+    let details = stale
+        .iter()
+        .map(|validity| validity.to_string())
+        .collect::<Vec<_>>()
+        .join("; ");
+    if allow_stale {
+        tracing::warn!(
+            "Stale collaterals at {}: {}",
+            format_timestamp(current_time as i64),
+            details
+        );
+        Ok(())
+    } else {
+        Err(anyhow::Error::msg(format!(
+            "Stale collaterals at {}: {}. The guest would reject them, pass --allow-stale to prove anyway",
+            format_timestamp(current_time as i64),
+            details
+        )))
+    }
+}
+
+impl fmt::Display for CollateralValidity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is valid from {} to {}",
+            self.collateral,
+            format_timestamp(self.issued),
+            format_timestamp(self.next_update)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pem_chain_to_der;
+
+    const TCB_INFO_V3_TDX: &str = include_str!("../../../data/collaterals/tcb_info_v3_tdx.json");
+    const TD_QE_IDENTITY_V2: &str =
+        include_str!("../../../data/collaterals/td_qe_identity_v2.json");
+    // Valid from 2024-01-01 to 2049-12-31
+    const TEST_ROOT_CA_CRL: &str = include_str!("../../../data/collaterals/test_root_ca_crl.pem");
+
+    // The TCBInfo is valid from 2024-09-05T09:47:21Z to 2024-10-05T09:47:21Z,
+    // the QEIdentity from 2024-09-05T09:29:55Z to 2024-10-05T09:29:55Z
+    const QE_IDENTITY_NEXT_UPDATE: i64 = 1728120595;
+    const BEFORE_TCB_INFO_ISSUE_DATE: i64 = 1725529200;
+    const WITHIN_BOTH: i64 = 1726790400;
+
+    fn collaterals() -> Collaterals {
+        let crl = pem_chain_to_der(TEST_ROOT_CA_CRL.as_bytes())
+            .unwrap()
+            .remove(0);
+        Collaterals::new(
+            TCB_INFO_V3_TDX.as_bytes().to_vec(),
+            TD_QE_IDENTITY_V2.as_bytes().to_vec(),
+            Vec::new(),
+            Vec::new(),
+            crl.clone(),
+            crl,
+        )
+    }
+
+    fn stale_names(timestamp: i64) -> Vec<&'static str> {
+        stale_collaterals(&collaterals(), timestamp)
+            .unwrap()
+            .iter()
+            .map(|validity| validity.collateral)
+            .collect()
+    }
+
+    #[test]
+    fn is_stale_outside_issue_date_to_next_update() {
+        let validity = CollateralValidity {
+            collateral: "TCBInfo",
+            issued: 1000,
+            next_update: 2000,
+        };
+        assert!(validity.is_stale(999));
+        assert!(!validity.is_stale(1000));
+        // A collateral is still valid at its nextUpdate
+        assert!(!validity.is_stale(2000));
+        assert!(validity.is_stale(2001));
+    }
+
+    #[test]
+    fn reads_collateral_validity() {
+        let validity = collateral_validity(&collaterals()).unwrap();
+        let periods: Vec<_> = validity
+            .iter()
+            .map(|validity| {
+                (
+                    validity.collateral,
+                    format_timestamp(validity.issued),
+                    format_timestamp(validity.next_update),
+                )
+            })
+            .collect();
+        assert_eq!(
+            periods[..2],
+            [
+                (
+                    "TCBInfo",
+                    "2024-09-05T09:47:21+00:00".to_string(),
+                    "2024-10-05T09:47:21+00:00".to_string()
+                ),
+                (
+                    "QEIdentity",
+                    "2024-09-05T09:29:55+00:00".to_string(),
+                    "2024-10-05T09:29:55+00:00".to_string()
+                ),
+            ]
+        );
+        assert_eq!(periods[2].0, "Root CA CRL");
+        assert_eq!(periods[3].0, "PCK CRL");
+        assert_eq!(periods[3].1, "2024-01-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn finds_stale_collaterals() {
+        assert!(stale_names(WITHIN_BOTH).is_empty());
+        assert_eq!(stale_names(BEFORE_TCB_INFO_ISSUE_DATE), ["TCBInfo"]);
+        assert!(stale_names(QE_IDENTITY_NEXT_UPDATE).is_empty());
+        assert_eq!(stale_names(QE_IDENTITY_NEXT_UPDATE + 1), ["QEIdentity"]);
+    }
+
+    #[test]
+    fn allow_stale_only_warns() {
+        let stale_at = (QE_IDENTITY_NEXT_UPDATE + 1) as u64;
+        check_freshness(&collaterals(), WITHIN_BOTH as u64, false).unwrap();
+        check_freshness(&collaterals(), stale_at, true).unwrap();
+
+        let err = check_freshness(&collaterals(), stale_at, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Stale collaterals at 2024-10-05T09:29:56+00:00: QEIdentity is valid from \
+             2024-09-05T09:29:55+00:00 to 2024-10-05T09:29:55+00:00. The guest would reject them, \
+             pass --allow-stale to prove anyway"
+        );
+    }
+}
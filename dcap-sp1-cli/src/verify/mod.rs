@@ -0,0 +1,9 @@
+pub mod freshness;
+pub mod native;
+pub mod pck;
+pub mod qe;
+pub mod report;
+pub mod root;
+pub mod signature;
+pub mod tcb;
+pub mod tdx;
@@ -0,0 +1,250 @@
+use thiserror::Error;
+use x509_parser::prelude::*;
+
+use crate::collaterals::Collaterals;
+use crate::format_timestamp;
+use crate::parser::{parse_certchain, parse_pem};
+
+/// Why a PCK certificate chain was rejected.
+#[derive(Debug, Error)]
+pub enum PckChainError {
+    #[error("Invalid PEM in the PCK certificate chain: {0}")]
+    InvalidPem(String),
+
+    #[error("Invalid certificate in the PCK certificate chain: {0}")]
+    InvalidCertificate(String),
+
+    #[error("The PCK certificate chain has {0} certificates, expected 3")]
+    InvalidLength(usize),
+
+    #[error(
+        "The root of the PCK certificate chain is not the Intel SGX Root CA of the collaterals"
+    )]
+    RootMismatch,
+
+    #[error("\"{subject}\" is not valid at {time}: it is valid from {not_before} to {not_after}")]
+    OutsideValidity {
+        subject: String,
+        time: String,
+        not_before: String,
+        not_after: String,
+    },
+
+    #[error("The signature of \"{subject}\" does not verify against \"{issuer}\": {reason}")]
+    InvalidSignature {
+        subject: String,
+        issuer: String,
+        reason: String,
+    },
+
+    #[error("Invalid {crl}: {reason}")]
+    InvalidCrl { crl: &'static str, reason: String },
+
+    #[error("The signature of the {crl} does not verify against \"{issuer}\": {reason}")]
+    InvalidCrlSignature {
+        crl: &'static str,
+        issuer: String,
+        reason: String,
+    },
+
+    #[error("The {crl} is issued by \"{found}\", expected \"{expected}\"")]
+    CrlIssuerMismatch {
+        crl: &'static str,
+        found: String,
+        expected: String,
+    },
+
+    #[error("\"{subject}\" (serial {serial}) is revoked by the {crl}")]
+    Revoked {
+        subject: String,
+        serial: String,
+        crl: &'static str,
+    },
+}
+
+/// Checks the PEM PCK certificate chain of a quote (PCK certificate, PCK CA, Root CA)
+/// against the fetched collaterals, the same way the verifier in the zkVM will:
+/// every signature, every validity period at `now`, that the root is the Intel SGX Root CA
+/// of the collaterals, that the PCK certificate is not revoked by the PCK CRL and that
+/// the PCK CA is not revoked by the Root CA CRL.
+pub fn verify_pck_chain(
+    pck_cert_chain: &[u8],
+    collaterals: &Collaterals,
+    now: i64,
+) -> Result<(), PckChainError> {
+    let pem = parse_pem(pck_cert_chain).map_err(|e| PckChainError::InvalidPem(e.to_string()))?;
+    let chain =
+        parse_certchain(&pem).map_err(|e| PckChainError::InvalidCertificate(e.to_string()))?;
+    let [pck, pck_ca, root_ca] = chain.as_slice() else {
+        return Err(PckChainError::InvalidLength(chain.len()));
+    };
+
+    if pem[2].contents != collaterals.root_ca {
+        return Err(PckChainError::RootMismatch);
+    }
+
+    let time = ASN1Time::from_timestamp(now)
+        .map_err(|e| PckChainError::InvalidCertificate(e.to_string()))?;
+    for cert in [pck, pck_ca, root_ca] {
+        check_validity(cert, time)?;
+    }
+
+    check_signature(pck, pck_ca)?;
+    check_signature(pck_ca, root_ca)?;
+    check_signature(root_ca, root_ca)?;
+
+    check_not_revoked(pck, pck_ca, &collaterals.pck_crl, "PCK CRL")?;
+    check_not_revoked(pck_ca, root_ca, &collaterals.root_ca_crl, "Root CA CRL")?;
+
+    Ok(())
+}
+
+fn check_validity(cert: &X509Certificate, time: ASN1Time) -> Result<(), PckChainError> {
+    let validity = cert.validity();
+    if validity.is_valid_at(time) {
+        return Ok(());
+    }
+    Err(PckChainError::OutsideValidity {
+        subject: cert.subject().to_string(),
+        time: format_timestamp(time.timestamp()),
+        not_before: format_timestamp(validity.not_before.timestamp()),
+        not_after: format_timestamp(validity.not_after.timestamp()),
+    })
+}
+
+fn check_signature(cert: &X509Certificate, issuer: &X509Certificate) -> Result<(), PckChainError> {
+    cert.verify_signature(Some(issuer.public_key()))
+        .map_err(|e| PckChainError::InvalidSignature {
+            subject: cert.subject().to_string(),
+            issuer: issuer.subject().to_string(),
+            reason: e.to_string(),
+        })
+}
+
+/// Checks that `crl` is issued and signed by `issuer` and does not list `cert`
+fn check_not_revoked(
+    cert: &X509Certificate,
+    issuer: &X509Certificate,
+    crl: &[u8],
+    crl_name: &'static str,
+) -> Result<(), PckChainError> {
+    let (_, crl) = parse_x509_crl(crl).map_err(|e| PckChainError::InvalidCrl {
+        crl: crl_name,
+        reason: e.to_string(),
+    })?;
+
+    if crl.issuer() != issuer.subject() {
+        return Err(PckChainError::CrlIssuerMismatch {
+            crl: crl_name,
+            found: crl.issuer().to_string(),
+            expected: issuer.subject().to_string(),
+        });
+    }
+    crl.verify_signature(issuer.public_key())
+        .map_err(|e| PckChainError::InvalidCrlSignature {
+            crl: crl_name,
+            issuer: issuer.subject().to_string(),
+            reason: e.to_string(),
+        })?;
+
+    let revoked = crl
+        .iter_revoked_certificates()
+        .any(|revoked| revoked.serial() == &cert.serial);
+    if revoked {
+        return Err(PckChainError::Revoked {
+            subject: cert.subject().to_string(),
+            serial: cert.raw_serial_as_string(),
+            crl: crl_name,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{get_pck_certification_data, PckCertificationData};
+    use crate::pem_chain_to_der;
+    use crate::quote::Quote;
+
+    const QUOTE_HEX: &str = include_str!("../../../data/quote.hex");
+    const TEST_ROOT_CA: &str = include_str!("../../../data/collaterals/test_root_ca.pem");
+    const TEST_ROOT_CA_CRL: &str = include_str!("../../../data/collaterals/test_root_ca_crl.pem");
+
+    /// The PEM PCK certificate chain of the sample quote
+    fn pck_cert_chain() -> Vec<u8> {
+        let quote = Quote::from_bytes(&hex::decode(QUOTE_HEX.trim()).unwrap()).unwrap();
+        match get_pck_certification_data(&quote).unwrap() {
+            PckCertificationData::CertChain(chain) => chain,
+            PckCertificationData::Identifier(_) => panic!("The sample quote embeds its PCK chain"),
+        }
+    }
+
+    /// Collaterals with the root of the chain, and CRLs of the test Root CA
+    fn collaterals(chain: &[u8]) -> Collaterals {
+        let crl = pem_chain_to_der(TEST_ROOT_CA_CRL.as_bytes())
+            .unwrap()
+            .remove(0);
+        Collaterals::new(
+            Vec::new(),
+            Vec::new(),
+            pem_chain_to_der(chain).unwrap().remove(2),
+            Vec::new(),
+            crl.clone(),
+            crl,
+        )
+    }
+
+    /// The notBefore and notAfter of the PCK certificate
+    fn pck_validity(chain: &[u8]) -> (i64, i64) {
+        let der = pem_chain_to_der(chain).unwrap().remove(0);
+        let (_, pck) = parse_x509_certificate(&der).unwrap();
+        (
+            pck.validity().not_before.timestamp(),
+            pck.validity().not_after.timestamp(),
+        )
+    }
+
+    #[test]
+    fn rejects_expired_pck_certificate() {
+        let chain = pck_cert_chain();
+        let (_, not_after) = pck_validity(&chain);
+
+        let err = verify_pck_chain(&chain, &collaterals(&chain), not_after + 1).unwrap_err();
+        let PckChainError::OutsideValidity { subject, .. } = err else {
+            panic!("Expected OutsideValidity, got {:?}", err);
+        };
+        assert!(subject.contains("Intel SGX PCK Certificate"));
+    }
+
+    #[test]
+    fn rejects_chain_of_another_root() {
+        let chain = pck_cert_chain();
+        let (not_before, _) = pck_validity(&chain);
+        let mut collaterals = collaterals(&chain);
+        collaterals.root_ca = pem_chain_to_der(TEST_ROOT_CA.as_bytes()).unwrap().remove(0);
+
+        let err = verify_pck_chain(&chain, &collaterals, not_before + 1).unwrap_err();
+        assert!(matches!(err, PckChainError::RootMismatch));
+    }
+
+    #[test]
+    fn rejects_crl_of_another_issuer() {
+        let chain = pck_cert_chain();
+        let (not_before, _) = pck_validity(&chain);
+
+        // Every signature and validity period checks out, but the PCK CRL is not the Platform CA's
+        let err = verify_pck_chain(&chain, &collaterals(&chain), not_before + 1).unwrap_err();
+        let PckChainError::CrlIssuerMismatch {
+            crl,
+            found,
+            expected,
+        } = err
+        else {
+            panic!("Expected CrlIssuerMismatch, got {:?}", err);
+        };
+        assert_eq!(crl, "PCK CRL");
+        assert!(found.contains("Test SGX Root CA"));
+        assert!(expected.contains("Intel SGX PCK Platform CA"));
+    }
+}
@@ -0,0 +1,126 @@
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use x509_parser::prelude::*;
+
+use crate::constants::{INTEL_ROOT_CA_PUBLIC_KEY, INTEL_ROOT_CA_SHA256};
+
+/// Why the Root CA of the collaterals was not trusted.
+#[derive(Debug, Error)]
+pub enum RootCaError {
+    #[error("Invalid {certificate}: {reason}")]
+    InvalidCertificate {
+        certificate: &'static str,
+        reason: String,
+    },
+
+    #[error("The Root CA of the collaterals has public key {found}, not the one of the Intel SGX Root CA ({expected}). Pass --trusted-root to trust another root")]
+    UntrustedKey { found: String, expected: String },
+
+    #[error("The Root CA of the collaterals has SHA-256 fingerprint {found}, not the one of the {trusted} ({expected})")]
+    UntrustedCertificate {
+        trusted: &'static str,
+        found: String,
+        expected: String,
+    },
+}
+
+/// Checks the DER Root CA of the collaterals against the root of trust: the pinned
+/// Intel SGX Root CA, by public key and certificate fingerprint, or else the DER
+/// `trusted_root` certificate, by fingerprint.
+pub fn check_root_ca(root_ca: &[u8], trusted_root: Option<&[u8]>) -> Result<(), RootCaError> {
+    let fingerprint = hex::encode(Sha256::digest(root_ca));
+
+    if let Some(trusted_root) = trusted_root {
+        let expected = hex::encode(Sha256::digest(trusted_root));
+        if fingerprint != expected {
+            return Err(RootCaError::UntrustedCertificate {
+                trusted: "trusted root",
+                found: fingerprint,
+                expected,
+            });
+        }
+        return Ok(());
+    }
+
+    let (_, cert) =
+        parse_x509_certificate(root_ca).map_err(|e| RootCaError::InvalidCertificate {
+            certificate: "Root CA of the collaterals",
+            reason: e.to_string(),
+        })?;
+    let public_key = hex::encode(cert.public_key().subject_public_key.data.as_ref());
+    if public_key != INTEL_ROOT_CA_PUBLIC_KEY {
+        return Err(RootCaError::UntrustedKey {
+            found: public_key,
+            expected: INTEL_ROOT_CA_PUBLIC_KEY.to_string(),
+        });
+    }
+    if fingerprint != INTEL_ROOT_CA_SHA256 {
+        return Err(RootCaError::UntrustedCertificate {
+            trusted: "Intel SGX Root CA",
+            found: fingerprint,
+            expected: INTEL_ROOT_CA_SHA256.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{get_pck_certification_data, PckCertificationData};
+    use crate::pem_chain_to_der;
+    use crate::quote::Quote;
+
+    const QUOTE_HEX: &str = include_str!("../../../data/quote.hex");
+    const TEST_ROOT_CA: &str = include_str!("../../../data/collaterals/test_root_ca.pem");
+
+    /// The Intel SGX Root CA at the end of the sample quote's PCK chain
+    fn intel_root_ca() -> Vec<u8> {
+        let quote = Quote::from_bytes(&hex::decode(QUOTE_HEX.trim()).unwrap()).unwrap();
+        let PckCertificationData::CertChain(chain) = get_pck_certification_data(&quote).unwrap()
+        else {
+            panic!("The sample quote embeds its PCK chain");
+        };
+        pem_chain_to_der(&chain).unwrap().remove(2)
+    }
+
+    fn test_root_ca() -> Vec<u8> {
+        pem_chain_to_der(TEST_ROOT_CA.as_bytes()).unwrap().remove(0)
+    }
+
+    #[test]
+    fn pins_intel_root_ca() {
+        let root_ca = intel_root_ca();
+        assert_eq!(hex::encode(Sha256::digest(&root_ca)), INTEL_ROOT_CA_SHA256);
+        let (_, cert) = parse_x509_certificate(&root_ca).unwrap();
+        assert_eq!(
+            hex::encode(cert.public_key().subject_public_key.data.as_ref()),
+            INTEL_ROOT_CA_PUBLIC_KEY
+        );
+
+        check_root_ca(&root_ca, None).unwrap();
+    }
+
+    #[test]
+    fn rejects_foreign_root_ca() {
+        let err = check_root_ca(&test_root_ca(), None).unwrap_err();
+        assert!(matches!(err, RootCaError::UntrustedKey { .. }));
+    }
+
+    #[test]
+    fn accepts_trusted_root() {
+        let root_ca = test_root_ca();
+        check_root_ca(&root_ca, Some(&root_ca)).unwrap();
+
+        // The trusted root replaces the pinned one
+        let err = check_root_ca(&intel_root_ca(), Some(&root_ca)).unwrap_err();
+        assert!(matches!(
+            err,
+            RootCaError::UntrustedCertificate {
+                trusted: "trusted root",
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,188 @@
+use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use thiserror::Error;
+use x509_parser::prelude::*;
+
+use crate::collaterals::Collaterals;
+use crate::signed::{EnclaveIdentity, Signed, SignedBody, SignedCollateralError, TcbInfo};
+
+/// Why a TCBInfo or QEIdentity signature was rejected.
+#[derive(Debug, Error)]
+pub enum CollateralSignatureError {
+    #[error("Invalid {certificate}: {reason}")]
+    InvalidCertificate {
+        certificate: &'static str,
+        reason: String,
+    },
+
+    #[error("The Intel TCB Signing CA is not issued by the Intel SGX Root CA: {0}")]
+    UntrustedSigningCa(String),
+
+    #[error(transparent)]
+    InvalidCollateral(#[from] SignedCollateralError),
+
+    #[error("The {collateral} signature does not verify against the Intel TCB Signing CA")]
+    InvalidSignature { collateral: &'static str },
+}
+
+/// Checks that the TCBInfo and QEIdentity are signed by the Intel TCB Signing CA of the
+/// collaterals, and that the Signing CA is issued by the Intel SGX Root CA.
+///
+/// Each signature is a raw P-256 ECDSA signature (r || s) over the exact bytes of the
+/// `tcbInfo` or `enclaveIdentity` body.
+pub fn verify_collateral_signatures(
+    collaterals: &Collaterals,
+) -> Result<(), CollateralSignatureError> {
+    let (_, root_ca) = parse_x509_certificate(&collaterals.root_ca).map_err(|e| {
+        CollateralSignatureError::InvalidCertificate {
+            certificate: "Intel SGX Root CA",
+            reason: e.to_string(),
+        }
+    })?;
+    let (_, signing_ca) = parse_x509_certificate(&collaterals.tcb_signing_ca).map_err(|e| {
+        CollateralSignatureError::InvalidCertificate {
+            certificate: "Intel TCB Signing CA",
+            reason: e.to_string(),
+        }
+    })?;
+    signing_ca
+        .verify_signature(Some(root_ca.public_key()))
+        .map_err(|e| CollateralSignatureError::UntrustedSigningCa(e.to_string()))?;
+
+    let key = VerifyingKey::from_sec1_bytes(&signing_ca.public_key().subject_public_key.data)
+        .map_err(|e| CollateralSignatureError::InvalidCertificate {
+            certificate: "Intel TCB Signing CA",
+            reason: e.to_string(),
+        })?;
+    verify_signed(&key, &Signed::<TcbInfo>::from_json(&collaterals.tcb_info)?)?;
+    verify_signed(
+        &key,
+        &Signed::<EnclaveIdentity>::from_json(&collaterals.qe_identity)?,
+    )?;
+
+    Ok(())
+}
+
+fn verify_signed<T: SignedBody>(
+    key: &VerifyingKey,
+    signed: &Signed<T>,
+) -> Result<(), CollateralSignatureError> {
+    let invalid_signature = || CollateralSignatureError::InvalidSignature {
+        collateral: T::NAME,
+    };
+    let signature = Signature::from_slice(&signed.signature).map_err(|_| invalid_signature())?;
+    key.verify(signed.raw_body.get().as_bytes(), &signature)
+        .map_err(|_| invalid_signature())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{get_pck_certification_data, PckCertificationData};
+    use crate::pem_chain_to_der;
+    use crate::quote::Quote;
+
+    // The collateral fixtures are signed by a test Signing CA, issued by a test Root CA
+    const TEST_ROOT_CA: &str = include_str!("../../../data/collaterals/test_root_ca.pem");
+    const TEST_SIGNING_CA: &str = include_str!("../../../data/collaterals/test_tcb_signing_ca.pem");
+    const TCB_INFO_V3_TDX: &str = include_str!("../../../data/collaterals/tcb_info_v3_tdx.json");
+    const TD_QE_IDENTITY_V2: &str =
+        include_str!("../../../data/collaterals/td_qe_identity_v2.json");
+    const QUOTE_HEX: &str = include_str!("../../../data/quote.hex");
+
+    fn der(pem: &str) -> Vec<u8> {
+        pem_chain_to_der(pem.as_bytes()).unwrap().remove(0)
+    }
+
+    fn collaterals(tcb_info: &str, qe_identity: &str) -> Collaterals {
+        Collaterals::new(
+            tcb_info.as_bytes().to_vec(),
+            qe_identity.as_bytes().to_vec(),
+            der(TEST_ROOT_CA),
+            der(TEST_SIGNING_CA),
+            Vec::new(),
+            Vec::new(),
+        )
+    }
+
+    /// Flips the last hex digit of the signature
+    fn tamper_signature(json: &str) -> String {
+        let end = json.rfind("\"}").unwrap();
+        let flipped = if &json[end - 1..end] == "0" { "1" } else { "0" };
+        format!("{}{}{}", &json[..end - 1], flipped, &json[end..])
+    }
+
+    #[test]
+    fn verifies_signed_collaterals() {
+        verify_collateral_signatures(&collaterals(TCB_INFO_V3_TDX, TD_QE_IDENTITY_V2)).unwrap();
+    }
+
+    #[test]
+    fn rejects_tampered_bodies() {
+        let tcb_info = TCB_INFO_V3_TDX.replacen(
+            "\"fmspc\":\"90C06F000000\"",
+            "\"fmspc\":\"90C06F000001\"",
+            1,
+        );
+        assert_ne!(tcb_info, TCB_INFO_V3_TDX);
+        let err =
+            verify_collateral_signatures(&collaterals(&tcb_info, TD_QE_IDENTITY_V2)).unwrap_err();
+        assert!(matches!(
+            err,
+            CollateralSignatureError::InvalidSignature {
+                collateral: "TCBInfo"
+            }
+        ));
+
+        let qe_identity = TD_QE_IDENTITY_V2.replacen("\"isvprodid\":2", "\"isvprodid\":3", 1);
+        assert_ne!(qe_identity, TD_QE_IDENTITY_V2);
+        let err =
+            verify_collateral_signatures(&collaterals(TCB_INFO_V3_TDX, &qe_identity)).unwrap_err();
+        assert!(matches!(
+            err,
+            CollateralSignatureError::InvalidSignature {
+                collateral: "enclave identity"
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_tampered_signatures() {
+        let tcb_info = tamper_signature(TCB_INFO_V3_TDX);
+        let err =
+            verify_collateral_signatures(&collaterals(&tcb_info, TD_QE_IDENTITY_V2)).unwrap_err();
+        assert!(matches!(
+            err,
+            CollateralSignatureError::InvalidSignature {
+                collateral: "TCBInfo"
+            }
+        ));
+
+        let qe_identity = tamper_signature(TD_QE_IDENTITY_V2);
+        let err =
+            verify_collateral_signatures(&collaterals(TCB_INFO_V3_TDX, &qe_identity)).unwrap_err();
+        assert!(matches!(
+            err,
+            CollateralSignatureError::InvalidSignature {
+                collateral: "enclave identity"
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_signing_ca_of_another_root() {
+        let quote = Quote::from_bytes(&hex::decode(QUOTE_HEX.trim()).unwrap()).unwrap();
+        let PckCertificationData::CertChain(chain) = get_pck_certification_data(&quote).unwrap()
+        else {
+            panic!("The sample quote embeds its PCK chain");
+        };
+        let mut collaterals = collaterals(TCB_INFO_V3_TDX, TD_QE_IDENTITY_V2);
+        // The Intel SGX Root CA did not issue the test Signing CA
+        collaterals.root_ca = pem_chain_to_der(&chain).unwrap().remove(2);
+
+        let err = verify_collateral_signatures(&collaterals).unwrap_err();
+        assert!(matches!(
+            err,
+            CollateralSignatureError::UntrustedSigningCa(_)
+        ));
+    }
+}
@@ -0,0 +1,339 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt;
+
+use crate::parser::PckExtensions;
+use crate::quote::field;
+use crate::signed::{Tcb, TcbInfo, TcbStatus};
+
+/// Where a platform stands against the TCB levels of its TCBInfo.
+#[derive(Debug, Clone, Serialize)]
+pub struct TcbEvaluation {
+    pub fmspc: String,
+    pub tcb_info_version: u32,
+    pub tcb_evaluation_data_number: Option<u32>,
+    /// The first TCB level the platform meets, none if it is below all of them
+    pub level: Option<TcbLevelMatch>,
+    /// What the platform lacks to reach the TCB level just above the matched one
+    pub next_level: Option<TcbUpgrade>,
+    /// What the platform lacks to reach the highest UpToDate TCB level, unless it is already UpToDate
+    pub up_to_date_level: Option<TcbUpgrade>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TcbLevelMatch {
+    /// Position of the level in the TCBInfo, 0 being the highest
+    pub index: usize,
+    pub tcb_status: TcbStatus,
+    pub tcb_date: String,
+    pub advisory_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TcbUpgrade {
+    pub index: usize,
+    pub tcb_status: TcbStatus,
+    pub tcb_date: String,
+    /// The components whose SVN is below the one the level requires
+    pub components: Vec<ComponentGap>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentGap {
+    pub name: String,
+    pub current: u16,
+    pub required: u16,
+}
+
+/// One SVN of a TCB level, with the name to report it under
+struct Requirement {
+    name: String,
+    required: u16,
+    current: u16,
+}
+
+/// Matches the platform's TCB against the TCB levels of a TCBInfo.
+///
+/// SGX platforms are matched on the 16 SGX TCB component SVNs and the PCESVN of their PCK
+/// certificate. TDX platforms are additionally matched on the TEE TCB SVN of the TD report,
+/// given as `tee_tcb_svn`.
+pub fn evaluate_tcb(
+    tcb_info: &TcbInfo,
+    pck: &PckExtensions,
+    tee_tcb_svn: Option<[u8; 16]>,
+) -> Result<TcbEvaluation> {
+    let requirements = tcb_info
+        .tcb_levels
+        .iter()
+        .map(|level| requirements(&level.tcb, pck, tee_tcb_svn))
+        .collect::<Result<Vec<_>>>()?;
+    let met = |index: usize| {
+        requirements[index]
+            .iter()
+            .all(|requirement| requirement.current >= requirement.required)
+    };
+    let upgrade = |index: usize| {
+        let level = &tcb_info.tcb_levels[index];
+        TcbUpgrade {
+            index,
+            tcb_status: level.tcb_status,
+            tcb_date: level.tcb_date.clone(),
+            components: requirements[index]
+                .iter()
+                .filter(|requirement| requirement.current < requirement.required)
+                .map(|requirement| ComponentGap {
+                    name: requirement.name.clone(),
+                    current: requirement.current,
+                    required: requirement.required,
+                })
+                .collect(),
+        }
+    };
+
+    let matched = (0..tcb_info.tcb_levels.len()).find(|index| met(*index));
+    let level = matched.map(|index| {
+        let level = &tcb_info.tcb_levels[index];
+        TcbLevelMatch {
+            index,
+            tcb_status: level.tcb_status,
+            tcb_date: level.tcb_date.clone(),
+            advisory_ids: level.advisory_ids.clone().unwrap_or_default(),
+        }
+    });
+
+    let next_index = match matched {
+        Some(0) => None,
+        Some(index) => Some(index - 1),
+        None => tcb_info.tcb_levels.len().checked_sub(1),
+    };
+    let up_to_date_index = tcb_info
+        .tcb_levels
+        .iter()
+        .position(|level| level.tcb_status == TcbStatus::UpToDate)
+        .filter(|index| matched.is_none_or(|matched| matched > *index));
+
+    Ok(TcbEvaluation {
+        fmspc: tcb_info.fmspc.clone(),
+        tcb_info_version: tcb_info.version,
+        tcb_evaluation_data_number: tcb_info.tcb_evaluation_data_number,
+        level,
+        next_level: next_index.map(upgrade),
+        up_to_date_level: up_to_date_index
+            .filter(|index| Some(*index) != next_index)
+            .map(upgrade),
+    })
+}
+
+impl TcbEvaluation {
+    /// The status of the matched TCB level
+    pub fn tcb_status(&self) -> TcbStatus {
+        self.level
+            .as_ref()
+            .map_or(TcbStatus::Unrecognized, |level| level.tcb_status)
+    }
+}
+
+/// Combines the platform TCB status with the QE TCB status the way dcap-rs does:
+/// an out of date QE makes an otherwise up to date platform out of date.
+pub fn converge_tcb_status(platform: TcbStatus, qe: TcbStatus) -> TcbStatus {
+    match (platform, qe) {
+        (TcbStatus::UpToDate | TcbStatus::SwHardeningNeeded, TcbStatus::OutOfDate) => {
+            TcbStatus::OutOfDate
+        }
+        (
+            TcbStatus::ConfigurationNeeded | TcbStatus::ConfigurationAndSwHardeningNeeded,
+            TcbStatus::OutOfDate,
+        ) => TcbStatus::OutOfDateConfigurationNeeded,
+        _ => platform,
+    }
+}
+
+fn requirements(
+    tcb: &Tcb,
+    pck: &PckExtensions,
+    tee_tcb_svn: Option<[u8; 16]>,
+) -> Result<Vec<Requirement>> {
+    let mut requirements = Vec::new();
+
+    let components = tcb.sgx_components();
+    for (i, (component, current)) in components.iter().zip(pck.tcb.sgx_tcb_comp_svns).enumerate() {
+        requirements.push(Requirement {
+            name: component_name("SGX", i, component.component_type.clone()),
+            required: component.svn,
+            current: current as u16,
+        });
+    }
+
+    requirements.push(Requirement {
+        name: String::from("PCESVN"),
+        required: tcb.pce_svn(),
+        current: pck.tcb.pce_svn,
+    });
+
+    if let Some(tee_tcb_svn) = tee_tcb_svn {
+        let components = tcb.tdx_components().ok_or_else(|| {
+            anyhow::Error::msg("TCB level has no TDX TCB components, is this an SGX TCBInfo?")
+        })?;
+        // From TDX 1.5 on, the first two bytes are the TDX module SVN and version,
+        // which are matched against the TDX module identities instead
+        let first = if tee_tcb_svn[1] > 0 { 2 } else { 0 };
+        for (i, (component, current)) in components.iter().zip(tee_tcb_svn).enumerate() {
+            if i < first {
+                continue;
+            }
+            requirements.push(Requirement {
+                name: component_name("TDX", i, component.component_type.clone()),
+                required: component.svn,
+                current: current as u16,
+            });
+        }
+    }
+
+    Ok(requirements)
+}
+
+fn component_name(tee: &str, index: usize, component_type: Option<String>) -> String {
+    let name = format!("{} TCB Comp{:02}", tee, index + 1);
+    match component_type {
+        Some(component_type) if !component_type.is_empty() => {
+            format!("{} ({})", name, component_type)
+        }
+        _ => name,
+    }
+}
+
+impl fmt::Display for TcbEvaluation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "TCB Evaluation")?;
+        field(f, "FMSPC", &self.fmspc)?;
+        field(f, "TCBInfo Version", self.tcb_info_version)?;
+        if let Some(number) = self.tcb_evaluation_data_number {
+            field(f, "TCB Eval Data Number", number)?;
+        }
+        match &self.level {
+            Some(level) => {
+                field(f, "TCB Level", level.index)?;
+                field(f, "TCB Status", level.tcb_status)?;
+                field(f, "TCB Date", &level.tcb_date)?;
+                if !level.advisory_ids.is_empty() {
+                    field(f, "Advisory IDs", level.advisory_ids.join(", "))?;
+                }
+            }
+            None => field(
+                f,
+                "TCB Status",
+                "Unrecognized (no TCB level matches the platform)",
+            )?,
+        }
+        if let Some(next_level) = &self.next_level {
+            writeln!(f, "Next TCB Level")?;
+            write!(f, "{}", next_level)?;
+        }
+        if let Some(up_to_date_level) = &self.up_to_date_level {
+            writeln!(f, "UpToDate TCB Level")?;
+            write!(f, "{}", up_to_date_level)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TcbUpgrade {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        field(f, "TCB Level", self.index)?;
+        field(f, "TCB Status", self.tcb_status)?;
+        field(f, "TCB Date", &self.tcb_date)?;
+        for component in &self.components {
+            field(
+                f,
+                &component.name,
+                format!("{} < {}", component.current, component.required),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{get_pck_certification_data, get_pck_extensions, PckCertificationData};
+    use crate::pem_chain_to_der;
+    use crate::quote::Quote;
+    use crate::signed::Signed;
+
+    const QUOTE_HEX: &str = include_str!("../../../data/quote.hex");
+    const TCB_INFO_V3_TDX: &str = include_str!("../../../data/collaterals/tcb_info_v3_tdx.json");
+
+    /// The TCBInfo, PCK extensions and TEE TCB SVN of the sample TDX quote's platform
+    fn sample() -> (TcbInfo, PckExtensions, Option<[u8; 16]>) {
+        let tcb_info = Signed::<TcbInfo>::from_json(TCB_INFO_V3_TDX.as_bytes())
+            .unwrap()
+            .body;
+        let quote = Quote::from_bytes(&hex::decode(QUOTE_HEX.trim()).unwrap()).unwrap();
+        let pck_cert_chain = match get_pck_certification_data(&quote).unwrap() {
+            PckCertificationData::CertChain(chain) => chain,
+            PckCertificationData::Identifier(_) => panic!("The sample quote embeds its PCK chain"),
+        };
+        let pck = get_pck_extensions(&pem_chain_to_der(&pck_cert_chain).unwrap()[0]).unwrap();
+        (tcb_info, pck, quote.body.tee_tcb_svn())
+    }
+
+    #[test]
+    fn matches_up_to_date_level() {
+        let (tcb_info, mut pck, tee_tcb_svn) = sample();
+        pck.tcb.sgx_tcb_comp_svns[7] = 5;
+
+        let evaluation = evaluate_tcb(&tcb_info, &pck, tee_tcb_svn).unwrap();
+        assert_eq!(evaluation.fmspc, "90C06F000000");
+        assert_eq!(evaluation.tcb_status(), TcbStatus::UpToDate);
+        assert_eq!(evaluation.level.unwrap().index, 0);
+        assert!(evaluation.next_level.is_none());
+        assert!(evaluation.up_to_date_level.is_none());
+    }
+
+    #[test]
+    fn matches_out_of_date_level() {
+        let (tcb_info, pck, tee_tcb_svn) = sample();
+
+        let evaluation = evaluate_tcb(&tcb_info, &pck, tee_tcb_svn).unwrap();
+        assert_eq!(evaluation.tcb_status(), TcbStatus::OutOfDate);
+        let level = evaluation.level.unwrap();
+        assert_eq!(level.index, 1);
+        assert_eq!(level.tcb_date, "2023-08-09T00:00:00Z");
+        assert_eq!(
+            level.advisory_ids,
+            ["INTEL-SA-00960", "INTEL-SA-00982", "INTEL-SA-00986"]
+        );
+
+        // The UpToDate level is the next one, reported once
+        let next_level = evaluation.next_level.unwrap();
+        assert_eq!(next_level.index, 0);
+        assert_eq!(next_level.components.len(), 1);
+        assert_eq!(
+            next_level.components[0].name,
+            "SGX TCB Comp08 (SEAMLDR ACM)"
+        );
+        assert_eq!(
+            (
+                next_level.components[0].current,
+                next_level.components[0].required
+            ),
+            (3, 5)
+        );
+        assert!(evaluation.up_to_date_level.is_none());
+    }
+
+    #[test]
+    fn matches_no_level() {
+        let (tcb_info, mut pck, tee_tcb_svn) = sample();
+        pck.tcb.pce_svn = 10;
+
+        let evaluation = evaluate_tcb(&tcb_info, &pck, tee_tcb_svn).unwrap();
+        assert!(evaluation.level.is_none());
+        assert_eq!(evaluation.tcb_status(), TcbStatus::Unrecognized);
+        let next_level = evaluation.next_level.unwrap();
+        assert_eq!(next_level.index, 2);
+        assert_eq!(next_level.components[0].name, "PCESVN");
+        assert_eq!(evaluation.up_to_date_level.unwrap().index, 0);
+    }
+}